@@ -31,15 +31,6 @@ fn load_points_and_check_challenge(
     let r1_point = decompress_point(r1)?;
     let r2_point = decompress_point(r2)?;
 
-    if t_point.is_small_order()
-        || u_point.is_small_order()
-        || y_point.is_small_order()
-        || r1_point.is_small_order()
-        || r2_point.is_small_order()
-    {
-        return Err(error!(ErrorCode::InvalidPointEncoding));
-    }
-
     let computed_challenge = compute_challenge_bytes(
         &ED25519_BASEPOINT_POINT,
         &y_point,
@@ -73,6 +64,9 @@ pub fn verify_dleq_proof_bytes(
      response: &[u8; 32],
      hashlock: &[u8; 32],
  ) -> Result<bool> {
+    let c = decode_scalar(challenge)?;
+    let s = decode_scalar(response)?;
+
     let Some(points) = load_points_and_check_challenge(
         adaptor_point,
         second_point,
@@ -86,8 +80,6 @@ pub fn verify_dleq_proof_bytes(
         return Ok(false);
     };
 
-    let c = Scalar::from_bytes_mod_order(*challenge);
-    let s = Scalar::from_bytes_mod_order(*response);
     let neg_c = -c;
 
     let lhs_r1 =
@@ -112,6 +104,9 @@ pub fn verify_dleq_proof_part1_bytes(
     response: &[u8; 32],
     hashlock: &[u8; 32],
 ) -> Result<bool> {
+    let c = decode_scalar(challenge)?;
+    let s = decode_scalar(response)?;
+
     let Some(points) = load_points_and_check_challenge(
         adaptor_point,
         second_point,
@@ -125,8 +120,6 @@ pub fn verify_dleq_proof_part1_bytes(
         return Ok(false);
     };
 
-    let c = Scalar::from_bytes_mod_order(*challenge);
-    let s = Scalar::from_bytes_mod_order(*response);
     let neg_c = -c;
 
     let lhs_r1 =
@@ -145,6 +138,9 @@ pub fn verify_dleq_proof_part2_bytes(
     response: &[u8; 32],
     hashlock: &[u8; 32],
 ) -> Result<bool> {
+    let c = decode_scalar(challenge)?;
+    let s = decode_scalar(response)?;
+
     let Some(points) = load_points_and_check_challenge(
         adaptor_point,
         second_point,
@@ -158,8 +154,6 @@ pub fn verify_dleq_proof_part2_bytes(
         return Ok(false);
     };
 
-    let c = Scalar::from_bytes_mod_order(*challenge);
-    let s = Scalar::from_bytes_mod_order(*response);
     let neg_c = -c;
 
     let lhs_r2 =
@@ -168,6 +162,9 @@ pub fn verify_dleq_proof_part2_bytes(
     Ok(lhs_r2 == points.r2_point)
 }
 
+/// Checks the challenge against the transcript without the curve equations.
+/// Every point and the response must still decode canonically, so a lock can
+/// only be stored with encodings the full verifier would accept.
 pub fn validate_dleq_transcript_bytes(
     adaptor_point: &[u8; 32],
     second_point: &[u8; 32],
@@ -175,8 +172,14 @@ pub fn validate_dleq_transcript_bytes(
     r1: &[u8; 32],
     r2: &[u8; 32],
     challenge: &[u8; 32],
+    response: &[u8; 32],
     hashlock: &[u8; 32],
 ) -> Result<bool> {
+    for point in [adaptor_point, second_point, y_point, r1, r2] {
+        decompress_point(point)?;
+    }
+    decode_scalar(response)?;
+
     let mut hasher = Blake2s256::new();
     hasher.update(DLEQ_DOMAIN_TAG);
     hasher.update(ED25519_BASEPOINT_POINT.compress().to_bytes());
@@ -214,15 +217,32 @@ pub fn validate_dleq_transcript_bytes(
     Scalar::from_bytes_mod_order(hash).to_bytes()
  }
  
- fn decompress_point(bytes: &[u8; 32]) -> Result<EdwardsPoint> {
-     CompressedEdwardsY(*bytes)
-         .decompress()
-         .ok_or_else(|| error!(ErrorCode::InvalidPointEncoding))
- }
+/// Decodes a compressed point, accepting only the canonical encoding of a
+/// point in the prime-order subgroup.
+fn decompress_point(bytes: &[u8; 32]) -> Result<EdwardsPoint> {
+    let point = CompressedEdwardsY(*bytes)
+        .decompress()
+        .ok_or_else(|| error!(ErrorCode::InvalidPointEncoding))?;
+    require!(
+        point.compress().to_bytes() == *bytes,
+        ErrorCode::NonCanonicalPoint
+    );
+    require!(!point.is_small_order(), ErrorCode::InvalidPointEncoding);
+    require!(point.is_torsion_free(), ErrorCode::TorsionedPoint);
+    Ok(point)
+}
+
+/// Decodes a scalar, rejecting encodings that are not reduced modulo the
+/// group order.
+pub fn decode_scalar(bytes: &[u8; 32]) -> Result<Scalar> {
+    Option::from(Scalar::from_canonical_bytes(*bytes))
+        .ok_or_else(|| error!(ErrorCode::NonCanonicalScalar))
+}
  
  #[cfg(test)]
  mod tests {
      use super::*;
+    use curve25519_dalek::constants::EIGHT_TORSION;
     use curve25519_dalek::traits::Identity;
     use rand::rngs::OsRng;
     use sha2::Sha256;
@@ -459,4 +479,155 @@ pub fn validate_dleq_transcript_bytes(
 
         assert_ne!(base, tweaked);
     }
+
+    /// Little-endian encoding of the group order l.
+    const GROUP_ORDER: [u8; 32] = [
+        0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde,
+        0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x10,
+    ];
+
+    struct ProofBytes {
+        adaptor_point: [u8; 32],
+        second_point: [u8; 32],
+        y_point: [u8; 32],
+        r1: [u8; 32],
+        r2: [u8; 32],
+        challenge: [u8; 32],
+        response: [u8; 32],
+        hashlock: [u8; 32],
+    }
+
+    fn build_proof() -> ProofBytes {
+        let mut rng = OsRng;
+
+        let t = Scalar::random(&mut rng);
+        let k = Scalar::random(&mut rng);
+        let y = Scalar::random(&mut rng);
+
+        let y_point = ED25519_BASEPOINT_POINT * y;
+        let t_point = ED25519_BASEPOINT_POINT * t;
+        let u_point = y_point * t;
+
+        let r1_point = ED25519_BASEPOINT_POINT * k;
+        let r2_point = y_point * k;
+
+        let hashlock: [u8; 32] = Sha256::digest(t.to_bytes()).into();
+
+        let challenge = compute_challenge_bytes(
+            &ED25519_BASEPOINT_POINT,
+            &y_point,
+            &t_point,
+            &u_point,
+            &r1_point,
+            &r2_point,
+            &hashlock,
+        );
+
+        let c = Scalar::from_bytes_mod_order(challenge);
+        let s = k + c * t;
+
+        ProofBytes {
+            adaptor_point: t_point.compress().to_bytes(),
+            second_point: u_point.compress().to_bytes(),
+            y_point: y_point.compress().to_bytes(),
+            r1: r1_point.compress().to_bytes(),
+            r2: r2_point.compress().to_bytes(),
+            challenge,
+            response: s.to_bytes(),
+            hashlock,
+        }
+    }
+
+    fn verify(proof: &ProofBytes) -> Result<bool> {
+        verify_dleq_proof_bytes(
+            &proof.adaptor_point,
+            &proof.second_point,
+            &proof.y_point,
+            &proof.r1,
+            &proof.r2,
+            &proof.challenge,
+            &proof.response,
+            &proof.hashlock,
+        )
+    }
+
+    fn add_group_order(scalar: [u8; 32]) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        let mut carry = 0u16;
+        for i in 0..32 {
+            let sum = scalar[i] as u16 + GROUP_ORDER[i] as u16 + carry;
+            out[i] = sum as u8;
+            carry = sum >> 8;
+        }
+        out
+    }
+
+    #[test]
+    fn dleq_rejects_non_canonical_response() {
+        let mut proof = build_proof();
+        assert!(verify(&proof).unwrap());
+
+        // s + l reduces to s, so it would verify if scalars were reduced.
+        proof.response = add_group_order(proof.response);
+        let err = verify(&proof).unwrap_err();
+        assert_eq!(err, error!(ErrorCode::NonCanonicalScalar));
+    }
+
+    #[test]
+    fn dleq_rejects_non_canonical_point() {
+        let mut proof = build_proof();
+        let mut encoding = EdwardsPoint::identity().compress().to_bytes();
+        encoding[31] |= 0x80;
+        proof.r1 = encoding;
+
+        let err = verify(&proof).unwrap_err();
+        assert_eq!(err, error!(ErrorCode::NonCanonicalPoint));
+    }
+
+    fn validate_transcript(proof: &ProofBytes) -> Result<bool> {
+        validate_dleq_transcript_bytes(
+            &proof.adaptor_point,
+            &proof.second_point,
+            &proof.y_point,
+            &proof.r1,
+            &proof.r2,
+            &proof.challenge,
+            &proof.response,
+            &proof.hashlock,
+        )
+    }
+
+    #[test]
+    fn transcript_check_decodes_points_and_response() {
+        let proof = build_proof();
+        assert!(validate_transcript(&proof).unwrap());
+
+        let mut unreduced = build_proof();
+        unreduced.response = add_group_order(unreduced.response);
+        assert_eq!(
+            validate_transcript(&unreduced).unwrap_err(),
+            error!(ErrorCode::NonCanonicalScalar)
+        );
+
+        let mut torsioned = build_proof();
+        let t_point = CompressedEdwardsY(torsioned.adaptor_point)
+            .decompress()
+            .unwrap();
+        torsioned.adaptor_point = (t_point + EIGHT_TORSION[1]).compress().to_bytes();
+        assert_eq!(
+            validate_transcript(&torsioned).unwrap_err(),
+            error!(ErrorCode::TorsionedPoint)
+        );
+    }
+
+    #[test]
+    fn dleq_rejects_torsioned_point() {
+        let mut proof = build_proof();
+        let y_point = CompressedEdwardsY(proof.y_point).decompress().unwrap();
+        proof.y_point = (y_point + EIGHT_TORSION[1]).compress().to_bytes();
+
+        let err = verify(&proof).unwrap_err();
+        assert_eq!(err, error!(ErrorCode::TorsionedPoint));
+    }
  }
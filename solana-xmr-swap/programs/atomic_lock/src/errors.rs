@@ -20,4 +20,10 @@
      InvalidTokenMint,
     #[msg("DLEQ proof not verified")]
     DleqNotVerified,
+    #[msg("Non-canonical point encoding")]
+    NonCanonicalPoint,
+    #[msg("Point not in prime-order subgroup")]
+    TorsionedPoint,
+    #[msg("Non-canonical scalar encoding")]
+    NonCanonicalScalar,
  }
@@ -21,40 +21,44 @@ use crate::logging::debug_log;
      amount: u64,
  ) -> Result<()> {
     debug_log("initialize: start");
-     let clock = Clock::get()?;
-    require!(lock_until > clock.unix_timestamp, ErrorCode::InvalidTimelock);
- 
-     require!(
-         ctx.accounts.depositor_token.mint == ctx.accounts.token_mint.key(),
-         ErrorCode::InvalidTokenMint
-     );
- 
-     let dleq_ok = validate_dleq_transcript_bytes(
-         &adaptor_point,
-         &second_point,
-         &y_point,
-         &r1,
-         &r2,
-         &challenge,
-         &hashlock,
-     )?;
- 
-     require!(dleq_ok, ErrorCode::InvalidDleqProof);
- 
-     let lock = &mut ctx.accounts.atomic_lock;
-     lock.depositor = ctx.accounts.depositor.key();
-     lock.hashlock = hashlock;
-     lock.adaptor_point = adaptor_point;
-     lock.second_point = second_point;
-     lock.y_point = y_point;
-     lock.r1 = r1;
-     lock.r2 = r2;
-     lock.challenge = challenge;
-     lock.response = response;
-     lock.lock_until = lock_until;
-     lock.amount = amount;
-     lock.token_mint = ctx.accounts.token_mint.key();
-     lock.vault = ctx.accounts.vault.key();
+    let clock = Clock::get()?;
+    require!(
+        lock_until > clock.unix_timestamp,
+        ErrorCode::InvalidTimelock
+    );
+
+    require!(
+        ctx.accounts.depositor_token.mint == ctx.accounts.token_mint.key(),
+        ErrorCode::InvalidTokenMint
+    );
+
+    let dleq_ok = validate_dleq_transcript_bytes(
+        &adaptor_point,
+        &second_point,
+        &y_point,
+        &r1,
+        &r2,
+        &challenge,
+        &response,
+        &hashlock,
+    )?;
+
+    require!(dleq_ok, ErrorCode::InvalidDleqProof);
+
+    let lock = &mut ctx.accounts.atomic_lock;
+    lock.depositor = ctx.accounts.depositor.key();
+    lock.hashlock = hashlock;
+    lock.adaptor_point = adaptor_point;
+    lock.second_point = second_point;
+    lock.y_point = y_point;
+    lock.r1 = r1;
+    lock.r2 = r2;
+    lock.challenge = challenge;
+    lock.response = response;
+    lock.lock_until = lock_until;
+    lock.amount = amount;
+    lock.token_mint = ctx.accounts.token_mint.key();
+    lock.vault = ctx.accounts.vault.key();
     lock.dleq_verified = false;
      lock.unlocked = false;
      lock.lock_bump = ctx.bumps.atomic_lock;
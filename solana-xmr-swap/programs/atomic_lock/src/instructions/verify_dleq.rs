@@ -21,6 +21,7 @@ pub fn handler(ctx: Context<VerifyDleq>) -> Result<()> {
         &lock.r1,
         &lock.r2,
         &lock.challenge,
+        &lock.response,
         &lock.hashlock,
     )?;
 
@@ -234,6 +234,29 @@ async fn initialize_swap(
     lock_until: i64,
     amount: u64,
 ) -> (Pubkey, Pubkey) {
+    let (tx, lock_pda, vault_pda) = initialize_swap_tx(
+        ctx,
+        proof,
+        depositor,
+        depositor_token,
+        token_mint,
+        lock_until,
+        amount,
+    )
+    .await;
+    ctx.banks_client.process_transaction(tx).await.unwrap();
+    (lock_pda, vault_pda)
+}
+
+async fn initialize_swap_tx(
+    ctx: &mut ProgramTestContext,
+    proof: &DleqProofData,
+    depositor: &Keypair,
+    depositor_token: Pubkey,
+    token_mint: Pubkey,
+    lock_until: i64,
+    amount: u64,
+) -> (Transaction, Pubkey, Pubkey) {
     let top_up_ix = depositor_top_up_ix(ctx, depositor.pubkey()).await;
 
     let (lock_pda, _bump) = Pubkey::find_program_address(
@@ -287,8 +310,50 @@ async fn initialize_swap(
         &[&ctx.payer, depositor],
         ctx.last_blockhash,
     );
-    ctx.banks_client.process_transaction(tx).await.unwrap();
-    (lock_pda, vault_pda)
+    (tx, lock_pda, vault_pda)
+}
+
+/// Sends `initialize` with `proof` from a fresh, funded depositor.
+async fn try_initialize(
+    ctx: &mut ProgramTestContext,
+    proof: &DleqProofData,
+) -> Result<(), BanksClientError> {
+    let depositor = Keypair::new();
+    let mint = create_mint(ctx, &depositor).await;
+    let depositor_token = create_token_account(ctx, &depositor, mint).await;
+    mint_tokens(ctx, mint, &depositor, depositor_token, SWAP_AMOUNT).await;
+
+    let clock: solana_sdk::clock::Clock = ctx.banks_client.get_sysvar().await.unwrap();
+    let (tx, _lock_pda, _vault_pda) = initialize_swap_tx(
+        ctx,
+        proof,
+        &depositor,
+        depositor_token,
+        mint,
+        clock.unix_timestamp + 100,
+        SWAP_AMOUNT,
+    )
+    .await;
+    ctx.banks_client.process_transaction(tx).await
+}
+
+/// Little-endian encoding of the group order l.
+const GROUP_ORDER: [u8; 32] = [
+    0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde, 0x14,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
+];
+
+/// Adds l to a little-endian scalar, giving an unreduced encoding of the
+/// same value.
+fn add_group_order(scalar: [u8; 32]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    let mut carry = 0u16;
+    for i in 0..32 {
+        let sum = scalar[i] as u16 + GROUP_ORDER[i] as u16 + carry;
+        out[i] = sum as u8;
+        carry = sum >> 8;
+    }
+    out
 }
 
 async fn verify_dleq(ctx: &mut ProgramTestContext, lock_pda: Pubkey) {
@@ -529,6 +594,93 @@ async fn invalid_dleq_proof_is_rejected() {
     assert_anchor_error(err, ErrorCode::InvalidDleqProof);
 }
 
+#[tokio::test]
+async fn non_canonical_point_is_rejected() {
+    if !should_run_bpf_tests() {
+        eprintln!("Skipping: set RUN_BPF_TESTS=1 (CI runs by default) and BPF_OUT_DIR to enable.");
+        return;
+    }
+    let mut ctx = program_context().await;
+    let mut proof = build_dleq_proof();
+    // The identity with the sign bit set decodes, but is not how it encodes.
+    let mut encoding = [0u8; 32];
+    encoding[0] = 1;
+    encoding[31] = 0x80;
+    proof.r1 = encoding;
+
+    let err = try_initialize(&mut ctx, &proof).await.unwrap_err();
+    assert_anchor_error(err, ErrorCode::NonCanonicalPoint);
+}
+
+#[tokio::test]
+async fn unreduced_response_is_rejected() {
+    if !should_run_bpf_tests() {
+        eprintln!("Skipping: set RUN_BPF_TESTS=1 (CI runs by default) and BPF_OUT_DIR to enable.");
+        return;
+    }
+    let mut ctx = program_context().await;
+    let mut proof = build_dleq_proof();
+    proof.response = add_group_order(proof.response);
+
+    let err = try_initialize(&mut ctx, &proof).await.unwrap_err();
+    assert_anchor_error(err, ErrorCode::NonCanonicalScalar);
+}
+
+#[tokio::test]
+async fn initialize_and_verify_dleq_fit_the_compute_budget() {
+    if !should_run_bpf_tests() {
+        eprintln!("Skipping: set RUN_BPF_TESTS=1 (CI runs by default) and BPF_OUT_DIR to enable.");
+        return;
+    }
+    let mut ctx = program_context().await;
+    let depositor = Keypair::new();
+    let proof = build_dleq_proof();
+
+    let mint = create_mint(&mut ctx, &depositor).await;
+    let depositor_token = create_token_account(&mut ctx, &depositor, mint).await;
+    mint_tokens(&mut ctx, mint, &depositor, depositor_token, SWAP_AMOUNT).await;
+
+    let clock: solana_sdk::clock::Clock = ctx.banks_client.get_sysvar().await.unwrap();
+    let (tx, lock_pda, _vault_pda) = initialize_swap_tx(
+        &mut ctx,
+        &proof,
+        &depositor,
+        depositor_token,
+        mint,
+        clock.unix_timestamp + 100,
+        SWAP_AMOUNT,
+    )
+    .await;
+    let simulated = ctx
+        .banks_client
+        .simulate_transaction(tx.clone())
+        .await
+        .unwrap();
+    let units = simulated.simulation_details.unwrap().units_consumed;
+    eprintln!("initialize: {units} compute units");
+    assert!(units < COMPUTE_UNIT_LIMIT as u64);
+    ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let ix = solana_sdk::instruction::Instruction {
+        program_id: atomic_lock::ID,
+        accounts: atomic_lock::accounts::VerifyDleq {
+            atomic_lock: lock_pda,
+        }
+        .to_account_metas(None),
+        data: atomic_lock::instruction::VerifyDleq {}.data(),
+    };
+    let tx = Transaction::new_signed_with_payer(
+        &[compute_budget_ix(), ix],
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer],
+        ctx.last_blockhash,
+    );
+    let simulated = ctx.banks_client.simulate_transaction(tx).await.unwrap();
+    let units = simulated.simulation_details.unwrap().units_consumed;
+    eprintln!("verify_dleq: {units} compute units");
+    assert!(units < COMPUTE_UNIT_LIMIT as u64);
+}
+
 #[tokio::test]
 async fn refund_before_timelock_fails() {
     if !should_run_bpf_tests() {
@@ -0,0 +1,159 @@
+use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+use curve25519_dalek::edwards::EdwardsPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
+
+use crate::{
+    compute_challenge_bytes, decode_point, decode_scalar, verify_dleq, DleqInputs, DleqReport,
+    VerifyError,
+};
+
+#[derive(Debug)]
+pub enum BatchFailure {
+    /// The proof decoded but did not verify.
+    Rejected { index: usize, report: DleqReport },
+    /// The proof could not be decoded.
+    Malformed { index: usize, error: VerifyError },
+}
+
+impl BatchFailure {
+    pub fn index(&self) -> usize {
+        match self {
+            BatchFailure::Rejected { index, .. } | BatchFailure::Malformed { index, .. } => *index,
+        }
+    }
+}
+
+struct DecodedProof {
+    index: usize,
+    t_point: EdwardsPoint,
+    u_point: EdwardsPoint,
+    y_point: EdwardsPoint,
+    r1_point: EdwardsPoint,
+    r2_point: EdwardsPoint,
+    c: Scalar,
+    s: Scalar,
+}
+
+/// Verifies many DLEQ proofs with a single multiscalar multiplication.
+///
+/// Each proof's two equations are weighted by independent random 128-bit
+/// scalars and summed. If the combined check fails, every proof is
+/// re-verified individually so the failures can be reported by index.
+pub fn verify_batch(inputs: &[DleqInputs]) -> Result<(), Vec<BatchFailure>> {
+    verify_batch_with_rng(inputs, &mut OsRng)
+}
+
+pub fn verify_batch_with_rng<R: RngCore + CryptoRng>(
+    inputs: &[DleqInputs],
+    rng: &mut R,
+) -> Result<(), Vec<BatchFailure>> {
+    let mut failures = Vec::new();
+    let mut decoded = Vec::with_capacity(inputs.len());
+
+    for (index, input) in inputs.iter().enumerate() {
+        match decode_proof(index, input) {
+            Ok(Some(proof)) => decoded.push(proof),
+            Ok(None) => failures.push(rejected(index, input)),
+            Err(error) => failures.push(BatchFailure::Malformed { index, error }),
+        }
+    }
+
+    if !decoded.is_empty() && !combined_check(&decoded, rng) {
+        for proof in &decoded {
+            let input = &inputs[proof.index];
+            match verify_dleq(input) {
+                Ok((true, _)) => {}
+                Ok((false, report)) => failures.push(BatchFailure::Rejected {
+                    index: proof.index,
+                    report,
+                }),
+                Err(error) => failures.push(BatchFailure::Malformed {
+                    index: proof.index,
+                    error,
+                }),
+            }
+        }
+        failures.sort_by_key(BatchFailure::index);
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures)
+    }
+}
+
+/// Decodes a proof and checks its challenge. Returns `None` when the
+/// challenge does not match the transcript.
+fn decode_proof(index: usize, input: &DleqInputs) -> Result<Option<DecodedProof>, VerifyError> {
+    let proof = DecodedProof {
+        index,
+        t_point: decode_point(&input.adaptor_point, "adaptor_point")?,
+        u_point: decode_point(&input.second_point, "second_point")?,
+        y_point: decode_point(&input.y_point, "y_point")?,
+        r1_point: decode_point(&input.r1, "r1")?,
+        r2_point: decode_point(&input.r2, "r2")?,
+        c: decode_scalar(&input.challenge, "challenge")?,
+        s: decode_scalar(&input.response, "response")?,
+    };
+
+    let computed_challenge = compute_challenge_bytes(
+        &input.y_point,
+        &input.adaptor_point,
+        &input.second_point,
+        &input.r1,
+        &input.r2,
+        &input.hashlock,
+    );
+    if computed_challenge != input.challenge {
+        return Ok(None);
+    }
+    Ok(Some(proof))
+}
+
+fn rejected(index: usize, input: &DleqInputs) -> BatchFailure {
+    match verify_dleq(input) {
+        Ok((_, report)) => BatchFailure::Rejected { index, report },
+        Err(error) => BatchFailure::Malformed { index, error },
+    }
+}
+
+/// Checks sum_i z_i (s_i G - c_i T_i - R1_i) + w_i (s_i Y_i - c_i U_i - R2_i) == 0.
+fn combined_check<R: RngCore + CryptoRng>(proofs: &[DecodedProof], rng: &mut R) -> bool {
+    let mut scalars = Vec::with_capacity(1 + proofs.len() * 5);
+    let mut points = Vec::with_capacity(1 + proofs.len() * 5);
+    let mut g_coefficient = Scalar::ZERO;
+
+    for proof in proofs {
+        let z = random_weight(rng);
+        let w = random_weight(rng);
+
+        g_coefficient += z * proof.s;
+
+        scalars.push(-(z * proof.c));
+        points.push(proof.t_point);
+        scalars.push(-z);
+        points.push(proof.r1_point);
+
+        scalars.push(w * proof.s);
+        points.push(proof.y_point);
+        scalars.push(-(w * proof.c));
+        points.push(proof.u_point);
+        scalars.push(-w);
+        points.push(proof.r2_point);
+    }
+
+    scalars.push(g_coefficient);
+    points.push(ED25519_BASEPOINT_POINT);
+
+    EdwardsPoint::vartime_multiscalar_mul(scalars, points).is_identity()
+}
+
+fn random_weight<R: RngCore + CryptoRng>(rng: &mut R) -> Scalar {
+    let mut bytes = [0u8; 32];
+    rng.fill_bytes(&mut bytes[..16]);
+    Scalar::from_bytes_mod_order(bytes)
+}
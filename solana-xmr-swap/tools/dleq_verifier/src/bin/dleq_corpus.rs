@@ -0,0 +1,48 @@
+use std::fs;
+use std::path::PathBuf;
+
+use dleq_verifier::generate_corpus;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut output = PathBuf::from("test_vectors/dleq_corpus.json");
+    let mut stdout = false;
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => {
+                let value = args.next().ok_or("missing --output value")?;
+                output = PathBuf::from(value);
+            }
+            "--stdout" => stdout = true,
+            _ => {
+                print_usage();
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let corpus = generate_corpus();
+    let json = serde_json::to_string_pretty(&corpus)?;
+
+    if stdout {
+        println!("{json}");
+        return Ok(());
+    }
+
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&output, json)?;
+    println!(
+        "wrote {} corpus vectors: {}",
+        corpus.vectors.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+fn print_usage() {
+    eprintln!("Usage: dleq-corpus [--output <path>] [--stdout]");
+    eprintln!("Default output: test_vectors/dleq_corpus.json");
+}
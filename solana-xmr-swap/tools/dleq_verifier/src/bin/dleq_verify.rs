@@ -1,11 +1,12 @@
 use std::fs;
 use std::path::PathBuf;
 
-use dleq_verifier::{verify_dleq, DleqVector};
+use dleq_verifier::{hex_encode, verify_batch, verify_dleq, BatchFailure, DleqInputs, DleqProof};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut args = std::env::args().skip(1);
     let mut input = None;
+    let mut batch = None;
     let mut verbose = false;
 
     while let Some(arg) = args.next() {
@@ -13,6 +14,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             "--input" => {
                 input = args.next().map(PathBuf::from);
             }
+            "--batch" => {
+                batch = args.next().map(PathBuf::from);
+            }
             "--verbose" => verbose = true,
             _ => {
                 eprintln!("Unknown arg: {arg}");
@@ -22,14 +26,21 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    if let Some(path) = batch {
+        return run_batch(&path);
+    }
+
     let input = input.unwrap_or_else(|| PathBuf::from("test_vectors/dleq.json"));
     let raw = fs::read_to_string(&input)?;
-    let vector: DleqVector = serde_json::from_str(&raw)?;
-    let inputs = vector.decode()?;
+    let proof = DleqProof::from_json(&raw)?;
+    let inputs = DleqInputs::from(&proof);
 
     let (ok, report) = verify_dleq(&inputs)?;
     if verbose {
-        println!("computed_challenge: {}", hex_encode(report.computed_challenge));
+        println!(
+            "computed_challenge: {}",
+            hex_encode(&report.computed_challenge)
+        );
         println!("challenge_matches: {}", report.challenge_matches);
         println!("lhs_r1_matches: {}", report.lhs_r1_matches);
         println!("lhs_r2_matches: {}", report.lhs_r2_matches);
@@ -44,17 +55,59 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 }
 
-fn print_usage() {
-    eprintln!("Usage: dleq-verify [--input <path>] [--verbose]");
-    eprintln!("Default input: test_vectors/dleq.json");
-}
+fn run_batch(path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+    let raw = fs::read_to_string(path)?;
+    let mut line_numbers = Vec::new();
+    let mut inputs = Vec::new();
+    let mut failed = 0usize;
 
-fn hex_encode(bytes: [u8; 32]) -> String {
-    const HEX: &[u8; 16] = b"0123456789abcdef";
-    let mut out = String::with_capacity(64);
-    for byte in bytes {
-        out.push(HEX[(byte >> 4) as usize] as char);
-        out.push(HEX[(byte & 0x0f) as usize] as char);
+    for (line_index, line) in raw.lines().enumerate() {
+        let line_number = line_index + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let decoded = DleqProof::from_json(line).map(|proof| DleqInputs::from(&proof));
+        match decoded {
+            Ok(decoded) => {
+                line_numbers.push(line_number);
+                inputs.push(decoded);
+            }
+            Err(err) => {
+                eprintln!("line {line_number}: invalid vector: {err}");
+                failed += 1;
+            }
+        }
     }
-    out
+
+    let mut rejected = 0usize;
+    if let Err(failures) = verify_batch(&inputs) {
+        for failure in &failures {
+            let line_number = line_numbers[failure.index()];
+            match failure {
+                BatchFailure::Rejected { report, .. } => eprintln!(
+                    "line {line_number}: FAILED (challenge_matches={}, lhs_r1={}, lhs_r2={})",
+                    report.challenge_matches, report.lhs_r1_matches, report.lhs_r2_matches
+                ),
+                BatchFailure::Malformed { error, .. } => {
+                    eprintln!("line {line_number}: FAILED ({error})")
+                }
+            }
+        }
+        rejected = failures.len();
+    }
+
+    let verified = inputs.len() - rejected;
+    let failed = failed + rejected;
+    if failed == 0 {
+        println!("DLEQ batch verification: OK ({verified} proofs)");
+        Ok(())
+    } else {
+        eprintln!("DLEQ batch verification: FAILED ({verified} ok, {failed} failed)");
+        std::process::exit(1);
+    }
+}
+
+fn print_usage() {
+    eprintln!("Usage: dleq-verify [--input <path> | --batch <jsonl>] [--verbose]");
+    eprintln!("Default input: test_vectors/dleq.json");
 }
@@ -0,0 +1,351 @@
+use curve25519_dalek::constants::{ED25519_BASEPOINT_POINT, EIGHT_TORSION};
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::{compute_challenge_bytes, verify_dleq, DleqInputs, DleqProof, VerifyError};
+
+/// Current version of the corpus document.
+pub const CORPUS_FORMAT_VERSION: u32 = 1;
+
+/// Little-endian encoding of the group order `l`.
+const GROUP_ORDER: [u8; 32] = [
+    0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde, 0x14,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
+];
+
+/// Why a verifier rejected a proof.
+///
+/// Classes are shared by every verifier implementation, so they are only as
+/// fine-grained as all of them can report: a point that fails to decompress
+/// and a small-order point are both `InvalidPoint`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    InvalidPoint,
+    NonCanonicalPoint,
+    TorsionedPoint,
+    NonCanonicalScalar,
+    ChallengeMismatch,
+    EquationMismatch,
+    Malformed,
+}
+
+impl ErrorClass {
+    pub fn from_verify_error(err: &VerifyError) -> Self {
+        match err {
+            VerifyError::InvalidPoint { .. } | VerifyError::SmallOrderPoint { .. } => {
+                ErrorClass::InvalidPoint
+            }
+            VerifyError::NonCanonicalPoint { .. } => ErrorClass::NonCanonicalPoint,
+            VerifyError::TorsionedPoint { .. } => ErrorClass::TorsionedPoint,
+            VerifyError::NonCanonicalScalar { .. } => ErrorClass::NonCanonicalScalar,
+            VerifyError::InvalidHex { .. }
+            | VerifyError::InvalidLength { .. }
+            | VerifyError::InvalidJson { .. }
+            | VerifyError::UnsupportedVersion { .. } => ErrorClass::Malformed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Accept,
+    Reject,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CorpusVector {
+    pub name: String,
+    pub description: String,
+    pub outcome: Outcome,
+    pub error_class: Option<ErrorClass>,
+    pub proof: DleqProof,
+}
+
+impl CorpusVector {
+    /// The expected verifier result, in the shape returned by [`classify`].
+    pub fn expected(&self) -> Result<(), ErrorClass> {
+        match (self.outcome, self.error_class) {
+            (Outcome::Accept, _) => Ok(()),
+            (Outcome::Reject, Some(class)) => Err(class),
+            (Outcome::Reject, None) => Err(ErrorClass::Malformed),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Corpus {
+    pub version: u32,
+    pub vectors: Vec<CorpusVector>,
+}
+
+/// Runs [`verify_dleq`] and reduces its result to an [`ErrorClass`].
+pub fn classify(inputs: &DleqInputs) -> Result<(), ErrorClass> {
+    match verify_dleq(inputs) {
+        Ok((true, _)) => Ok(()),
+        Ok((false, report)) if !report.challenge_matches => Err(ErrorClass::ChallengeMismatch),
+        Ok((false, _)) => Err(ErrorClass::EquationMismatch),
+        Err(err) => Err(ErrorClass::from_verify_error(&err)),
+    }
+}
+
+/// Secrets and nonces behind one corpus proof, derived from a label so the
+/// corpus is reproducible byte for byte.
+struct Witness {
+    t: Scalar,
+    k: Scalar,
+    y_point: EdwardsPoint,
+    hashlock: [u8; 32],
+}
+
+impl Witness {
+    fn new(label: &str) -> Self {
+        let t = labelled_scalar(label, "t");
+        Self {
+            t,
+            k: labelled_scalar(label, "k"),
+            y_point: ED25519_BASEPOINT_POINT * labelled_scalar(label, "y"),
+            hashlock: Sha256::digest(t.to_bytes()).into(),
+        }
+    }
+
+    fn prove(&self) -> DleqProof {
+        self.prove_over(ED25519_BASEPOINT_POINT, self.y_point, self.hashlock)
+    }
+
+    /// Proves with `first` and `second` standing in for `G` and `Y`; the
+    /// transcript always hashes the real basepoint.
+    fn prove_over(
+        &self,
+        first: EdwardsPoint,
+        second: EdwardsPoint,
+        hashlock: [u8; 32],
+    ) -> DleqProof {
+        let adaptor_point = (first * self.t).compress().to_bytes();
+        let second_point = (second * self.t).compress().to_bytes();
+        let r1 = (first * self.k).compress().to_bytes();
+        let r2 = (second * self.k).compress().to_bytes();
+        let y_point = self.y_point.compress().to_bytes();
+        let challenge =
+            compute_challenge_bytes(&y_point, &adaptor_point, &second_point, &r1, &r2, &hashlock);
+        let response = self.k + Scalar::from_bytes_mod_order(challenge) * self.t;
+        DleqProof {
+            hashlock: hashlock.into(),
+            adaptor_point: adaptor_point.into(),
+            second_point: second_point.into(),
+            y_point: y_point.into(),
+            r1: r1.into(),
+            r2: r2.into(),
+            challenge: challenge.into(),
+            response: response.to_bytes().into(),
+        }
+    }
+}
+
+/// Builds the labelled corpus: valid proofs plus one vector per class of
+/// malformed or forged proof.
+pub fn generate_corpus() -> Corpus {
+    let mut vectors = Vec::new();
+    let mut push = |name: &str, description: &str, class: Option<ErrorClass>, proof| {
+        vectors.push(CorpusVector {
+            name: name.to_string(),
+            description: description.to_string(),
+            outcome: if class.is_some() {
+                Outcome::Reject
+            } else {
+                Outcome::Accept
+            },
+            error_class: class,
+            proof,
+        });
+    };
+
+    for index in 0..3 {
+        let name = format!("valid_{index}");
+        push(
+            &name,
+            "honestly generated proof",
+            None,
+            Witness::new(&name).prove(),
+        );
+    }
+
+    let witness = Witness::new("negative");
+    let valid = witness.prove();
+    let identity = EdwardsPoint::identity().compress().to_bytes();
+
+    let mut proof = valid.clone();
+    proof.challenge.0[0] ^= 0x01;
+    push(
+        "wrong_challenge",
+        "challenge does not match the transcript hash",
+        Some(ErrorClass::ChallengeMismatch),
+        proof,
+    );
+
+    let mut proof = valid.clone();
+    proof.response = (Scalar::from_bytes_mod_order(*valid.response) + Scalar::ONE)
+        .to_bytes()
+        .into();
+    push(
+        "tampered_response",
+        "response is s + 1; transcript still matches",
+        Some(ErrorClass::EquationMismatch),
+        proof,
+    );
+
+    let mut proof = valid.clone();
+    proof.hashlock.0[0] ^= 0x11;
+    push(
+        "wrong_hashlock",
+        "hashlock altered after proving",
+        Some(ErrorClass::ChallengeMismatch),
+        proof,
+    );
+
+    let unbound_hashlock: [u8; 32] = Sha256::digest(b"not the secret").into();
+    push(
+        "hashlock_not_preimage_of_secret",
+        "consistent proof over a hashlock that is not SHA-256(t); DLEQ alone cannot \
+         detect this, the preimage check in verify_and_unlock does",
+        None,
+        witness.prove_over(ED25519_BASEPOINT_POINT, witness.y_point, unbound_hashlock),
+    );
+
+    push(
+        "swapped_bases",
+        "proof computed with G and Y swapped, challenge recomputed",
+        Some(ErrorClass::EquationMismatch),
+        witness.prove_over(witness.y_point, ED25519_BASEPOINT_POINT, witness.hashlock),
+    );
+
+    let mut proof = valid.clone();
+    proof.adaptor_point = identity.into();
+    push(
+        "identity_adaptor_point",
+        "adaptor point is the identity",
+        Some(ErrorClass::InvalidPoint),
+        proof,
+    );
+
+    let mut proof = valid.clone();
+    proof.r1 = identity.into();
+    push(
+        "identity_r1",
+        "commitment r1 is the identity",
+        Some(ErrorClass::InvalidPoint),
+        proof,
+    );
+
+    let mut proof = valid.clone();
+    proof.y_point = EIGHT_TORSION[2].compress().to_bytes().into();
+    push(
+        "small_order_y_point",
+        "y_point has order 4",
+        Some(ErrorClass::InvalidPoint),
+        proof,
+    );
+
+    let mut proof = valid.clone();
+    proof.second_point = undecodable_point().into();
+    push(
+        "undecodable_second_point",
+        "second_point is not a curve point",
+        Some(ErrorClass::InvalidPoint),
+        proof,
+    );
+
+    let mut proof = valid.clone();
+    proof.adaptor_point = (ED25519_BASEPOINT_POINT * witness.t + EIGHT_TORSION[1])
+        .compress()
+        .to_bytes()
+        .into();
+    push(
+        "torsioned_adaptor_point",
+        "adaptor point carries an order-8 component",
+        Some(ErrorClass::TorsionedPoint),
+        proof,
+    );
+
+    let mut proof = valid.clone();
+    proof.r2 = identity.into();
+    proof.r2.0[31] |= 0x80;
+    push(
+        "non_canonical_point_sign_bit",
+        "r2 is the identity with the x sign bit set",
+        Some(ErrorClass::NonCanonicalPoint),
+        proof,
+    );
+
+    let mut proof = valid.clone();
+    // p + 1 reduces to y = 1, the identity, but is not its canonical encoding.
+    let mut overflow = [0xff; 32];
+    overflow[0] = 0xee;
+    overflow[31] = 0x7f;
+    proof.r1 = overflow.into();
+    push(
+        "non_canonical_point_y_overflow",
+        "r1 encodes y = p + 1",
+        Some(ErrorClass::NonCanonicalPoint),
+        proof,
+    );
+
+    let mut proof = valid.clone();
+    proof.response = add_group_order(*valid.response).into();
+    push(
+        "non_canonical_response",
+        "response is s + l",
+        Some(ErrorClass::NonCanonicalScalar),
+        proof,
+    );
+
+    let mut proof = valid;
+    proof.challenge = add_group_order(*proof.challenge).into();
+    push(
+        "non_canonical_challenge",
+        "challenge is c + l",
+        Some(ErrorClass::NonCanonicalScalar),
+        proof,
+    );
+
+    Corpus {
+        version: CORPUS_FORMAT_VERSION,
+        vectors,
+    }
+}
+
+fn labelled_scalar(label: &str, role: &str) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(b"DLEQ/corpus/v1");
+    hasher.update(label.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(role.as_bytes());
+    Scalar::from_bytes_mod_order_wide(&hasher.finalize().into())
+}
+
+/// The first small y whose encoding does not decompress.
+fn undecodable_point() -> [u8; 32] {
+    (2u8..)
+        .map(|y| {
+            let mut bytes = [0u8; 32];
+            bytes[0] = y;
+            bytes
+        })
+        .find(|bytes| CompressedEdwardsY(*bytes).decompress().is_none())
+        .expect("some small y is off the curve")
+}
+
+fn add_group_order(bytes: [u8; 32]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    let mut carry = 0u16;
+    for i in 0..32 {
+        let sum = bytes[i] as u16 + GROUP_ORDER[i] as u16 + carry;
+        out[i] = sum as u8;
+        carry = sum >> 8;
+    }
+    out
+}
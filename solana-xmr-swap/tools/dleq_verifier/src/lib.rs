@@ -4,6 +4,18 @@ use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
 use curve25519_dalek::scalar::Scalar;
 use serde::Deserialize;
 
+mod batch;
+mod corpus;
+mod proof;
+mod prover;
+
+pub use batch::{verify_batch, verify_batch_with_rng, BatchFailure};
+pub use corpus::{
+    classify, generate_corpus, Corpus, CorpusVector, ErrorClass, Outcome, CORPUS_FORMAT_VERSION,
+};
+pub use proof::{DleqProof, Hex32, PROOF_FORMAT_VERSION, PROOF_WIRE_LEN};
+pub use prover::{prove, prove_with_aux, ProveError};
+
 const DLEQ_DOMAIN_TAG: &[u8; 4] = b"DLEQ";
 
 #[derive(Debug, Deserialize)]
@@ -40,10 +52,36 @@ pub struct DleqReport {
 
 #[derive(Debug)]
 pub enum VerifyError {
-    InvalidHex { field: &'static str, reason: String },
-    InvalidLength { field: &'static str, expected: usize, got: usize },
-    InvalidPoint { field: &'static str },
-    SmallOrderPoint { field: &'static str },
+    InvalidHex {
+        field: &'static str,
+        reason: String,
+    },
+    InvalidLength {
+        field: &'static str,
+        expected: usize,
+        got: usize,
+    },
+    InvalidPoint {
+        field: &'static str,
+    },
+    SmallOrderPoint {
+        field: &'static str,
+    },
+    NonCanonicalPoint {
+        field: &'static str,
+    },
+    TorsionedPoint {
+        field: &'static str,
+    },
+    NonCanonicalScalar {
+        field: &'static str,
+    },
+    InvalidJson {
+        reason: String,
+    },
+    UnsupportedVersion {
+        version: u64,
+    },
 }
 
 impl std::fmt::Display for VerifyError {
@@ -57,6 +95,19 @@ impl std::fmt::Display for VerifyError {
             }
             VerifyError::InvalidPoint { field } => write!(f, "invalid point encoding: {field}"),
             VerifyError::SmallOrderPoint { field } => write!(f, "small-order point: {field}"),
+            VerifyError::NonCanonicalPoint { field } => {
+                write!(f, "non-canonical point encoding: {field}")
+            }
+            VerifyError::TorsionedPoint { field } => {
+                write!(f, "point not in prime-order subgroup: {field}")
+            }
+            VerifyError::NonCanonicalScalar { field } => {
+                write!(f, "non-canonical scalar encoding: {field}")
+            }
+            VerifyError::InvalidJson { reason } => write!(f, "invalid proof JSON: {reason}"),
+            VerifyError::UnsupportedVersion { version } => {
+                write!(f, "unsupported proof format version: {version}")
+            }
         }
     }
 }
@@ -103,6 +154,15 @@ pub fn compute_challenge_bytes(
 }
 
 pub fn verify_dleq(inputs: &DleqInputs) -> Result<(bool, DleqReport), VerifyError> {
+    let t_point = decode_point(&inputs.adaptor_point, "adaptor_point")?;
+    let u_point = decode_point(&inputs.second_point, "second_point")?;
+    let y_point = decode_point(&inputs.y_point, "y_point")?;
+    let r1_point = decode_point(&inputs.r1, "r1")?;
+    let r2_point = decode_point(&inputs.r2, "r2")?;
+
+    let c = decode_scalar(&inputs.challenge, "challenge")?;
+    let s = decode_scalar(&inputs.response, "response")?;
+
     let computed_challenge = compute_challenge_bytes(
         &inputs.y_point,
         &inputs.adaptor_point,
@@ -125,25 +185,10 @@ pub fn verify_dleq(inputs: &DleqInputs) -> Result<(bool, DleqReport), VerifyErro
         ));
     }
 
-    let t_point = decompress_point(&inputs.adaptor_point, "adaptor_point")?;
-    let u_point = decompress_point(&inputs.second_point, "second_point")?;
-    let y_point = decompress_point(&inputs.y_point, "y_point")?;
-    let r1_point = decompress_point(&inputs.r1, "r1")?;
-    let r2_point = decompress_point(&inputs.r2, "r2")?;
-
-    ensure_not_small_order(&t_point, "adaptor_point")?;
-    ensure_not_small_order(&u_point, "second_point")?;
-    ensure_not_small_order(&y_point, "y_point")?;
-    ensure_not_small_order(&r1_point, "r1")?;
-    ensure_not_small_order(&r2_point, "r2")?;
-
-    let c = Scalar::from_bytes_mod_order(inputs.challenge);
-    let s = Scalar::from_bytes_mod_order(inputs.response);
-
-    let s_g = &ED25519_BASEPOINT_POINT * &s;
-    let s_y = &y_point * &s;
-    let c_t = &t_point * &c;
-    let c_u = &u_point * &c;
+    let s_g = ED25519_BASEPOINT_POINT * s;
+    let s_y = y_point * s;
+    let c_t = t_point * c;
+    let c_u = u_point * c;
 
     let r1_check = r1_point + c_t;
     let r2_check = r2_point + c_u;
@@ -162,43 +207,64 @@ pub fn verify_dleq(inputs: &DleqInputs) -> Result<(bool, DleqReport), VerifyErro
     ))
 }
 
-fn decompress_point(bytes: &[u8; 32], field: &'static str) -> Result<EdwardsPoint, VerifyError> {
-    CompressedEdwardsY(*bytes)
+/// Decodes a compressed point, accepting only the canonical encoding of a
+/// point in the prime-order subgroup.
+pub fn decode_point(bytes: &[u8; 32], field: &'static str) -> Result<EdwardsPoint, VerifyError> {
+    let point = CompressedEdwardsY(*bytes)
         .decompress()
-        .ok_or(VerifyError::InvalidPoint { field })
-}
-
-fn ensure_not_small_order(point: &EdwardsPoint, field: &'static str) -> Result<(), VerifyError> {
+        .ok_or(VerifyError::InvalidPoint { field })?;
+    // decompress() accepts y >= p and a set sign bit on x = 0; re-encoding
+    // exposes both.
+    if point.compress().to_bytes() != *bytes {
+        return Err(VerifyError::NonCanonicalPoint { field });
+    }
     if point.is_small_order() {
         return Err(VerifyError::SmallOrderPoint { field });
     }
-    Ok(())
+    if !point.is_torsion_free() {
+        return Err(VerifyError::TorsionedPoint { field });
+    }
+    Ok(point)
+}
+
+/// Decodes a scalar, rejecting encodings that are not reduced modulo the
+/// group order.
+pub fn decode_scalar(bytes: &[u8; 32], field: &'static str) -> Result<Scalar, VerifyError> {
+    Option::from(Scalar::from_canonical_bytes(*bytes))
+        .ok_or(VerifyError::NonCanonicalScalar { field })
 }
 
-fn decode_hex_32(value: &str, field: &'static str) -> Result<[u8; 32], VerifyError> {
-    let mut out = [0u8; 32];
+/// Decodes exactly 32 bytes of hex, naming `field` in the error.
+pub fn decode_hex_32(value: &str, field: &'static str) -> Result<[u8; 32], VerifyError> {
+    decode_hex_array(value, field)
+}
+
+/// Decodes exactly `N` bytes of hex, naming `field` in the error.
+pub fn decode_hex_array<const N: usize>(
+    value: &str,
+    field: &'static str,
+) -> Result<[u8; N], VerifyError> {
     let bytes = decode_hex(value, field)?;
-    if bytes.len() != 32 {
-        return Err(VerifyError::InvalidLength {
+    bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| VerifyError::InvalidLength {
             field,
-            expected: 32,
+            expected: N,
             got: bytes.len(),
-        });
-    }
-    out.copy_from_slice(&bytes);
-    Ok(out)
+        })
 }
 
-fn decode_hex(value: &str, field: &'static str) -> Result<Vec<u8>, VerifyError> {
-    if value.len() % 2 != 0 {
+/// Decodes hex of any even length; both cases are accepted.
+pub fn decode_hex(value: &str, field: &'static str) -> Result<Vec<u8>, VerifyError> {
+    if !value.len().is_multiple_of(2) {
         return Err(VerifyError::InvalidHex {
             field,
             reason: "odd length".to_string(),
         });
     }
     let mut out = Vec::with_capacity(value.len() / 2);
-    let mut iter = value.as_bytes().chunks_exact(2);
-    while let Some(pair) = iter.next() {
+    for pair in value.as_bytes().chunks_exact(2) {
         let hi = from_hex_digit(pair[0], field)?;
         let lo = from_hex_digit(pair[1], field)?;
         out.push((hi << 4) | lo);
@@ -217,3 +283,14 @@ fn from_hex_digit(byte: u8, field: &'static str) -> Result<u8, VerifyError> {
         }),
     }
 }
+
+/// Lowercase hex, the encoding every tool prints.
+pub fn hex_encode(bytes: &[u8]) -> String {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        out.push(HEX[(byte >> 4) as usize] as char);
+        out.push(HEX[(byte & 0x0f) as usize] as char);
+    }
+    out
+}
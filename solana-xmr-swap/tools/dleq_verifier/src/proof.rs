@@ -0,0 +1,216 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{decode_hex_32, hex_encode, DleqInputs, DleqVector, VerifyError};
+
+/// Current version of the JSON proof document.
+pub const PROOF_FORMAT_VERSION: u32 = 1;
+
+/// Length of the binary proof encoding.
+pub const PROOF_WIRE_LEN: usize = 32 * 8;
+
+/// 32 bytes that serialize as a lowercase hex string.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Hex32(pub [u8; 32]);
+
+impl Hex32 {
+    pub fn to_bytes(self) -> [u8; 32] {
+        self.0
+    }
+}
+
+impl std::ops::Deref for Hex32 {
+    type Target = [u8; 32];
+
+    fn deref(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl From<[u8; 32]> for Hex32 {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl PartialEq<[u8; 32]> for Hex32 {
+    fn eq(&self, other: &[u8; 32]) -> bool {
+        &self.0 == other
+    }
+}
+
+impl fmt::Display for Hex32 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex_encode(&self.0))
+    }
+}
+
+impl fmt::Debug for Hex32 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Hex32({self})")
+    }
+}
+
+impl FromStr for Hex32 {
+    type Err = VerifyError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        decode_hex_32(value, "hex32").map(Self)
+    }
+}
+
+impl Serialize for Hex32 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Hex32 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// A DLEQ proof that `T = t·G` and `U = t·Y` share the discrete log `t`,
+/// bound to `hashlock = SHA-256(t)`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DleqProof {
+    pub hashlock: Hex32,
+    pub adaptor_point: Hex32,
+    pub second_point: Hex32,
+    pub y_point: Hex32,
+    pub r1: Hex32,
+    pub r2: Hex32,
+    pub challenge: Hex32,
+    pub response: Hex32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VersionedProof {
+    version: u32,
+    #[serde(flatten)]
+    proof: DleqProof,
+}
+
+impl DleqProof {
+    /// Encodes the proof in the order and layout of the on-chain
+    /// `initialize` arguments (Borsh encodes `[u8; 32]` as raw bytes).
+    pub fn to_wire_bytes(&self) -> [u8; PROOF_WIRE_LEN] {
+        let mut out = [0u8; PROOF_WIRE_LEN];
+        for (chunk, field) in out.chunks_exact_mut(32).zip(self.fields()) {
+            chunk.copy_from_slice(&field.0);
+        }
+        out
+    }
+
+    pub fn from_wire_bytes(bytes: &[u8]) -> Result<Self, VerifyError> {
+        if bytes.len() != PROOF_WIRE_LEN {
+            return Err(VerifyError::InvalidLength {
+                field: "proof",
+                expected: PROOF_WIRE_LEN,
+                got: bytes.len(),
+            });
+        }
+        let mut fields = [Hex32::default(); 8];
+        for (field, chunk) in fields.iter_mut().zip(bytes.chunks_exact(32)) {
+            field.0.copy_from_slice(chunk);
+        }
+        let [hashlock, adaptor_point, second_point, y_point, r1, r2, challenge, response] = fields;
+        Ok(Self {
+            hashlock,
+            adaptor_point,
+            second_point,
+            y_point,
+            r1,
+            r2,
+            challenge,
+            response,
+        })
+    }
+
+    /// Serializes the proof as a versioned JSON document.
+    pub fn to_json(&self) -> Result<String, VerifyError> {
+        let document = VersionedProof {
+            version: PROOF_FORMAT_VERSION,
+            proof: self.clone(),
+        };
+        serde_json::to_string_pretty(&document).map_err(|err| VerifyError::InvalidJson {
+            reason: err.to_string(),
+        })
+    }
+
+    /// Parses a versioned JSON document, or a legacy unversioned vector
+    /// such as `test_vectors/dleq.json`.
+    pub fn from_json(raw: &str) -> Result<Self, VerifyError> {
+        let value: serde_json::Value = serde_json::from_str(raw).map_err(invalid_json)?;
+        match value.get("version") {
+            None => {
+                let vector: DleqVector = serde_json::from_value(value).map_err(invalid_json)?;
+                Ok(Self::from(&vector.decode()?))
+            }
+            Some(version) => {
+                let version = version.as_u64().ok_or_else(|| VerifyError::InvalidJson {
+                    reason: "version must be an unsigned integer".to_string(),
+                })?;
+                if version != u64::from(PROOF_FORMAT_VERSION) {
+                    return Err(VerifyError::UnsupportedVersion { version });
+                }
+                let document: VersionedProof =
+                    serde_json::from_value(value).map_err(invalid_json)?;
+                Ok(document.proof)
+            }
+        }
+    }
+
+    fn fields(&self) -> [Hex32; 8] {
+        [
+            self.hashlock,
+            self.adaptor_point,
+            self.second_point,
+            self.y_point,
+            self.r1,
+            self.r2,
+            self.challenge,
+            self.response,
+        ]
+    }
+}
+
+impl From<&DleqProof> for DleqInputs {
+    fn from(proof: &DleqProof) -> Self {
+        DleqInputs {
+            adaptor_point: proof.adaptor_point.0,
+            second_point: proof.second_point.0,
+            y_point: proof.y_point.0,
+            r1: proof.r1.0,
+            r2: proof.r2.0,
+            challenge: proof.challenge.0,
+            response: proof.response.0,
+            hashlock: proof.hashlock.0,
+        }
+    }
+}
+
+impl From<&DleqInputs> for DleqProof {
+    fn from(inputs: &DleqInputs) -> Self {
+        DleqProof {
+            hashlock: inputs.hashlock.into(),
+            adaptor_point: inputs.adaptor_point.into(),
+            second_point: inputs.second_point.into(),
+            y_point: inputs.y_point.into(),
+            r1: inputs.r1.into(),
+            r2: inputs.r2.into(),
+            challenge: inputs.challenge.into(),
+            response: inputs.response.into(),
+        }
+    }
+}
+
+fn invalid_json(err: serde_json::Error) -> VerifyError {
+    VerifyError::InvalidJson {
+        reason: err.to_string(),
+    }
+}
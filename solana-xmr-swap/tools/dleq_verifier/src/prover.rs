@@ -0,0 +1,117 @@
+use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+use curve25519_dalek::scalar::Scalar;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256, Sha512};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::{compute_challenge_bytes, decode_point, decode_scalar, DleqProof, VerifyError};
+
+const NONCE_DOMAIN_TAG: &[u8] = b"DLEQ/nonce/v1";
+
+#[derive(Debug)]
+pub enum ProveError {
+    NonCanonicalSecret,
+    ZeroSecret,
+    InvalidYPoint(VerifyError),
+}
+
+impl std::fmt::Display for ProveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProveError::NonCanonicalSecret => write!(f, "secret is not a canonical scalar"),
+            ProveError::ZeroSecret => write!(f, "secret must be non-zero"),
+            ProveError::InvalidYPoint(err) => write!(f, "invalid y_point: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ProveError {}
+
+/// Proves knowledge of `secret` as the shared discrete log of the adaptor
+/// point over the basepoint and over `y_point`.
+///
+/// `context` is mixed into nonce derivation only (e.g. a swap id); it is not
+/// part of the challenge transcript, which must match the on-chain rule.
+/// Fresh randomness is mixed in as well, so repeated calls yield different
+/// but equally valid proofs.
+pub fn prove(
+    secret: &[u8; 32],
+    y_point: &[u8; 32],
+    context: &[u8],
+) -> Result<DleqProof, ProveError> {
+    let mut aux = Zeroizing::new([0u8; 32]);
+    OsRng.fill_bytes(aux.as_mut());
+    prove_with_aux(secret, y_point, context, &aux)
+}
+
+/// Deterministic variant of [`prove`]: the same inputs and `aux` always
+/// yield the same proof. `aux` should be fresh randomness in production.
+pub fn prove_with_aux(
+    secret: &[u8; 32],
+    y_point: &[u8; 32],
+    context: &[u8],
+    aux: &[u8; 32],
+) -> Result<DleqProof, ProveError> {
+    let t = Zeroizing::new(
+        decode_scalar(secret, "secret").map_err(|_| ProveError::NonCanonicalSecret)?,
+    );
+    if *t == Scalar::ZERO {
+        return Err(ProveError::ZeroSecret);
+    }
+    let y = decode_point(y_point, "y_point").map_err(ProveError::InvalidYPoint)?;
+
+    let t_point = ED25519_BASEPOINT_POINT * *t;
+    let u_point = y * *t;
+    let adaptor_point = t_point.compress().to_bytes();
+    let second_point = u_point.compress().to_bytes();
+    let hashlock: [u8; 32] = Sha256::digest(secret).into();
+
+    let k = derive_nonce(
+        secret,
+        &[y_point, &adaptor_point, &second_point, &hashlock],
+        context,
+        aux,
+    );
+    let r1 = (ED25519_BASEPOINT_POINT * *k).compress().to_bytes();
+    let r2 = (y * *k).compress().to_bytes();
+
+    let challenge =
+        compute_challenge_bytes(y_point, &adaptor_point, &second_point, &r1, &r2, &hashlock);
+    let c = Scalar::from_bytes_mod_order(challenge);
+    let response = (*k + c * *t).to_bytes();
+
+    Ok(DleqProof {
+        hashlock: hashlock.into(),
+        adaptor_point: adaptor_point.into(),
+        second_point: second_point.into(),
+        y_point: (*y_point).into(),
+        r1: r1.into(),
+        r2: r2.into(),
+        challenge: challenge.into(),
+        response: response.into(),
+    })
+}
+
+/// Derives the nonce as `H(tag || secret || transcript || context || aux)`
+/// reduced from 512 bits, in the spirit of RFC 6979 with added randomness.
+fn derive_nonce(
+    secret: &[u8; 32],
+    transcript: &[&[u8; 32]],
+    context: &[u8],
+    aux: &[u8; 32],
+) -> Zeroizing<Scalar> {
+    let mut hasher = Sha512::new();
+    hasher.update(NONCE_DOMAIN_TAG);
+    hasher.update(secret);
+    for item in transcript {
+        hasher.update(item);
+    }
+    hasher.update((context.len() as u64).to_le_bytes());
+    hasher.update(context);
+    hasher.update(aux);
+    let mut wide: [u8; 64] = hasher.finalize().into();
+    let nonce = Scalar::from_bytes_mod_order_wide(&wide);
+    wide.zeroize();
+    Zeroizing::new(nonce)
+}
@@ -0,0 +1,68 @@
+use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+use curve25519_dalek::scalar::Scalar;
+
+use dleq_verifier::{prove_with_aux, verify_batch, BatchFailure, DleqInputs, VerifyError};
+
+fn proofs(count: u64) -> Vec<DleqInputs> {
+    (1..=count)
+        .map(|i| {
+            let secret = Scalar::from(1_000 + i).to_bytes();
+            let y_point = (ED25519_BASEPOINT_POINT * Scalar::from(7 * i))
+                .compress()
+                .to_bytes();
+            let proof = prove_with_aux(&secret, &y_point, b"batch", &[i as u8; 32]).expect("prove");
+            DleqInputs::from(&proof)
+        })
+        .collect()
+}
+
+#[test]
+fn batch_accepts_valid_proofs() {
+    assert!(verify_batch(&proofs(16)).is_ok());
+}
+
+#[test]
+fn batch_accepts_empty_input() {
+    assert!(verify_batch(&[]).is_ok());
+}
+
+#[test]
+fn batch_pinpoints_tampered_response() {
+    let mut inputs = proofs(8);
+    inputs[5].response[0] ^= 0x01;
+
+    let failures = verify_batch(&inputs).expect_err("batch should fail");
+    assert_eq!(failures.len(), 1);
+    match &failures[0] {
+        BatchFailure::Rejected { index, report } => {
+            assert_eq!(*index, 5);
+            assert!(report.challenge_matches);
+            assert!(!report.lhs_r1_matches || !report.lhs_r2_matches);
+        }
+        other => panic!("unexpected failure: {other:?}"),
+    }
+}
+
+#[test]
+fn batch_reports_every_failure_in_order() {
+    let mut inputs = proofs(6);
+    inputs[4].hashlock[0] ^= 0x01;
+    inputs[1].response[0] ^= 0x01;
+    inputs[3].y_point = [0u8; 32];
+    inputs[3].y_point[0] = 1;
+
+    let failures = verify_batch(&inputs).expect_err("batch should fail");
+    let indices: Vec<usize> = failures.iter().map(BatchFailure::index).collect();
+    assert_eq!(indices, vec![1, 3, 4]);
+    assert!(matches!(
+        failures[1],
+        BatchFailure::Malformed {
+            error: VerifyError::SmallOrderPoint { field: "y_point" },
+            ..
+        }
+    ));
+    assert!(matches!(
+        &failures[2],
+        BatchFailure::Rejected { report, .. } if !report.challenge_matches
+    ));
+}
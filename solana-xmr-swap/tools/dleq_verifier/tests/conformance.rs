@@ -0,0 +1,108 @@
+//! Runs the labelled corpus through both the on-chain verifier in
+//! `atomic_lock::crypto::dleq` and the off-chain `dleq_verifier`, and fails
+//! on any disagreement between them or with the recorded expectation.
+
+use anchor_lang::error::Error;
+use atomic_lock::crypto::dleq::{
+    validate_dleq_transcript_bytes, verify_dleq_proof_bytes, verify_dleq_proof_part1_bytes,
+    verify_dleq_proof_part2_bytes,
+};
+use atomic_lock::errors::ErrorCode;
+use dleq_verifier::{classify, Corpus, DleqInputs, DleqProof, ErrorClass};
+
+fn load_corpus() -> Corpus {
+    let manifest = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+    let path = manifest.join("../../test_vectors/dleq_corpus.json");
+    let raw = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("read corpus at {}", path.display()));
+    serde_json::from_str(&raw).expect("parse corpus")
+}
+
+fn on_chain_class(err: Error) -> ErrorClass {
+    let table = [
+        (ErrorCode::InvalidPointEncoding, ErrorClass::InvalidPoint),
+        (ErrorCode::NonCanonicalPoint, ErrorClass::NonCanonicalPoint),
+        (ErrorCode::TorsionedPoint, ErrorClass::TorsionedPoint),
+        (
+            ErrorCode::NonCanonicalScalar,
+            ErrorClass::NonCanonicalScalar,
+        ),
+    ];
+    table
+        .into_iter()
+        .find(|(code, _)| err == Error::from(*code))
+        .map(|(_, class)| class)
+        .unwrap_or_else(|| panic!("unexpected on-chain error: {err}"))
+}
+
+fn on_chain(proof: &DleqProof) -> Result<(), ErrorClass> {
+    let ok = verify_dleq_proof_bytes(
+        &proof.adaptor_point,
+        &proof.second_point,
+        &proof.y_point,
+        &proof.r1,
+        &proof.r2,
+        &proof.challenge,
+        &proof.response,
+        &proof.hashlock,
+    )
+    .map_err(on_chain_class)?;
+    if ok {
+        return Ok(());
+    }
+    let transcript_ok = validate_dleq_transcript_bytes(
+        &proof.adaptor_point,
+        &proof.second_point,
+        &proof.y_point,
+        &proof.r1,
+        &proof.r2,
+        &proof.challenge,
+        &proof.response,
+        &proof.hashlock,
+    )
+    .expect("encodings were already checked by the full verifier");
+    if transcript_ok {
+        Err(ErrorClass::EquationMismatch)
+    } else {
+        Err(ErrorClass::ChallengeMismatch)
+    }
+}
+
+fn on_chain_split(proof: &DleqProof) -> bool {
+    let args = (
+        &proof.adaptor_point,
+        &proof.second_point,
+        &proof.y_point,
+        &proof.r1,
+        &proof.r2,
+        &proof.challenge,
+        &proof.response,
+        &proof.hashlock,
+    );
+    let part1 = verify_dleq_proof_part1_bytes(
+        args.0, args.1, args.2, args.3, args.4, args.5, args.6, args.7,
+    );
+    let part2 = verify_dleq_proof_part2_bytes(
+        args.0, args.1, args.2, args.3, args.4, args.5, args.6, args.7,
+    );
+    matches!((part1, part2), (Ok(true), Ok(true)))
+}
+
+#[test]
+fn on_chain_and_off_chain_verifiers_agree() {
+    let mut disagreements = Vec::new();
+    for vector in load_corpus().vectors {
+        let expected = vector.expected();
+        let off_chain = classify(&DleqInputs::from(&vector.proof));
+        let on_chain = on_chain(&vector.proof);
+        let split = on_chain_split(&vector.proof);
+        if off_chain != expected || on_chain != expected || split != expected.is_ok() {
+            disagreements.push(format!(
+                "{}: expected {expected:?}, dleq_verifier {off_chain:?}, \
+                 atomic_lock {on_chain:?}, atomic_lock split {split}",
+                vector.name
+            ));
+        }
+    }
+    assert!(disagreements.is_empty(), "{}", disagreements.join("\n"));
+}
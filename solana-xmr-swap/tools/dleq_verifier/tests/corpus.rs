@@ -0,0 +1,47 @@
+use dleq_verifier::{classify, generate_corpus, Corpus, DleqInputs, ErrorClass, Outcome};
+
+fn load_corpus() -> Corpus {
+    let manifest = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+    let path = manifest.join("../../test_vectors/dleq_corpus.json");
+    let raw = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("read corpus at {}", path.display()));
+    serde_json::from_str(&raw).expect("parse corpus")
+}
+
+#[test]
+fn checked_in_corpus_matches_generator() {
+    assert_eq!(
+        load_corpus(),
+        generate_corpus(),
+        "test_vectors/dleq_corpus.json is stale; regenerate with dleq-corpus"
+    );
+}
+
+#[test]
+fn corpus_vectors_have_expected_outcomes() {
+    for vector in load_corpus().vectors {
+        let actual = classify(&DleqInputs::from(&vector.proof));
+        assert_eq!(actual, vector.expected(), "vector {}", vector.name);
+    }
+}
+
+#[test]
+fn corpus_covers_every_rejection_class() {
+    let corpus = load_corpus();
+    for class in [
+        ErrorClass::InvalidPoint,
+        ErrorClass::NonCanonicalPoint,
+        ErrorClass::TorsionedPoint,
+        ErrorClass::NonCanonicalScalar,
+        ErrorClass::ChallengeMismatch,
+        ErrorClass::EquationMismatch,
+    ] {
+        assert!(
+            corpus
+                .vectors
+                .iter()
+                .any(|v| v.outcome == Outcome::Reject && v.error_class == Some(class)),
+            "no vector for {class:?}"
+        );
+    }
+}
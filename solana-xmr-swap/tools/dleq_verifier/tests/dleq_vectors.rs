@@ -1,7 +1,10 @@
 use std::fs;
 use std::path::PathBuf;
 
-use dleq_verifier::{compute_challenge_bytes, verify_dleq, DleqVector};
+use curve25519_dalek::constants::EIGHT_TORSION;
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::traits::Identity;
+use dleq_verifier::{compute_challenge_bytes, verify_dleq, DleqVector, VerifyError};
 
 fn load_vector() -> DleqVector {
     let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
@@ -54,3 +57,95 @@ fn vector_rejects_tampered_response() {
     assert!(report.challenge_matches, "challenge should still match");
     assert!(!report.lhs_r1_matches || !report.lhs_r2_matches, "expected proof mismatch");
 }
+
+/// Little-endian encoding of the group order l = 2^252 + 27742317777372353535851937790883648493.
+const GROUP_ORDER: [u8; 32] = [
+    0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde, 0x14,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
+];
+
+fn add_group_order(scalar: [u8; 32]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    let mut carry = 0u16;
+    for i in 0..32 {
+        let sum = scalar[i] as u16 + GROUP_ORDER[i] as u16 + carry;
+        out[i] = sum as u8;
+        carry = sum >> 8;
+    }
+    out
+}
+
+#[test]
+fn vector_rejects_non_canonical_response() {
+    let vector = load_vector();
+    let mut inputs = vector.decode().expect("decode vector");
+    // s + l is congruent to s and would verify under mod-order reduction.
+    inputs.response = add_group_order(inputs.response);
+
+    let err = verify_dleq(&inputs).expect_err("non-canonical response must fail");
+    assert!(matches!(
+        err,
+        VerifyError::NonCanonicalScalar { field: "response" }
+    ));
+}
+
+#[test]
+fn vector_rejects_non_canonical_challenge() {
+    let vector = load_vector();
+    let mut inputs = vector.decode().expect("decode vector");
+    inputs.challenge = add_group_order(inputs.challenge);
+
+    let err = verify_dleq(&inputs).expect_err("non-canonical challenge must fail");
+    assert!(matches!(
+        err,
+        VerifyError::NonCanonicalScalar { field: "challenge" }
+    ));
+}
+
+#[test]
+fn vector_rejects_non_canonical_point() {
+    let vector = load_vector();
+    let mut inputs = vector.decode().expect("decode vector");
+    // Setting the sign bit on an x = 0 point decompresses but does not
+    // round-trip.
+    let mut encoding = EdwardsPoint::identity().compress().to_bytes();
+    encoding[31] |= 0x80;
+    inputs.r1 = encoding;
+
+    let err = verify_dleq(&inputs).expect_err("non-canonical point must fail");
+    assert!(matches!(
+        err,
+        VerifyError::NonCanonicalPoint { field: "r1" }
+    ));
+}
+
+#[test]
+fn vector_rejects_torsioned_point() {
+    let vector = load_vector();
+    let mut inputs = vector.decode().expect("decode vector");
+    let y_point = CompressedEdwardsY(inputs.y_point)
+        .decompress()
+        .expect("decompress y_point");
+    inputs.y_point = (y_point + EIGHT_TORSION[1]).compress().to_bytes();
+
+    let err = verify_dleq(&inputs).expect_err("torsioned point must fail");
+    assert!(matches!(
+        err,
+        VerifyError::TorsionedPoint { field: "y_point" }
+    ));
+}
+
+#[test]
+fn vector_rejects_small_order_point() {
+    let vector = load_vector();
+    let mut inputs = vector.decode().expect("decode vector");
+    inputs.adaptor_point = EIGHT_TORSION[4].compress().to_bytes();
+
+    let err = verify_dleq(&inputs).expect_err("small-order point must fail");
+    assert!(matches!(
+        err,
+        VerifyError::SmallOrderPoint {
+            field: "adaptor_point"
+        }
+    ));
+}
@@ -0,0 +1,73 @@
+use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+use curve25519_dalek::scalar::Scalar;
+use sha2::{Digest, Sha256};
+
+use dleq_verifier::{prove, prove_with_aux, verify_dleq, DleqInputs, ProveError};
+
+fn y_point() -> [u8; 32] {
+    (ED25519_BASEPOINT_POINT * Scalar::from(987_654_321u64))
+        .compress()
+        .to_bytes()
+}
+
+fn secret() -> [u8; 32] {
+    Scalar::from(123_456_789u64).to_bytes()
+}
+
+#[test]
+fn proof_verifies_and_binds_hashlock() {
+    let secret = secret();
+    let proof = prove(&secret, &y_point(), b"swap-1").expect("prove");
+
+    let expected_hashlock: [u8; 32] = Sha256::digest(secret).into();
+    assert_eq!(proof.hashlock, expected_hashlock);
+    assert_eq!(
+        proof.adaptor_point,
+        (ED25519_BASEPOINT_POINT * Scalar::from(123_456_789u64))
+            .compress()
+            .to_bytes()
+    );
+
+    let (ok, report) = verify_dleq(&DleqInputs::from(&proof)).expect("verify");
+    assert!(ok, "expected proof to verify: {report:?}");
+}
+
+#[test]
+fn nonces_are_deterministic_for_fixed_aux() {
+    let aux = [7u8; 32];
+    let first = prove_with_aux(&secret(), &y_point(), b"swap-1", &aux).expect("prove");
+    let second = prove_with_aux(&secret(), &y_point(), b"swap-1", &aux).expect("prove");
+    assert_eq!(first, second);
+}
+
+#[test]
+fn nonces_depend_on_context_and_aux() {
+    let aux = [7u8; 32];
+    let base = prove_with_aux(&secret(), &y_point(), b"swap-1", &aux).expect("prove");
+    let other_context = prove_with_aux(&secret(), &y_point(), b"swap-2", &aux).expect("prove");
+    let other_aux = prove_with_aux(&secret(), &y_point(), b"swap-1", &[8u8; 32]).expect("prove");
+
+    assert_ne!(base.r1, other_context.r1);
+    assert_ne!(base.r1, other_aux.r1);
+    assert_eq!(base.adaptor_point, other_aux.adaptor_point);
+}
+
+#[test]
+fn rejects_non_canonical_secret() {
+    let result = prove(&[0xff; 32], &y_point(), b"");
+    assert!(matches!(result, Err(ProveError::NonCanonicalSecret)));
+}
+
+#[test]
+fn rejects_zero_secret() {
+    let result = prove(&[0u8; 32], &y_point(), b"");
+    assert!(matches!(result, Err(ProveError::ZeroSecret)));
+}
+
+#[test]
+fn rejects_small_order_y_point() {
+    let mut identity = [0u8; 32];
+    identity[0] = 1;
+    let result = prove(&secret(), &identity, b"");
+    assert!(matches!(result, Err(ProveError::InvalidYPoint(_))));
+}
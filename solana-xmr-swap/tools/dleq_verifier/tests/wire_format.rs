@@ -0,0 +1,116 @@
+use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+use curve25519_dalek::scalar::Scalar;
+
+use dleq_verifier::{
+    prove_with_aux, verify_dleq, DleqInputs, DleqProof, Hex32, VerifyError, PROOF_FORMAT_VERSION,
+    PROOF_WIRE_LEN,
+};
+
+fn sample_proof() -> DleqProof {
+    let y_point = (ED25519_BASEPOINT_POINT * Scalar::from(42u64))
+        .compress()
+        .to_bytes();
+    prove_with_aux(
+        &Scalar::from(7u64).to_bytes(),
+        &y_point,
+        b"wire",
+        &[1u8; 32],
+    )
+    .expect("prove")
+}
+
+fn read_vector(name: &str) -> String {
+    let manifest = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+    let path = manifest.join("../../test_vectors").join(name);
+    std::fs::read_to_string(&path).unwrap_or_else(|_| panic!("read vector {}", path.display()))
+}
+
+#[test]
+fn legacy_vectors_still_decode() {
+    for name in ["dleq.json", "dleq_demo.json"] {
+        let proof = DleqProof::from_json(&read_vector(name)).expect("decode legacy vector");
+        let (ok, report) = verify_dleq(&DleqInputs::from(&proof)).expect("verify");
+        assert!(ok, "{name} should verify: {report:?}");
+    }
+}
+
+#[test]
+fn json_round_trip_is_versioned() {
+    let proof = sample_proof();
+    let json = proof.to_json().expect("encode");
+
+    let value: serde_json::Value = serde_json::from_str(&json).expect("parse");
+    assert_eq!(value["version"], PROOF_FORMAT_VERSION);
+    assert_eq!(value["challenge"], proof.challenge.to_string());
+
+    assert_eq!(DleqProof::from_json(&json).expect("decode"), proof);
+}
+
+#[test]
+fn unknown_version_is_rejected() {
+    let mut value: serde_json::Value =
+        serde_json::from_str(&sample_proof().to_json().unwrap()).unwrap();
+    value["version"] = serde_json::json!(2);
+
+    let err = DleqProof::from_json(&value.to_string()).unwrap_err();
+    assert!(matches!(
+        err,
+        VerifyError::UnsupportedVersion { version: 2 }
+    ));
+}
+
+#[test]
+fn malformed_hex_is_rejected() {
+    let mut value: serde_json::Value =
+        serde_json::from_str(&sample_proof().to_json().unwrap()).unwrap();
+    value["r1"] = serde_json::json!("zz");
+
+    let err = DleqProof::from_json(&value.to_string()).unwrap_err();
+    assert!(matches!(err, VerifyError::InvalidJson { .. }));
+}
+
+#[test]
+fn wire_bytes_follow_initialize_argument_order() {
+    let proof = sample_proof();
+    let wire = proof.to_wire_bytes();
+    assert_eq!(wire.len(), PROOF_WIRE_LEN);
+
+    let expected: Vec<u8> = [
+        proof.hashlock,
+        proof.adaptor_point,
+        proof.second_point,
+        proof.y_point,
+        proof.r1,
+        proof.r2,
+        proof.challenge,
+        proof.response,
+    ]
+    .iter()
+    .flat_map(|field| field.to_bytes())
+    .collect();
+    assert_eq!(wire.as_slice(), expected.as_slice());
+
+    assert_eq!(DleqProof::from_wire_bytes(&wire).expect("decode"), proof);
+}
+
+#[test]
+fn wire_bytes_reject_wrong_length() {
+    let wire = sample_proof().to_wire_bytes();
+    let err = DleqProof::from_wire_bytes(&wire[..PROOF_WIRE_LEN - 1]).unwrap_err();
+    assert!(matches!(
+        err,
+        VerifyError::InvalidLength {
+            field: "proof",
+            got: 255,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn hex32_parses_and_displays() {
+    let hex = format!("{}ff", "00".repeat(31));
+    let value: Hex32 = hex.parse().expect("parse");
+    assert_eq!(value.to_bytes()[31], 0xff);
+    assert_eq!(value.to_string(), hex);
+}
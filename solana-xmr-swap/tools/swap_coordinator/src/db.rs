@@ -4,9 +4,16 @@
 //! https://github.com/omarespejel/monero-starknet-atomic-swap (Omar Espejel)
 
 use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use xmr_wallet::{
+    LockMechanism, LockMechanismStore, RestoreHeight, RestoreHeightStore, WatcherState,
+    WatcherStore,
+};
+
 use crate::state::SwapState;
 
 pub trait SwapDb: Send + Sync {
@@ -30,6 +37,49 @@ impl JsonFileDb {
     fn swap_file_path(&self, swap_id: &str) -> PathBuf {
         self.base_dir.join(format!("{swap_id}.json"))
     }
+
+    /// Per-swap record stored beside the swap state as
+    /// `{swap_id}.{kind}.json`.
+    fn record_path(&self, swap_id: &str, kind: &str) -> PathBuf {
+        self.base_dir.join(format!("{swap_id}.{kind}.json"))
+    }
+
+    /// Replaces the `kind` record of `swap_id` atomically. `label` names
+    /// the record in errors.
+    fn save_json<T: Serialize>(
+        &self,
+        swap_id: &str,
+        kind: &str,
+        label: &str,
+        value: &T,
+    ) -> Result<()> {
+        let path = self.record_path(swap_id, kind);
+        let json = serde_json::to_string_pretty(value)
+            .with_context(|| format!("Failed to serialize {label} for swap {swap_id}"))?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, json)
+            .with_context(|| format!("Failed to write {label} file: {:?}", tmp))?;
+        fs::rename(&tmp, &path)
+            .with_context(|| format!("Failed to replace {label} file: {:?}", path))?;
+        Ok(())
+    }
+
+    fn load_json<T: DeserializeOwned>(
+        &self,
+        swap_id: &str,
+        kind: &str,
+        label: &str,
+    ) -> Result<Option<T>> {
+        let path = self.record_path(swap_id, kind);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {label} file: {:?}", path))?;
+        let value = serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse {label} file: {:?}", path))?;
+        Ok(Some(value))
+    }
 }
 
 impl SwapDb for JsonFileDb {
@@ -54,3 +104,43 @@ impl SwapDb for JsonFileDb {
         Ok(Some(state))
     }
 }
+
+/// Watcher progress lives next to the swap state so a restarted coordinator
+/// resumes both.
+impl WatcherStore for JsonFileDb {
+    fn save_watcher_state(&self, swap_id: &str, state: &WatcherState) -> xmr_wallet::Result<()> {
+        Ok(self.save_json(swap_id, "watcher", "watcher state", state)?)
+    }
+
+    fn load_watcher_state(&self, swap_id: &str) -> xmr_wallet::Result<Option<WatcherState>> {
+        Ok(self.load_json(swap_id, "watcher", "watcher state")?)
+    }
+}
+
+/// The claim wallet's restore height is chosen once per swap and reused by
+/// every claim attempt.
+impl RestoreHeightStore for JsonFileDb {
+    fn save_restore_height(&self, swap_id: &str, height: &RestoreHeight) -> xmr_wallet::Result<()> {
+        Ok(self.save_json(swap_id, "restore_height", "restore height", height)?)
+    }
+
+    fn load_restore_height(&self, swap_id: &str) -> xmr_wallet::Result<Option<RestoreHeight>> {
+        Ok(self.load_json(swap_id, "restore_height", "restore height")?)
+    }
+}
+
+/// Whether a swap's XMR is locked with the key-sum address or 2-of-2
+/// multisig is recorded once, and lock, watch and claim all read it.
+impl LockMechanismStore for JsonFileDb {
+    fn save_lock_mechanism(
+        &self,
+        swap_id: &str,
+        mechanism: &LockMechanism,
+    ) -> xmr_wallet::Result<()> {
+        Ok(self.save_json(swap_id, "lock_mechanism", "lock mechanism", mechanism)?)
+    }
+
+    fn load_lock_mechanism(&self, swap_id: &str) -> xmr_wallet::Result<Option<LockMechanism>> {
+        Ok(self.load_json(swap_id, "lock_mechanism", "lock mechanism")?)
+    }
+}
@@ -8,6 +8,7 @@ pub mod xmr;
 
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::Duration;
 
 use anyhow::{anyhow, Context, Result};
 use clap::{Parser, Subcommand};
@@ -25,8 +26,18 @@ use spl_associated_token_account::get_associated_token_address;
 use spl_associated_token_account::instruction::create_associated_token_account;
 use spl_token::instruction as token_instruction;
 
-use dleq_verifier::{verify_dleq, DleqVector};
-use xmr_wallet::XmrWallet;
+use dleq_verifier::{
+    decode_hex_32, decode_hex_array, hex_encode, verify_dleq, DleqInputs, DleqProof, Hex32,
+};
+use xmr_wallet::{
+    combine_view_key_shares, cosign_multisig_sweep, create_key_share, export_cold_sync,
+    export_multisig_sync, export_sweep, import_cold_sync, import_multisig_sync,
+    prepare_multisig_wallet, propose_multisig_sweep, relay_exported_sweep, sign_sweep,
+    submit_multisig_sweep, validate_destination_address, ColdSyncFile, KeyShare, MultisigLock,
+    MultisigTxSet, PendingSweep, SecretKey, TransferOptions, TxProofLock, UnlockWait,
+    UnsignedSweep, WalletCleanup, WatcherConfig, KEY_SHARE_LEN,
+};
+use zeroize::Zeroizing;
 
 use crate::db::{JsonFileDb, SwapDb};
 use crate::driver::step;
@@ -34,13 +45,28 @@ use crate::metrics::NoopMetrics;
 use crate::config::{validate_nonzero, validate_rpc_url};
 use crate::solana::SolanaSwapClient;
 use crate::state::SwapState;
-use crate::xmr::{claim_xmr, parse_network, XmrClaimRequest};
+use crate::xmr::{
+    advance_swap_multisig, claim_restore_height, claim_xmr, ensure_key_sum_lock, lock_view_scanner,
+    lock_xmr_for_swap, open_multisig_wallet, open_signing_wallet, open_view_wallet,
+    parse_cold_sync_kind, parse_network, parse_priority, swap_lock_address, watch_xmr_lock,
+    LockKeys, XmrClaimRequest, XmrConnections, XmrLockRequest, XmrSigningWalletRequest,
+    XmrWatchRequest,
+};
 
 #[derive(Parser)]
 #[command(name = "swap-coordinator", version, about = "Swap orchestration CLI")]
 struct Cli {
     #[command(subcommand)]
     command: Command,
+    /// JSON wallet-rpc connection settings (login, cert_sha256, ca_cert,
+    /// client_cert, client_key, timeout_ms); defaults to the
+    /// XMR_WALLET_RPC_* environment
+    #[arg(long, global = true)]
+    wallet_connection: Option<PathBuf>,
+    /// JSON monerod connection settings; defaults to the XMR_DAEMON_RPC_*
+    /// environment
+    #[arg(long, global = true)]
+    daemon_connection: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -49,8 +75,40 @@ enum Command {
     Demo(DemoArgs),
     /// Create a new mint and fund the depositor
     SetupMint(SetupMintArgs),
+    /// Lock XMR to the joint address and store the tx key
+    LockXmr(LockXmrArgs),
+    /// Watch the counterparty's XMR lock until it is confirmed
+    WatchXmr(WatchXmrArgs),
     /// Claim XMR using the revealed secret
-    ClaimXmr(ClaimXmrArgs),
+    ClaimXmr(Box<ClaimXmrArgs>),
+    /// Broadcast a claim sweep signed with `sign-sweep`
+    RelayXmr(RelayXmrArgs),
+    /// Cold claim, online: create and sync the view-only claim wallet
+    ViewWallet(ViewWalletArgs),
+    /// Cold claim, offline: create the claim wallet holding the spend key
+    ClaimWallet(ClaimWalletArgs),
+    /// Export outputs or key images for cold signing
+    ColdExport(ColdExportArgs),
+    /// Import outputs or key images exported by `cold-export`
+    ColdImport(ColdImportArgs),
+    /// Cold claim, online: build the unsigned sweep from the view-only wallet
+    SweepUnsigned(SweepUnsignedArgs),
+    /// Cold claim, offline: sign the sweep built by `sweep-unsigned`
+    SignSweep(SignSweepArgs),
+    /// Multisig lock, setup: create our multisig wallet and print its first message
+    MultisigPrepare(MultisigPrepareArgs),
+    /// Multisig lock, setup: feed the counterparty's latest message to our wallet
+    MultisigAdvance(MultisigAdvanceArgs),
+    /// Multisig lock, spend: export our sync info and import the counterparty's
+    MultisigSync(MultisigSyncArgs),
+    /// Multisig lock, spend: build the sweep carrying our signature
+    MultisigPropose(MultisigProposeArgs),
+    /// Multisig lock, spend: check the counterparty's sweep and add our signature
+    MultisigCosign(MultisigCosignArgs),
+    /// Multisig lock, spend: broadcast the sweep signed by both parties
+    MultisigSubmit(MultisigSubmitArgs),
+    /// Publish a partial spend key share with a proof of knowledge
+    KeyShare(KeyShareArgs),
 }
 
 #[derive(Parser)]
@@ -104,47 +162,461 @@ struct SetupMintArgs {
 }
 
 #[derive(Parser)]
-struct ClaimXmrArgs {
+struct LockXmrArgs {
+    /// Monero wallet RPC URL (wallet holding the funds must be open)
+    #[arg(long, default_value = "http://127.0.0.1:18083")]
+    rpc: String,
+    /// Network: mainnet | testnet | stagenet
+    #[arg(long, default_value = "stagenet")]
+    network: String,
+    /// Swap id the key shares were proven for; names the stored lock receipt
+    #[arg(long)]
+    swap_id: String,
+    /// Key shares of a key-sum lock; a multisig swap pays the address from
+    /// `multisig-advance` and takes none
+    #[command(flatten)]
+    keys: Option<LockKeyArgs>,
+    /// Amount in piconero
+    #[arg(long)]
+    amount: u64,
+    #[command(flatten)]
+    transfer: TransferArgs,
+    /// State directory
+    #[arg(long, default_value = "swap_state")]
+    state_dir: PathBuf,
+}
+
+#[derive(Parser)]
+struct TransferArgs {
+    /// Monero daemon RPC URL; the ring size is checked against its hard fork
+    #[arg(long, default_value = "http://127.0.0.1:38081")]
+    daemon: String,
+    /// Priority: default | unimportant | elevated | priority
+    #[arg(long, default_value = "default")]
+    priority: String,
+    /// Ring size; defaults to what the daemon's hard fork requires
+    #[arg(long)]
+    ring_size: Option<u64>,
+    /// Maximum fee in piconero; a costlier transaction is not relayed
+    #[arg(long)]
+    max_fee: Option<u64>,
+}
+
+/// Public inputs the joint lock address is derived from. Giving any of
+/// them requires the rest, so a multisig swap can leave all out.
+#[derive(clap::Args)]
+#[group(multiple = true, requires_all = ["alice_share", "bob_share", "adaptor_point"])]
+struct LockKeyArgs {
+    /// Alice public key share with proof of knowledge (hex, 96 bytes)
+    #[arg(long, required = false)]
+    alice_share: String,
+    /// Bob public key share with proof of knowledge (hex, 96 bytes)
+    #[arg(long, required = false)]
+    bob_share: String,
+    /// Adaptor point T from the Solana lock (hex)
+    #[arg(long, required = false)]
+    adaptor_point: String,
+    /// View-key share (hex); pass exactly two, one per party. The joint
+    /// view key is their sum.
+    #[arg(long = "view-share")]
+    view_shares: Vec<String>,
+}
+
+#[derive(Parser)]
+struct WatchXmrArgs {
     /// Monero wallet RPC URL
     #[arg(long, default_value = "http://127.0.0.1:18083")]
     rpc: String,
+    /// Monero daemon RPC URL
+    #[arg(long, default_value = "http://127.0.0.1:38081")]
+    daemon: String,
     /// Network: mainnet | testnet | stagenet
     #[arg(long, default_value = "stagenet")]
     network: String,
-    /// Wallet filename to create for claim
-    #[arg(long, default_value = "claim_wallet")]
-    wallet: String,
-    /// Destination address to sweep funds to
+    /// Swap id the key shares were proven for; names the stored watcher state
     #[arg(long)]
-    destination: String,
+    swap_id: String,
+    /// Lock transaction id from the counterparty
+    #[arg(long)]
+    tx_id: String,
+    /// Lock transaction key from the counterparty; a key-sum lock needs it,
+    /// --tx-proof or --scan
+    #[arg(long, conflicts_with = "tx_proof")]
+    tx_key: Option<String>,
+    /// Lock transaction proof from the counterparty's `get_tx_proof`, in
+    /// place of the tx key
+    #[arg(long)]
+    tx_proof: Option<String>,
+    /// Message the tx proof was signed over
+    #[arg(long, default_value = "", requires = "tx_proof")]
+    tx_proof_message: String,
+    /// Scan the lock from the daemon with the joint view key instead of
+    /// checking the tx key through wallet RPC
+    #[arg(long)]
+    scan: bool,
+    /// Key shares of a key-sum lock. A multisig swap takes none; its lock is
+    /// checked from the multisig wallet open in --rpc
+    #[command(flatten)]
+    keys: Option<LockKeyArgs>,
+    /// Expected amount in piconero
+    #[arg(long)]
+    amount: u64,
+    /// Confirmations required
+    #[arg(long, default_value_t = 10)]
+    confirmations: u64,
+    /// Seconds between polls
+    #[arg(long, default_value_t = 30)]
+    interval: u64,
+    /// State directory
+    #[arg(long, default_value = "swap_state")]
+    state_dir: PathBuf,
+}
+
+/// Secrets the claim spend key is derived from, with the published shares
+/// they are checked against.
+#[derive(clap::Args)]
+struct ClaimKeyArgs {
+    /// Swap id the key shares were proven for
+    #[arg(long)]
+    swap_id: String,
     /// Alice partial secret (hex)
     #[arg(long)]
     alice_partial: String,
     /// Bob partial secret (hex)
     #[arg(long)]
     bob_partial: String,
+    /// Alice public key share with proof of knowledge (hex, 96 bytes)
+    #[arg(long)]
+    alice_share: String,
+    /// Bob public key share with proof of knowledge (hex, 96 bytes)
+    #[arg(long)]
+    bob_share: String,
     /// Revealed secret from Solana (hex)
     #[arg(long)]
     secret: String,
-    /// Optional restore height
+    /// View-key share (hex); pass exactly two, one per party. The joint
+    /// view key is their sum. Omit for legacy swaps whose view key derives
+    /// from the spend key.
+    #[arg(long = "view-share")]
+    view_shares: Vec<String>,
+}
+
+#[derive(Parser)]
+struct ClaimXmrArgs {
+    /// Monero wallet RPC URL
+    #[arg(long, default_value = "http://127.0.0.1:18083")]
+    rpc: String,
+    /// Network: mainnet | testnet | stagenet
+    #[arg(long, default_value = "stagenet")]
+    network: String,
+    /// Wallet filename to create for claim
+    #[arg(long, default_value = "claim_wallet")]
+    wallet: String,
+    /// Destination address to sweep funds to
+    #[arg(long)]
+    destination: String,
+    #[command(flatten)]
+    keys: ClaimKeyArgs,
+    /// Restore height for the claim wallet. Defaults to the height stored
+    /// for the swap, taken from the watched lock
     #[arg(long)]
     restore_height: Option<u64>,
+    /// Unix time the lock was sent, to estimate a restore height when the
+    /// lock was never watched
+    #[arg(long)]
+    lock_time: Option<u64>,
+    /// Locked amount in piconero; the sweep waits until this much is spendable
+    #[arg(long)]
+    amount: u64,
+    /// Seconds to wait for the claimed outputs to unlock
+    #[arg(long, default_value_t = 7200)]
+    unlock_timeout: u64,
+    #[command(flatten)]
+    transfer: TransferArgs,
+    /// State directory; the claim journal is kept here until confirmed
+    #[arg(long, default_value = "swap_state")]
+    state_dir: PathBuf,
+    /// monero-wallet-rpc's --wallet-dir; the claim wallet's files there are
+    /// overwritten and removed once the sweep confirms
+    #[arg(long)]
+    delete_wallet_dir: Option<PathBuf>,
+}
+
+#[derive(Parser)]
+struct RelayXmrArgs {
+    /// Monero daemon RPC URL
+    #[arg(long, default_value = "http://127.0.0.1:38081")]
+    daemon: String,
+    /// Signed sweep written by `sign-sweep`
+    #[arg(long)]
+    input: PathBuf,
+}
+
+#[derive(Parser)]
+struct ViewWalletArgs {
+    /// Monero wallet RPC URL of the online host
+    #[arg(long, default_value = "http://127.0.0.1:18083")]
+    rpc: String,
+    /// Monero daemon RPC URL, to estimate the restore height
+    #[arg(long, default_value = "http://127.0.0.1:38081")]
+    daemon: String,
+    /// Network: mainnet | testnet | stagenet
+    #[arg(long, default_value = "stagenet")]
+    network: String,
+    /// View-only wallet filename to create
+    #[arg(long, default_value = "claim_view_wallet")]
+    wallet: String,
+    /// Swap id the key shares were proven for
+    #[arg(long)]
+    swap_id: String,
+    #[command(flatten)]
+    keys: LockKeyArgs,
+    /// Restore height; defaults to the height stored for the swap
+    #[arg(long)]
+    restore_height: Option<u64>,
+    /// Unix time the lock was sent, to estimate a restore height when the
+    /// lock was never watched
+    #[arg(long)]
+    lock_time: Option<u64>,
+    /// State directory
+    #[arg(long, default_value = "swap_state")]
+    state_dir: PathBuf,
+}
+
+#[derive(Parser)]
+struct ClaimWalletArgs {
+    /// Monero wallet RPC URL of the offline host
+    #[arg(long, default_value = "http://127.0.0.1:18083")]
+    rpc: String,
+    /// Network: mainnet | testnet | stagenet
+    #[arg(long, default_value = "stagenet")]
+    network: String,
+    /// Claim wallet filename to create
+    #[arg(long, default_value = "claim_wallet")]
+    wallet: String,
+    #[command(flatten)]
+    keys: ClaimKeyArgs,
+}
+
+#[derive(Parser)]
+struct SweepUnsignedArgs {
+    /// Monero wallet RPC URL (view-only wallet from `view-wallet` must be open)
+    #[arg(long, default_value = "http://127.0.0.1:18083")]
+    rpc: String,
+    /// Key images written by `cold-export --kind key-images` on the offline host
+    #[arg(long)]
+    key_images: PathBuf,
+    /// Destination address to sweep funds to
+    #[arg(long)]
+    destination: String,
+    /// Locked amount in piconero; the sweep waits until this much is spendable
+    #[arg(long)]
+    amount: u64,
+    /// Seconds to wait for the claimed outputs to unlock
+    #[arg(long, default_value_t = 7200)]
+    unlock_timeout: u64,
+    #[command(flatten)]
+    transfer: TransferArgs,
+    /// File to write the unsigned sweep to
+    #[arg(long)]
+    output: PathBuf,
+}
+
+#[derive(Parser)]
+struct SignSweepArgs {
+    /// Monero wallet RPC URL (claim wallet from `claim-wallet` must be open)
+    #[arg(long, default_value = "http://127.0.0.1:18083")]
+    rpc: String,
+    /// Unsigned sweep written by `sweep-unsigned`
+    #[arg(long)]
+    input: PathBuf,
+    /// Destination address the sweep must pay
+    #[arg(long)]
+    destination: String,
+    /// Maximum fee in piconero; a costlier sweep is not signed
+    #[arg(long)]
+    max_fee: Option<u64>,
+    /// File to write the signed sweep to
+    #[arg(long)]
+    output: PathBuf,
+}
+
+#[derive(Parser)]
+struct ColdExportArgs {
+    /// Monero wallet RPC URL (wallet to export from must be open)
+    #[arg(long, default_value = "http://127.0.0.1:18083")]
+    rpc: String,
+    /// What to export: outputs (view-only wallet) | key-images (offline wallet)
+    #[arg(long)]
+    kind: String,
+    /// File to write
+    #[arg(long)]
+    output: PathBuf,
+}
+
+#[derive(Parser)]
+struct ColdImportArgs {
+    /// Monero wallet RPC URL (wallet to import into must be open)
+    #[arg(long, default_value = "http://127.0.0.1:18083")]
+    rpc: String,
+    /// File written by `cold-export`
+    #[arg(long)]
+    input: PathBuf,
+}
+
+#[derive(Parser)]
+struct MultisigPrepareArgs {
+    /// Monero wallet RPC URL
+    #[arg(long, default_value = "http://127.0.0.1:18083")]
+    rpc: String,
+    /// Multisig wallet filename to create
+    #[arg(long, default_value = "swap_multisig")]
+    wallet: String,
+}
+
+#[derive(Parser)]
+struct MultisigAdvanceArgs {
+    /// Monero wallet RPC URL (multisig wallet from `multisig-prepare` must be open)
+    #[arg(long, default_value = "http://127.0.0.1:18083")]
+    rpc: String,
+    /// Network: mainnet | testnet | stagenet
+    #[arg(long, default_value = "stagenet")]
+    network: String,
+    /// Swap id the multisig lock is recorded for
+    #[arg(long)]
+    swap_id: String,
+    /// The counterparty's latest multisig message
+    #[arg(long)]
+    peer_info: String,
+    /// State directory
+    #[arg(long, default_value = "swap_state")]
+    state_dir: PathBuf,
+}
+
+#[derive(Parser)]
+struct MultisigSyncArgs {
+    /// Monero wallet RPC URL (the swap's multisig wallet must be open)
+    #[arg(long, default_value = "http://127.0.0.1:18083")]
+    rpc: String,
+    /// Swap id the multisig lock is recorded for
+    #[arg(long)]
+    swap_id: String,
+    /// The counterparty's sync info to import
+    #[arg(long)]
+    peer_info: Option<String>,
+    /// State directory
+    #[arg(long, default_value = "swap_state")]
+    state_dir: PathBuf,
+}
+
+#[derive(Parser)]
+struct MultisigProposeArgs {
+    /// Monero wallet RPC URL (the swap's multisig wallet must be open)
+    #[arg(long, default_value = "http://127.0.0.1:18083")]
+    rpc: String,
+    /// Swap id the multisig lock is recorded for
+    #[arg(long)]
+    swap_id: String,
+    /// Destination address to sweep funds to
+    #[arg(long)]
+    destination: String,
+    #[command(flatten)]
+    transfer: TransferArgs,
+    /// File to write the half-signed set to, for the counterparty
+    #[arg(long)]
+    output: PathBuf,
+    /// State directory
+    #[arg(long, default_value = "swap_state")]
+    state_dir: PathBuf,
+}
+
+#[derive(Parser)]
+struct MultisigCosignArgs {
+    /// Monero wallet RPC URL (the swap's multisig wallet must be open)
+    #[arg(long, default_value = "http://127.0.0.1:18083")]
+    rpc: String,
+    /// Swap id the multisig lock is recorded for
+    #[arg(long)]
+    swap_id: String,
+    /// Set written by the counterparty's `multisig-propose`
+    #[arg(long)]
+    input: PathBuf,
+    /// Destination address the sweep must pay
+    #[arg(long)]
+    destination: String,
+    #[command(flatten)]
+    transfer: TransferArgs,
+    /// File to write the fully signed set to
+    #[arg(long)]
+    output: PathBuf,
+    /// State directory
+    #[arg(long, default_value = "swap_state")]
+    state_dir: PathBuf,
+}
+
+#[derive(Parser)]
+struct MultisigSubmitArgs {
+    /// Monero wallet RPC URL (the swap's multisig wallet must be open)
+    #[arg(long, default_value = "http://127.0.0.1:18083")]
+    rpc: String,
+    /// Swap id the multisig lock is recorded for
+    #[arg(long)]
+    swap_id: String,
+    /// Set written by `multisig-cosign`
+    #[arg(long)]
+    input: PathBuf,
+    /// State directory
+    #[arg(long, default_value = "swap_state")]
+    state_dir: PathBuf,
+}
+
+#[derive(Parser)]
+struct KeyShareArgs {
+    /// Swap id to bind the proof to
+    #[arg(long)]
+    swap_id: String,
+    /// Partial spend secret (hex)
+    #[arg(long)]
+    partial: String,
 }
 
-#[derive(Deserialize)]
 struct DemoVector {
-    secret: String,
-    #[serde(flatten)]
-    vector: DleqVector,
+    secret: Hex32,
+    proof: DleqProof,
+}
+
+#[derive(Deserialize)]
+struct DemoSecret {
+    secret: Hex32,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    let connections = XmrConnections {
+        wallet_config: cli.wallet_connection,
+        daemon_config: cli.daemon_connection,
+    };
     match cli.command {
         Command::Demo(args) => run_demo(args).await,
         Command::SetupMint(args) => run_setup_mint(args),
-        Command::ClaimXmr(args) => run_claim_xmr(args).await,
+        Command::LockXmr(args) => run_lock_xmr(args, &connections).await,
+        Command::WatchXmr(args) => run_watch_xmr(args, &connections).await,
+        Command::ClaimXmr(args) => run_claim_xmr(*args, &connections).await,
+        Command::RelayXmr(args) => run_relay_xmr(args, &connections).await,
+        Command::ViewWallet(args) => run_view_wallet(args, &connections).await,
+        Command::ClaimWallet(args) => run_claim_wallet(args, &connections).await,
+        Command::ColdExport(args) => run_cold_export(args, &connections).await,
+        Command::ColdImport(args) => run_cold_import(args, &connections).await,
+        Command::SweepUnsigned(args) => run_sweep_unsigned(args, &connections).await,
+        Command::SignSweep(args) => run_sign_sweep(args, &connections).await,
+        Command::MultisigPrepare(args) => run_multisig_prepare(args, &connections).await,
+        Command::MultisigAdvance(args) => run_multisig_advance(args, &connections).await,
+        Command::MultisigSync(args) => run_multisig_sync(args, &connections).await,
+        Command::MultisigPropose(args) => run_multisig_propose(args, &connections).await,
+        Command::MultisigCosign(args) => run_multisig_cosign(args, &connections).await,
+        Command::MultisigSubmit(args) => run_multisig_submit(args, &connections).await,
+        Command::KeyShare(args) => run_key_share(args),
     }
 }
 
@@ -193,22 +665,22 @@ async fn run_demo(args: DemoArgs) -> Result<()> {
         token_mint: token_mint.to_string(),
         amount: args.amount,
         lock_duration_secs: args.lock_duration,
-        hashlock: decode_hex_32(&demo.vector.hashlock)?,
-        adaptor_point: decode_hex_32(&demo.vector.adaptor_point_compressed)?,
-        second_point: decode_hex_32(&demo.vector.dleq_second_point_compressed)?,
-        y_point: decode_hex_32(&demo.vector.y_compressed)?,
-        r1: decode_hex_32(&demo.vector.r1_compressed)?,
-        r2: decode_hex_32(&demo.vector.r2_compressed)?,
-        challenge: decode_hex_32(&demo.vector.challenge)?,
-        response: decode_hex_32(&demo.vector.response)?,
+        hashlock: *demo.proof.hashlock,
+        adaptor_point: *demo.proof.adaptor_point,
+        second_point: *demo.proof.second_point,
+        y_point: *demo.proof.y_point,
+        r1: *demo.proof.r1,
+        r2: *demo.proof.r2,
+        challenge: *demo.proof.challenge,
+        response: *demo.proof.response,
     };
 
     let db = JsonFileDb::new(&args.state_dir)?;
     db.save(&state)?;
 
     let mut current = state;
-    let secret = decode_hex_32(&demo.secret)?;
-    let metrics = NoopMetrics::default();
+    let secret = *demo.secret;
+    let metrics = NoopMetrics;
 
     let client = SolanaSwapClient::new(
         &args.rpc,
@@ -217,14 +689,14 @@ async fn run_demo(args: DemoArgs) -> Result<()> {
         unlocker,
         token_mint,
         args.amount,
-        decode_hex_32(&demo.vector.hashlock)?,
-        decode_hex_32(&demo.vector.adaptor_point_compressed)?,
-        decode_hex_32(&demo.vector.dleq_second_point_compressed)?,
-        decode_hex_32(&demo.vector.y_compressed)?,
-        decode_hex_32(&demo.vector.r1_compressed)?,
-        decode_hex_32(&demo.vector.r2_compressed)?,
-        decode_hex_32(&demo.vector.challenge)?,
-        decode_hex_32(&demo.vector.response)?,
+        *demo.proof.hashlock,
+        *demo.proof.adaptor_point,
+        *demo.proof.second_point,
+        *demo.proof.y_point,
+        *demo.proof.r1,
+        *demo.proof.r2,
+        *demo.proof.challenge,
+        *demo.proof.response,
     );
 
     println!("swap_id: {swap_id}");
@@ -258,33 +730,451 @@ fn run_setup_mint(args: SetupMintArgs) -> Result<()> {
     Ok(())
 }
 
-async fn run_claim_xmr(args: ClaimXmrArgs) -> Result<()> {
+async fn run_lock_xmr(args: LockXmrArgs, connections: &XmrConnections) -> Result<()> {
+    validate_rpc_url("rpc", &args.rpc)?;
+    validate_nonzero("amount", args.amount)?;
+    let keys = args.keys.as_ref().map(lock_keys).transpose()?;
+    let db = JsonFileDb::new(&args.state_dir)?;
+    let address = swap_lock_address(
+        &db,
+        &args.swap_id,
+        keys.as_ref(),
+        parse_network(&args.network)?,
+    )?;
+    let request = XmrLockRequest {
+        swap_id: args.swap_id,
+        address,
+        amount: args.amount,
+        transfer: transfer_options(&args.transfer, connections).await?,
+        state_dir: args.state_dir,
+    };
+    let wallet = connections.wallet(&args.rpc).await?;
+    let receipt = lock_xmr_for_swap(&wallet, &request).await?;
+    println!("xmr_lock_address: {}", receipt.address);
+    println!("xmr_lock_tx: {}", receipt.tx_id);
+    println!("xmr_lock_tx_key: {}", receipt.tx_key);
+    println!("xmr_lock_fee: {}", receipt.fee);
+    Ok(())
+}
+
+async fn run_watch_xmr(args: WatchXmrArgs, connections: &XmrConnections) -> Result<()> {
+    validate_rpc_url("rpc", &args.rpc)?;
+    validate_rpc_url("daemon", &args.daemon)?;
+    validate_nonzero("amount", args.amount)?;
+    validate_nonzero("interval", args.interval)?;
+    let network = parse_network(&args.network)?;
+    let keys = args.keys.as_ref().map(lock_keys).transpose()?;
+    let db = JsonFileDb::new(&args.state_dir)?;
+    // Without key shares this only succeeds for a multisig swap.
+    let joint_address = swap_lock_address(&db, &args.swap_id, keys.as_ref(), network)?;
+    match &keys {
+        None if args.scan => {
+            return Err(anyhow!(
+                "--scan needs the key shares; a multisig lock is checked from the multisig wallet"
+            ))
+        }
+        Some(_) if !args.scan && args.tx_key.is_none() && args.tx_proof.is_none() => {
+            return Err(anyhow!(
+                "a key-sum lock needs --tx-key or --tx-proof unless --scan is given"
+            ))
+        }
+        _ => {}
+    }
+    // --scan reads the lock from the daemon alone.
+    let wallet = if args.scan {
+        None
+    } else {
+        Some(connections.wallet(&args.rpc).await?)
+    };
+    let request = XmrWatchRequest {
+        swap_id: args.swap_id,
+        tx_id: args.tx_id,
+        tx_key: args.tx_key,
+        joint_address,
+        expected_amount: args.amount,
+        config: WatcherConfig {
+            confirmations_required: args.confirmations,
+            ..WatcherConfig::default()
+        },
+        poll_interval: Duration::from_secs(args.interval),
+    };
+    let daemon = connections.daemon(&args.daemon).await?;
+    let event = match (&keys, &wallet) {
+        (Some(keys), None) => {
+            let scanner = lock_view_scanner(&request.joint_address, keys)?;
+            watch_xmr_lock(&scanner, &daemon, &db, &request, |event| {
+                println!("watch -> {event:?}");
+            })
+            .await?
+        }
+        (None, Some(wallet)) => {
+            watch_xmr_lock(
+                &MultisigLock::new(wallet),
+                &daemon,
+                &db,
+                &request,
+                |event| {
+                    println!("watch -> {event:?}");
+                },
+            )
+            .await?
+        }
+        (Some(_), Some(wallet)) => match &args.tx_proof {
+            Some(proof) => {
+                let backend = TxProofLock::new(wallet, proof, &args.tx_proof_message);
+                watch_xmr_lock(&backend, &daemon, &db, &request, |event| {
+                    println!("watch -> {event:?}");
+                })
+                .await?
+            }
+            None => {
+                watch_xmr_lock(wallet, &daemon, &db, &request, |event| {
+                    println!("watch -> {event:?}");
+                })
+                .await?
+            }
+        },
+        (None, None) => unreachable!("--scan is refused for a multisig lock"),
+    };
+    println!("xmr_lock_confirmed: {event:?}");
+    Ok(())
+}
+
+async fn run_claim_xmr(args: ClaimXmrArgs, connections: &XmrConnections) -> Result<()> {
     validate_rpc_url("rpc", &args.rpc)?;
+    validate_nonzero("amount", args.amount)?;
     let network = parse_network(&args.network)?;
-    let wallet = XmrWallet::connect(&args.rpc).await?;
+    validate_rpc_url("daemon", &args.transfer.daemon)?;
+    let wallet = connections.wallet(&args.rpc).await?;
+    let daemon = connections.daemon(&args.transfer.daemon).await?;
+    let db = JsonFileDb::new(&args.state_dir)?;
+    ensure_key_sum_lock(&db, &args.keys.swap_id)?;
+    let restore_height = claim_restore_height(
+        &db,
+        &daemon,
+        &args.keys.swap_id,
+        args.restore_height,
+        args.lock_time,
+    )
+    .await?;
+    println!("claim -> {restore_height:?}");
     let request = XmrClaimRequest {
-        alice_partial: decode_hex_32(&args.alice_partial)?,
-        bob_partial: decode_hex_32(&args.bob_partial)?,
-        revealed_secret: decode_hex_32(&args.secret)?,
+        swap_id: args.keys.swap_id.clone(),
+        alice_partial: decode_hex_32(&args.keys.alice_partial, "alice_partial")?,
+        bob_partial: decode_hex_32(&args.keys.bob_partial, "bob_partial")?,
+        alice_share: decode_key_share(&args.keys.alice_share)?,
+        bob_share: decode_key_share(&args.keys.bob_share)?,
+        revealed_secret: decode_hex_32(&args.keys.secret, "secret")?,
+        view_key: joint_view_key(&args.keys.view_shares)?,
         destination_address: args.destination,
         wallet_filename: args.wallet,
         network,
-        restore_height: args.restore_height,
+        restore_height: Some(restore_height.height),
+        unlock: UnlockWait {
+            expected_amount: args.amount,
+            timeout: Duration::from_secs(args.unlock_timeout),
+            ..UnlockWait::default()
+        },
+        transfer: transfer_options(&args.transfer, connections).await?,
+        state_dir: args.state_dir,
+        wallet_password: claim_wallet_password_from_env(),
+        cleanup: match args.delete_wallet_dir {
+            Some(wallet_dir) => WalletCleanup::SecureDelete { wallet_dir },
+            None => WalletCleanup::KeepEncrypted,
+        },
     };
-    let tx_hash = claim_xmr(&wallet, &request).await?;
+    let tx_hash = claim_xmr(&wallet, &request, |progress| {
+        println!("claim -> {progress:?}");
+    })
+    .await?;
     println!("xmr_claim_tx: {tx_hash}");
     Ok(())
 }
 
+async fn run_relay_xmr(args: RelayXmrArgs, connections: &XmrConnections) -> Result<()> {
+    validate_rpc_url("daemon", &args.daemon)?;
+    let sweep = PendingSweep::load(&args.input)?;
+    let daemon = connections.daemon(&args.daemon).await?;
+    for tx_hash in relay_exported_sweep(&daemon, &sweep).await? {
+        println!("xmr_claim_tx: {tx_hash}");
+    }
+    Ok(())
+}
+
+async fn run_view_wallet(args: ViewWalletArgs, connections: &XmrConnections) -> Result<()> {
+    validate_rpc_url("rpc", &args.rpc)?;
+    validate_rpc_url("daemon", &args.daemon)?;
+    let network = parse_network(&args.network)?;
+    let keys = lock_keys(&args.keys)?;
+    let db = JsonFileDb::new(&args.state_dir)?;
+    ensure_key_sum_lock(&db, &args.swap_id)?;
+    let wallet = connections.wallet(&args.rpc).await?;
+    let daemon = connections.daemon(&args.daemon).await?;
+    let restore_height = claim_restore_height(
+        &db,
+        &daemon,
+        &args.swap_id,
+        args.restore_height,
+        args.lock_time,
+    )
+    .await?;
+    let address = open_view_wallet(
+        &wallet,
+        &keys,
+        &args.swap_id,
+        network,
+        &args.wallet,
+        Some(restore_height.height),
+    )
+    .await?;
+    println!("view_wallet: {} ({address})", args.wallet);
+    Ok(())
+}
+
+async fn run_claim_wallet(args: ClaimWalletArgs, connections: &XmrConnections) -> Result<()> {
+    validate_rpc_url("rpc", &args.rpc)?;
+    let request = XmrSigningWalletRequest {
+        swap_id: args.keys.swap_id.clone(),
+        alice_partial: decode_hex_32(&args.keys.alice_partial, "alice_partial")?,
+        bob_partial: decode_hex_32(&args.keys.bob_partial, "bob_partial")?,
+        alice_share: decode_key_share(&args.keys.alice_share)?,
+        bob_share: decode_key_share(&args.keys.bob_share)?,
+        revealed_secret: decode_hex_32(&args.keys.secret, "secret")?,
+        view_key: joint_view_key(&args.keys.view_shares)?,
+        network: parse_network(&args.network)?,
+        wallet_filename: args.wallet,
+        wallet_password: claim_wallet_password_from_env(),
+    };
+    let wallet = connections.wallet(&args.rpc).await?;
+    let address = open_signing_wallet(&wallet, &request).await?;
+    println!("claim_wallet: {} ({address})", request.wallet_filename);
+    Ok(())
+}
+
+async fn run_sweep_unsigned(args: SweepUnsignedArgs, connections: &XmrConnections) -> Result<()> {
+    validate_rpc_url("rpc", &args.rpc)?;
+    validate_nonzero("amount", args.amount)?;
+    validate_destination_address(&args.destination)?;
+    let key_images = ColdSyncFile::load(&args.key_images)?;
+    let wallet = connections.wallet(&args.rpc).await?;
+    let unlock = UnlockWait {
+        expected_amount: args.amount,
+        timeout: Duration::from_secs(args.unlock_timeout),
+        ..UnlockWait::default()
+    };
+    export_sweep(
+        &wallet,
+        &key_images,
+        &args.destination,
+        &unlock,
+        &transfer_options(&args.transfer, connections).await?,
+        &args.output,
+        |progress| println!("sweep -> {progress:?}"),
+    )
+    .await?;
+    println!("unsigned_sweep: {}", args.output.display());
+    Ok(())
+}
+
+async fn run_sign_sweep(args: SignSweepArgs, connections: &XmrConnections) -> Result<()> {
+    validate_rpc_url("rpc", &args.rpc)?;
+    if let Some(max_fee) = args.max_fee {
+        validate_nonzero("max fee", max_fee)?;
+    }
+    let unsigned = UnsignedSweep::load(&args.input)?;
+    let wallet = connections.wallet(&args.rpc).await?;
+    // The offline host has no daemon to check the ring size against; the
+    // view-only wallet already built the set.
+    let options = TransferOptions {
+        max_fee: args.max_fee,
+        ..TransferOptions::default()
+    };
+    let sweep = sign_sweep(
+        &wallet,
+        &unsigned,
+        &args.destination,
+        &options,
+        &args.output,
+    )
+    .await?;
+    println!(
+        "signed_sweep: {} (amount {}, fee {})",
+        args.output.display(),
+        sweep.amount(),
+        sweep.fee()
+    );
+    Ok(())
+}
+
+async fn run_cold_export(args: ColdExportArgs, connections: &XmrConnections) -> Result<()> {
+    validate_rpc_url("rpc", &args.rpc)?;
+    let kind = parse_cold_sync_kind(&args.kind)?;
+    let wallet = connections.wallet(&args.rpc).await?;
+    export_cold_sync(&wallet, kind).await?.save(&args.output)?;
+    println!("cold_export: {}", args.output.display());
+    Ok(())
+}
+
+async fn run_cold_import(args: ColdImportArgs, connections: &XmrConnections) -> Result<()> {
+    validate_rpc_url("rpc", &args.rpc)?;
+    let file = ColdSyncFile::load(&args.input)?;
+    let wallet = connections.wallet(&args.rpc).await?;
+    let imported = import_cold_sync(&wallet, &file).await?;
+    println!("cold_import: {imported:?}");
+    Ok(())
+}
+
+async fn transfer_options(
+    args: &TransferArgs,
+    connections: &XmrConnections,
+) -> Result<TransferOptions> {
+    validate_rpc_url("daemon", &args.daemon)?;
+    if let Some(max_fee) = args.max_fee {
+        validate_nonzero("max fee", max_fee)?;
+    }
+    let options = TransferOptions {
+        priority: parse_priority(&args.priority)?,
+        ring_size: args.ring_size,
+        max_fee: args.max_fee,
+    };
+    let daemon = connections.daemon(&args.daemon).await?;
+    Ok(options.validate_with_daemon(&daemon).await?)
+}
+
+/// Claim wallet password from `XMR_CLAIM_WALLET_PASSWORD`, kept out of the
+/// command line so it does not show up in process listings.
+fn claim_wallet_password_from_env() -> Option<Zeroizing<String>> {
+    std::env::var("XMR_CLAIM_WALLET_PASSWORD")
+        .ok()
+        .filter(|value| !value.is_empty())
+        .map(Zeroizing::new)
+}
+
+/// Multisig wallet password from `XMR_MULTISIG_WALLET_PASSWORD`. The
+/// wallet holds a multisig key share, so the password is required.
+fn multisig_wallet_password_from_env() -> Result<Zeroizing<String>> {
+    std::env::var("XMR_MULTISIG_WALLET_PASSWORD")
+        .ok()
+        .filter(|value| !value.is_empty())
+        .map(Zeroizing::new)
+        .ok_or_else(|| anyhow!("set XMR_MULTISIG_WALLET_PASSWORD to encrypt the multisig wallet"))
+}
+
+async fn run_multisig_prepare(
+    args: MultisigPrepareArgs,
+    connections: &XmrConnections,
+) -> Result<()> {
+    validate_rpc_url("rpc", &args.rpc)?;
+    let password = multisig_wallet_password_from_env()?;
+    let wallet = connections.wallet(&args.rpc).await?;
+    let info = prepare_multisig_wallet(&wallet, &args.wallet, &password).await?;
+    println!("multisig_info: {info}");
+    Ok(())
+}
+
+async fn run_multisig_advance(
+    args: MultisigAdvanceArgs,
+    connections: &XmrConnections,
+) -> Result<()> {
+    validate_rpc_url("rpc", &args.rpc)?;
+    let network = parse_network(&args.network)?;
+    let password = multisig_wallet_password_from_env()?;
+    let wallet = connections.wallet(&args.rpc).await?;
+    let db = JsonFileDb::new(&args.state_dir)?;
+    let round = advance_swap_multisig(
+        &db,
+        &wallet,
+        &args.swap_id,
+        &args.peer_info,
+        &password,
+        network,
+    )
+    .await?;
+    if let Some(info) = round.info {
+        println!("multisig_info: {info}");
+    }
+    if let Some(address) = round.address {
+        println!("xmr_lock_address: {address}");
+    }
+    Ok(())
+}
+
+async fn run_multisig_sync(args: MultisigSyncArgs, connections: &XmrConnections) -> Result<()> {
+    validate_rpc_url("rpc", &args.rpc)?;
+    let wallet = connections.wallet(&args.rpc).await?;
+    let db = JsonFileDb::new(&args.state_dir)?;
+    open_multisig_wallet(&db, &wallet, &args.swap_id).await?;
+    println!(
+        "multisig_sync_info: {}",
+        export_multisig_sync(&wallet).await?
+    );
+    if let Some(peer_info) = &args.peer_info {
+        let imported = import_multisig_sync(&wallet, peer_info).await?;
+        println!("multisig_sync_imported: {imported}");
+    }
+    Ok(())
+}
+
+async fn run_multisig_propose(
+    args: MultisigProposeArgs,
+    connections: &XmrConnections,
+) -> Result<()> {
+    validate_rpc_url("rpc", &args.rpc)?;
+    validate_destination_address(&args.destination)?;
+    let wallet = connections.wallet(&args.rpc).await?;
+    let db = JsonFileDb::new(&args.state_dir)?;
+    open_multisig_wallet(&db, &wallet, &args.swap_id).await?;
+    let options = transfer_options(&args.transfer, connections).await?;
+    let txset = propose_multisig_sweep(&wallet, &args.destination, &options).await?;
+    txset.save(&args.output)?;
+    println!("multisig_proposed: {}", args.output.display());
+    Ok(())
+}
+
+async fn run_multisig_cosign(args: MultisigCosignArgs, connections: &XmrConnections) -> Result<()> {
+    validate_rpc_url("rpc", &args.rpc)?;
+    validate_destination_address(&args.destination)?;
+    let txset = MultisigTxSet::load(&args.input)?;
+    let wallet = connections.wallet(&args.rpc).await?;
+    let db = JsonFileDb::new(&args.state_dir)?;
+    open_multisig_wallet(&db, &wallet, &args.swap_id).await?;
+    let options = transfer_options(&args.transfer, connections).await?;
+    let signed = cosign_multisig_sweep(&wallet, &txset, &args.destination, &options).await?;
+    signed.save(&args.output)?;
+    println!("multisig_signed: {}", args.output.display());
+    Ok(())
+}
+
+async fn run_multisig_submit(args: MultisigSubmitArgs, connections: &XmrConnections) -> Result<()> {
+    validate_rpc_url("rpc", &args.rpc)?;
+    let txset = MultisigTxSet::load(&args.input)?;
+    let wallet = connections.wallet(&args.rpc).await?;
+    let db = JsonFileDb::new(&args.state_dir)?;
+    open_multisig_wallet(&db, &wallet, &args.swap_id).await?;
+    for tx_hash in submit_multisig_sweep(&wallet, &txset).await? {
+        println!("xmr_claim_tx: {tx_hash}");
+    }
+    Ok(())
+}
+
+fn run_key_share(args: KeyShareArgs) -> Result<()> {
+    let partial = SecretKey::from_bytes(decode_hex_32(&args.partial, "partial")?);
+    let share = create_key_share(&partial, args.swap_id.as_bytes())
+        .map_err(|err| anyhow!("create key share: {err}"))?;
+    println!("key_share: {}", hex_encode(&share.to_bytes()));
+    Ok(())
+}
+
 fn load_demo_vector(path: &PathBuf) -> Result<DemoVector> {
     let raw = std::fs::read_to_string(path)
         .with_context(|| format!("read vector {}", path.display()))?;
-    let demo: DemoVector = serde_json::from_str(&raw).context("parse demo vector")?;
-    Ok(demo)
+    let proof = DleqProof::from_json(&raw).context("parse demo vector")?;
+    let DemoSecret { secret } = serde_json::from_str(&raw).context("parse demo vector secret")?;
+    Ok(DemoVector { secret, proof })
 }
 
 fn validate_demo_vector(demo: &DemoVector) -> Result<()> {
-    let inputs = demo.vector.decode().context("decode vector")?;
+    let inputs = DleqInputs::from(&demo.proof);
     let (ok, report) = verify_dleq(&inputs).context("verify DLEQ")?;
     if !ok {
         return Err(anyhow!(
@@ -295,46 +1185,55 @@ fn validate_demo_vector(demo: &DemoVector) -> Result<()> {
         ));
     }
 
-    let secret = decode_hex_32(&demo.secret)?;
-    let computed_hashlock: [u8; 32] = Sha256::digest(secret).into();
-    let expected_hashlock = decode_hex_32(&demo.vector.hashlock)?;
-    if computed_hashlock != expected_hashlock {
+    let computed_hashlock: [u8; 32] = Sha256::digest(*demo.secret).into();
+    if demo.proof.hashlock != computed_hashlock {
         return Err(anyhow!("secret/hashlock mismatch"));
     }
     Ok(())
 }
 
-fn decode_hex_32(value: &str) -> Result<[u8; 32]> {
-    let bytes = decode_hex(value)?;
-    if bytes.len() != 32 {
-        return Err(anyhow!("expected 32-byte hex, got {}", bytes.len()));
+/// Sums the view-key shares: none for a legacy swap, otherwise exactly one
+/// from each party. A missing or repeated share would give a view key
+/// the other party does not hold.
+fn joint_view_key(shares: &[String]) -> Result<Option<[u8; 32]>> {
+    if shares.is_empty() {
+        return Ok(None);
+    }
+    let shares = shares
+        .iter()
+        .map(|share| Ok(decode_hex_32(share, "view_share")?))
+        .collect::<Result<Vec<_>>>()?;
+    match shares.as_slice() {
+        [first, second] if first == second => {
+            return Err(anyhow!("the two view-key shares are the same share"));
+        }
+        [_, _] => {}
+        _ => {
+            return Err(anyhow!(
+                "expected one view-key share from each party, got {}",
+                shares.len()
+            ));
+        }
     }
-    let mut out = [0u8; 32];
-    out.copy_from_slice(&bytes);
-    Ok(out)
+    let shares: Vec<SecretKey> = shares.into_iter().map(SecretKey::from_bytes).collect();
+    let view_key = combine_view_key_shares(&shares)
+        .map_err(|err| anyhow!("invalid view-key shares: {err}"))?;
+    Ok(Some(view_key.to_bytes()))
 }
 
-fn decode_hex(value: &str) -> Result<Vec<u8>> {
-    if value.len() % 2 != 0 {
-        return Err(anyhow!("hex string has odd length"));
-    }
-    let mut out = Vec::with_capacity(value.len() / 2);
-    let mut iter = value.as_bytes().chunks_exact(2);
-    while let Some(pair) = iter.next() {
-        let hi = from_hex_digit(pair[0])?;
-        let lo = from_hex_digit(pair[1])?;
-        out.push((hi << 4) | lo);
-    }
-    Ok(out)
+fn lock_keys(args: &LockKeyArgs) -> Result<LockKeys> {
+    Ok(LockKeys {
+        alice_share: decode_key_share(&args.alice_share)?,
+        bob_share: decode_key_share(&args.bob_share)?,
+        adaptor_point: decode_hex_32(&args.adaptor_point, "adaptor_point")?,
+        view_key: joint_view_key(&args.view_shares)?
+            .ok_or_else(|| anyhow!("both parties' view-key shares are required"))?,
+    })
 }
 
-fn from_hex_digit(byte: u8) -> Result<u8> {
-    match byte {
-        b'0'..=b'9' => Ok(byte - b'0'),
-        b'a'..=b'f' => Ok(byte - b'a' + 10),
-        b'A'..=b'F' => Ok(byte - b'A' + 10),
-        _ => Err(anyhow!("invalid hex digit: 0x{byte:02x}")),
-    }
+fn decode_key_share(value: &str) -> Result<KeyShare> {
+    let bytes: [u8; KEY_SHARE_LEN] = decode_hex_array(value, "key_share")?;
+    KeyShare::from_bytes(&bytes).map_err(|err| anyhow!("invalid key share: {err}"))
 }
 
 fn generate_swap_id() -> String {
@@ -1,37 +1,444 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
 use anyhow::{anyhow, Result};
+use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
 use curve25519_dalek::scalar::Scalar;
-use monero::Network;
+use monero::{Address, Network};
 use zeroize::Zeroizing;
 
-use xmr_wallet::{execute_claim, ClaimParams, XmrWallet};
+use xmr_wallet::{
+    advance_multisig, derive_lock_address, execute_claim_with_progress, lock_xmr,
+    open_claim_wallet, resolve_restore_height, verify_key_share, ClaimKeys, ClaimParams,
+    ClaimProgress, ColdSyncKind, KeyShare, LockBackend, LockMechanism, LockMechanismStore,
+    LockReceipt, LockWatcher, MultisigRound, PublicKey, RestoreHeight, RestoreHeightStore,
+    RetryConfig, SecretKey, TransferOptions, TransferPriority, UnlockWait, ViewKeyScanner,
+    WalletCleanup, WalletConnectionConfig, WatcherConfig, WatcherEvent, WatcherStore, XmrDaemon,
+    XmrWallet, DEFAULT_RESTORE_MARGIN,
+};
+
+/// Where wallet-rpc and monerod connection settings (login, pinned
+/// certificates, timeout) come from: the JSON file when one is given,
+/// otherwise the `XMR_WALLET_RPC_*` and `XMR_DAEMON_RPC_*` environment.
+#[derive(Debug, Clone, Default)]
+pub struct XmrConnections {
+    pub wallet_config: Option<PathBuf>,
+    pub daemon_config: Option<PathBuf>,
+}
+
+impl XmrConnections {
+    pub async fn wallet(&self, url: &str) -> Result<XmrWallet> {
+        let connection = match &self.wallet_config {
+            Some(path) => WalletConnectionConfig::from_file(path)?,
+            None => WalletConnectionConfig::from_env()?,
+        };
+        Ok(XmrWallet::connect_with_connection(url, RetryConfig::from_env(), &connection).await?)
+    }
+
+    pub async fn daemon(&self, url: &str) -> Result<XmrDaemon> {
+        let connection = match &self.daemon_config {
+            Some(path) => WalletConnectionConfig::from_file(path)?,
+            None => WalletConnectionConfig::daemon_from_env()?,
+        };
+        Ok(XmrDaemon::connect_with_connection(url, RetryConfig::from_env(), &connection).await?)
+    }
+}
+
+/// Public inputs of the key-sum lock address: both parties' key shares,
+/// the adaptor point `T` from the Solana lock and the joint view key.
+pub struct LockKeys {
+    pub alice_share: KeyShare,
+    pub bob_share: KeyShare,
+    pub adaptor_point: [u8; 32],
+    pub view_key: [u8; 32],
+}
+
+impl LockKeys {
+    /// Derives the joint address `A + B + T`. Both shares must carry a
+    /// valid proof of knowledge for `swap_id`, so neither party can make the
+    /// other fund or watch an address it controls alone.
+    pub fn address(&self, swap_id: &str, network: Network) -> Result<Address> {
+        let shares = [("alice", &self.alice_share), ("bob", &self.bob_share)]
+            .into_iter()
+            .map(|(party, share)| {
+                verify_key_share(share, swap_id.as_bytes())
+                    .map_err(|err| anyhow!("{party} key share rejected: {err}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let adaptor_point = PublicKey::from_compressed(self.adaptor_point)
+            .map_err(|err| anyhow!("invalid adaptor point: {err}"))?;
+        let view_key = SecretKey::from_bytes(self.view_key);
+        derive_lock_address(&shares, &adaptor_point, &view_key, network)
+            .map_err(|err| anyhow!("derive joint address: {err}"))
+    }
+}
+
+pub struct XmrLockRequest {
+    pub swap_id: String,
+    /// Joint address from [`swap_lock_address`].
+    pub address: Address,
+    pub amount: u64,
+    pub transfer: TransferOptions,
+    pub state_dir: PathBuf,
+}
+
+/// Sends the XMR lock to `req.address` and stores its receipt next to the
+/// swap state so the tx key can later be handed to the counterparty.
+pub async fn lock_xmr_for_swap(wallet: &XmrWallet, req: &XmrLockRequest) -> Result<LockReceipt> {
+    std::fs::create_dir_all(&req.state_dir)?;
+    let path = lock_receipt_path(&req.state_dir, &req.swap_id);
+    lock_xmr(wallet, req.amount, &req.address, &req.transfer, &path)
+        .await
+        .map_err(|err| anyhow!(err))
+}
+
+/// The joint address the swap's XMR is locked to, following the lock
+/// mechanism recorded for it. A multisig swap uses the address its key
+/// exchange produced and takes no key shares. Any other swap needs `keys`
+/// and is recorded as key-sum, so it cannot later be switched to multisig.
+pub fn swap_lock_address<S: LockMechanismStore>(
+    store: &S,
+    swap_id: &str,
+    keys: Option<&LockKeys>,
+    network: Network,
+) -> Result<Address> {
+    let address = match (LockMechanism::load(store, swap_id)?, keys) {
+        (LockMechanism::Multisig { address }, None) => Address::from_str(&address)
+            .map_err(|err| anyhow!("invalid multisig address: {err}"))?,
+        (LockMechanism::Multisig { .. }, Some(_)) => {
+            return Err(anyhow!(
+                "swap {swap_id} is locked with 2-of-2 multisig and takes no key shares"
+            ))
+        }
+        (LockMechanism::KeySum, Some(keys)) => {
+            let address = keys.address(swap_id, network)?;
+            LockMechanism::KeySum.record(store, swap_id)?;
+            address
+        }
+        (LockMechanism::KeySum, None) => {
+            return Err(anyhow!(
+                "swap {swap_id} has a key-sum lock: the key shares, adaptor point and view shares are required"
+            ))
+        }
+    };
+    if address.network != network {
+        return Err(anyhow!(
+            "joint address is for {:?}, expected {network:?}",
+            address.network
+        ));
+    }
+    Ok(address)
+}
+
+/// Refuses to claim a multisig swap with the key-sum claim key; its XMR
+/// moves only through the multisig sweep.
+pub fn ensure_key_sum_lock<S: LockMechanismStore>(store: &S, swap_id: &str) -> Result<()> {
+    match LockMechanism::load(store, swap_id)? {
+        LockMechanism::KeySum => Ok(()),
+        LockMechanism::Multisig { .. } => Err(anyhow!(
+            "swap {swap_id} is locked with 2-of-2 multisig; spend it with multisig-propose and multisig-cosign"
+        )),
+    }
+}
+
+/// Checks that `swap_id` is a multisig swap and that `wallet` has its
+/// multisig wallet open, returning the joint address.
+pub async fn open_multisig_wallet<S: LockMechanismStore>(
+    store: &S,
+    wallet: &XmrWallet,
+    swap_id: &str,
+) -> Result<String> {
+    let LockMechanism::Multisig { address } = LockMechanism::load(store, swap_id)? else {
+        return Err(anyhow!(
+            "swap {swap_id} has no multisig lock; set one up with multisig-prepare and multisig-advance"
+        ));
+    };
+    let open = wallet.get_primary_address().await?;
+    if open != address {
+        return Err(anyhow!(
+            "open wallet holds {open}, not the multisig address {address} of swap {swap_id}"
+        ));
+    }
+    Ok(address)
+}
+
+/// Feeds the counterparty's latest key exchange message to the open
+/// multisig wallet. Once the exchange completes, the joint address is
+/// recorded as the swap's lock.
+pub async fn advance_swap_multisig<S: LockMechanismStore>(
+    store: &S,
+    wallet: &XmrWallet,
+    swap_id: &str,
+    peer_info: &str,
+    password: &str,
+    network: Network,
+) -> Result<MultisigRound> {
+    if let Some(recorded) = store.load_lock_mechanism(swap_id)? {
+        return Err(anyhow!(
+            "swap {swap_id} already has a {} lock",
+            recorded.name()
+        ));
+    }
+    let round = advance_multisig(wallet, peer_info, password).await?;
+    if let Some(address) = &round.address {
+        let parsed =
+            Address::from_str(address).map_err(|err| anyhow!("invalid multisig address: {err}"))?;
+        if parsed.network != network {
+            return Err(anyhow!(
+                "multisig address is for {:?}, expected {network:?}",
+                parsed.network
+            ));
+        }
+        LockMechanism::Multisig {
+            address: address.clone(),
+        }
+        .record(store, swap_id)?;
+    }
+    Ok(round)
+}
+
+pub struct XmrWatchRequest {
+    pub swap_id: String,
+    pub tx_id: String,
+    /// Sender's tx key, for backends that check the lock with it.
+    pub tx_key: Option<String>,
+    pub joint_address: Address,
+    pub expected_amount: u64,
+    pub config: WatcherConfig,
+    pub poll_interval: Duration,
+}
+
+/// Scanner for the joint lock address, used by `watch-xmr --scan` to follow
+/// the lock from the daemon alone, without wallet-rpc or the tx key.
+pub fn lock_view_scanner(joint_address: &Address, keys: &LockKeys) -> Result<ViewKeyScanner> {
+    let view_key = SecretKey::from_bytes(keys.view_key);
+    ViewKeyScanner::new(joint_address, &view_key).map_err(|err| anyhow!(err))
+}
+
+/// Polls the counterparty's lock until it is confirmed, checking it through
+/// `backend`. Watcher progress is saved to `store` after every poll, so a
+/// restarted coordinator resumes with its confirmation count and block
+/// hashes intact. Once confirmed, the claim wallet's restore height is
+/// derived from the lock height and stored too.
+pub async fn watch_xmr_lock<B: LockBackend, S: WatcherStore + RestoreHeightStore>(
+    backend: &B,
+    daemon: &XmrDaemon,
+    store: &S,
+    req: &XmrWatchRequest,
+    mut on_event: impl FnMut(&WatcherEvent),
+) -> Result<WatcherEvent> {
+    let address = &req.joint_address;
+
+    let mut watcher = LockWatcher::load(req.config, store, &req.swap_id)?;
+    loop {
+        let event = watcher
+            .poll_for_lock(
+                backend,
+                daemon,
+                &req.tx_id,
+                req.tx_key.as_deref(),
+                address,
+                req.expected_amount,
+            )
+            .await?;
+        watcher.save(store, &req.swap_id)?;
+        if let Some(event) = event {
+            on_event(&event);
+            match event {
+                WatcherEvent::Confirmed { .. } => {
+                    resolve_restore_height(
+                        store,
+                        daemon,
+                        &req.swap_id,
+                        None,
+                        DEFAULT_RESTORE_MARGIN,
+                    )
+                    .await?;
+                    return Ok(event);
+                }
+                WatcherEvent::Underpaid { expected, received } => {
+                    return Err(anyhow!(
+                        "lock underpaid: expected {expected}, received {received}"
+                    ))
+                }
+                _ => {}
+            }
+        }
+        tokio::time::sleep(req.poll_interval).await;
+    }
+}
+
+pub fn lock_receipt_path(state_dir: &Path, swap_id: &str) -> PathBuf {
+    state_dir.join(format!("{swap_id}.xmr_lock.json"))
+}
+
+pub fn claim_journal_path(state_dir: &Path, swap_id: &str) -> PathBuf {
+    state_dir.join(format!("{swap_id}.xmr_claim.json"))
+}
+
+/// Restore height for the claim wallet. A height given on the command line
+/// replaces the stored one; otherwise the stored height, the watcher's lock
+/// height or an estimate from `lock_time` is used, and persisted.
+pub async fn claim_restore_height<S: WatcherStore + RestoreHeightStore>(
+    store: &S,
+    daemon: &XmrDaemon,
+    swap_id: &str,
+    manual: Option<u64>,
+    lock_time: Option<u64>,
+) -> Result<RestoreHeight> {
+    if let Some(height) = manual {
+        let height = RestoreHeight::manual(height);
+        store.save_restore_height(swap_id, &height)?;
+        return Ok(height);
+    }
+    Ok(resolve_restore_height(store, daemon, swap_id, lock_time, DEFAULT_RESTORE_MARGIN).await?)
+}
 
 pub struct XmrClaimRequest {
+    pub swap_id: String,
     pub alice_partial: [u8; 32],
     pub bob_partial: [u8; 32],
+    pub alice_share: KeyShare,
+    pub bob_share: KeyShare,
     pub revealed_secret: [u8; 32],
+    pub view_key: Option<[u8; 32]>,
     pub destination_address: String,
     pub wallet_filename: String,
     pub network: Network,
     pub restore_height: Option<u64>,
+    pub unlock: UnlockWait,
+    pub transfer: TransferOptions,
+    pub state_dir: PathBuf,
+    /// Claim wallet password; `None` derives one from the claim keys.
+    pub wallet_password: Option<Zeroizing<String>>,
+    pub cleanup: WalletCleanup,
 }
 
-pub async fn claim_xmr(wallet: &XmrWallet, req: &XmrClaimRequest) -> Result<String> {
+pub async fn claim_xmr(
+    wallet: &XmrWallet,
+    req: &XmrClaimRequest,
+    on_progress: impl FnMut(ClaimProgress),
+) -> Result<String> {
+    let alice_partial = Scalar::from_bytes_mod_order(req.alice_partial);
+    let bob_partial = Scalar::from_bytes_mod_order(req.bob_partial);
+    verify_partial_share("alice", &req.swap_id, &alice_partial, &req.alice_share)?;
+    verify_partial_share("bob", &req.swap_id, &bob_partial, &req.bob_share)?;
+    std::fs::create_dir_all(&req.state_dir)?;
+
     let params = ClaimParams {
-        alice_partial: Scalar::from_bytes_mod_order(req.alice_partial),
-        bob_partial: Scalar::from_bytes_mod_order(req.bob_partial),
+        alice_partial,
+        bob_partial,
         revealed_secret: Zeroizing::new(req.revealed_secret),
+        view_key: req.view_key.map(Scalar::from_bytes_mod_order),
         destination_address: req.destination_address.clone(),
         network: req.network,
         wallet_filename: req.wallet_filename.clone(),
         restore_height: req.restore_height,
         expected_hashlock: None,
-        claim_guard_path: None,
+        journal_path: Some(claim_journal_path(&req.state_dir, &req.swap_id)),
+        unlock: req.unlock,
+        transfer: req.transfer,
+        wallet_password: req.wallet_password.clone(),
+        cleanup: req.cleanup.clone(),
     };
-    execute_claim(wallet, &params)
+    execute_claim_with_progress(wallet, &params, on_progress)
         .await
         .map_err(|err| anyhow!(err))
 }
 
+/// Claim keys for the offline signing wallet of a cold claim. The
+/// partials are checked against the published key shares as in
+/// [`claim_xmr`].
+pub struct XmrSigningWalletRequest {
+    pub swap_id: String,
+    pub alice_partial: [u8; 32],
+    pub bob_partial: [u8; 32],
+    pub alice_share: KeyShare,
+    pub bob_share: KeyShare,
+    pub revealed_secret: [u8; 32],
+    pub view_key: Option<[u8; 32]>,
+    pub network: Network,
+    pub wallet_filename: String,
+    /// Wallet password; `None` derives one from the claim keys.
+    pub wallet_password: Option<Zeroizing<String>>,
+}
+
+/// Creates (or reopens) the spendable claim wallet on the offline host and
+/// returns its address. Nothing is synced: the wallet learns its outputs
+/// from the view-only wallet's `cold-export`.
+pub async fn open_signing_wallet(
+    wallet: &XmrWallet,
+    req: &XmrSigningWalletRequest,
+) -> Result<Address> {
+    let alice_partial = Scalar::from_bytes_mod_order(req.alice_partial);
+    let bob_partial = Scalar::from_bytes_mod_order(req.bob_partial);
+    verify_partial_share("alice", &req.swap_id, &alice_partial, &req.alice_share)?;
+    verify_partial_share("bob", &req.swap_id, &bob_partial, &req.bob_share)?;
+    let keys = ClaimKeys::derive(
+        &alice_partial,
+        &bob_partial,
+        &Scalar::from_bytes_mod_order(req.revealed_secret),
+        req.view_key.map(Scalar::from_bytes_mod_order),
+        req.network,
+    )?;
+    let password = match &req.wallet_password {
+        Some(password) if password.is_empty() => {
+            return Err(anyhow!("claim wallet password must not be empty"))
+        }
+        Some(password) => password.clone(),
+        None => keys.wallet_password(),
+    };
+    open_claim_wallet(wallet, &keys, &req.wallet_filename, &password, None).await?;
+    Ok(keys.address)
+}
+
+/// Creates (or reopens) the view-only wallet on the joint lock address for
+/// the online half of a cold claim, and syncs it from `restore_height`.
+pub async fn open_view_wallet(
+    wallet: &XmrWallet,
+    keys: &LockKeys,
+    swap_id: &str,
+    network: Network,
+    filename: &str,
+    restore_height: Option<u64>,
+) -> Result<Address> {
+    let address = keys.address(swap_id, network)?;
+    let lock_address = address.to_string();
+    match wallet
+        .generate_view_only(keys.view_key, &lock_address, filename, restore_height)
+        .await
+    {
+        Ok(()) => {}
+        // A rerun finds the wallet the first run created.
+        Err(err) if err.is_wallet_exists() => {
+            wallet.open_view_only(filename, &lock_address).await?
+        }
+        Err(err) => return Err(err.into()),
+    }
+    wallet.refresh(restore_height).await?;
+    Ok(address)
+}
+
+/// Refuses a partial key unless its published share carries a valid proof of
+/// knowledge for this swap and matches the partial being claimed with.
+pub fn verify_partial_share(
+    party: &str,
+    swap_id: &str,
+    partial: &Scalar,
+    share: &KeyShare,
+) -> Result<()> {
+    let verified = verify_key_share(share, swap_id.as_bytes())
+        .map_err(|err| anyhow!("{party} key share rejected: {err}"))?;
+    let expected = (ED25519_BASEPOINT_POINT * partial).compress().to_bytes();
+    if verified.public().to_compressed() != expected {
+        return Err(anyhow!(
+            "{party} partial does not match its verified key share"
+        ));
+    }
+    Ok(())
+}
+
 pub fn parse_network(value: &str) -> Result<Network> {
     match value.to_lowercase().as_str() {
         "mainnet" => Ok(Network::Mainnet),
@@ -40,3 +447,25 @@ pub fn parse_network(value: &str) -> Result<Network> {
         _ => Err(anyhow!("invalid network (expected mainnet/testnet/stagenet)")),
     }
 }
+
+pub fn parse_cold_sync_kind(value: &str) -> Result<ColdSyncKind> {
+    match value.to_lowercase().as_str() {
+        "outputs" => Ok(ColdSyncKind::Outputs),
+        "key-images" | "key_images" => Ok(ColdSyncKind::KeyImages),
+        _ => Err(anyhow!(
+            "invalid cold sync kind (expected outputs/key-images)"
+        )),
+    }
+}
+
+pub fn parse_priority(value: &str) -> Result<TransferPriority> {
+    match value.to_lowercase().as_str() {
+        "default" => Ok(TransferPriority::Default),
+        "unimportant" => Ok(TransferPriority::Unimportant),
+        "elevated" => Ok(TransferPriority::Elevated),
+        "priority" => Ok(TransferPriority::Priority),
+        _ => Err(anyhow!(
+            "invalid priority (expected default/unimportant/elevated/priority)"
+        )),
+    }
+}
@@ -0,0 +1,66 @@
+use swap_coordinator::db::JsonFileDb;
+use xmr_wallet::{
+    LockMechanism, LockMechanismStore, LockWatcher, RestoreHeight, RestoreHeightStore, TxKeyCheck,
+    WatcherConfig, WatcherEvent, WatcherStore,
+};
+
+#[test]
+fn watcher_state_survives_restart() {
+    let dir = std::env::temp_dir().join(format!("swap_db_watcher_{}", std::process::id()));
+    let db = JsonFileDb::new(&dir).expect("db");
+    assert!(db.load_watcher_state("swap-1").unwrap().is_none());
+
+    let config = WatcherConfig::default();
+    let mut watcher = LockWatcher::new(config);
+    watcher.update_height(120);
+    watcher.apply_tx_key_check(
+        TxKeyCheck {
+            received: 5_000,
+            confirmations: 4,
+            in_pool: false,
+        },
+        120,
+        5_000,
+    );
+    watcher.save(&db, "swap-1").expect("save");
+
+    let resumed = LockWatcher::load(config, &db, "swap-1").expect("load");
+    assert_eq!(resumed.state(), watcher.state());
+    assert_eq!(
+        resumed.evaluate(126),
+        WatcherEvent::Confirmed {
+            observed_height: 116,
+            confirmations: 10,
+            amount: 5_000,
+        }
+    );
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn restore_height_is_kept_per_swap() {
+    let dir = std::env::temp_dir().join(format!("swap_db_restore_{}", std::process::id()));
+    let db = JsonFileDb::new(&dir).expect("db");
+    assert!(db.load_restore_height("swap-1").unwrap().is_none());
+
+    let height = RestoreHeight::from_lock_height(2_000, 720);
+    db.save_restore_height("swap-1", &height).expect("save");
+    assert_eq!(db.load_restore_height("swap-1").unwrap(), Some(height));
+    assert!(db.load_restore_height("swap-2").unwrap().is_none());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn lock_mechanism_is_kept_per_swap() {
+    let dir = std::env::temp_dir().join(format!("swap_db_mechanism_{}", std::process::id()));
+    let db = JsonFileDb::new(&dir).expect("db");
+    assert!(db.load_lock_mechanism("swap-1").unwrap().is_none());
+
+    let mechanism = LockMechanism::Multisig {
+        address: "5joint".to_string(),
+    };
+    db.save_lock_mechanism("swap-1", &mechanism).expect("save");
+    assert_eq!(db.load_lock_mechanism("swap-1").unwrap(), Some(mechanism));
+    assert!(db.load_lock_mechanism("swap-2").unwrap().is_none());
+    let _ = std::fs::remove_dir_all(&dir);
+}
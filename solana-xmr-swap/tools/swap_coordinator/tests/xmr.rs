@@ -1,4 +1,16 @@
-use swap_coordinator::xmr::parse_network;
+use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+use curve25519_dalek::scalar::Scalar;
+use monero::Network;
+use swap_coordinator::db::JsonFileDb;
+use swap_coordinator::xmr::{
+    ensure_key_sum_lock, parse_cold_sync_kind, parse_network, parse_priority, swap_lock_address,
+    verify_partial_share, LockKeys,
+};
+use xmr_wallet::{
+    create_key_share, derive_claim_keys, ClaimParams, ColdSyncKind, KeyShare, LockMechanism,
+    LockMechanismStore, SecretKey, TransferOptions, UnlockWait, WalletCleanup,
+};
+use zeroize::Zeroizing;
 
 #[test]
 fn parses_valid_networks() {
@@ -11,3 +23,132 @@ fn parses_valid_networks() {
 fn rejects_invalid_network() {
     assert!(parse_network("invalid").is_err());
 }
+
+#[test]
+fn parses_transfer_priorities() {
+    for value in ["default", "unimportant", "elevated", "Priority"] {
+        assert!(parse_priority(value).is_ok(), "{value}");
+    }
+    assert!(parse_priority("urgent").is_err());
+}
+
+#[test]
+fn parses_cold_sync_kinds() {
+    assert_eq!(
+        parse_cold_sync_kind("outputs").unwrap(),
+        ColdSyncKind::Outputs
+    );
+    assert_eq!(
+        parse_cold_sync_kind("key-images").unwrap(),
+        ColdSyncKind::KeyImages
+    );
+    assert!(parse_cold_sync_kind("wallet").is_err());
+}
+
+fn share_for(partial: Scalar, swap_id: &str) -> KeyShare {
+    create_key_share(&SecretKey::from_scalar(partial), swap_id.as_bytes()).unwrap()
+}
+
+#[test]
+fn accepts_partial_matching_verified_share() {
+    let partial = Scalar::from(42u64);
+    let share = share_for(partial, "swap-1");
+    assert!(verify_partial_share("alice", "swap-1", &partial, &share).is_ok());
+}
+
+#[test]
+fn refuses_share_proven_for_another_swap() {
+    let partial = Scalar::from(42u64);
+    let share = share_for(partial, "swap-2");
+    assert!(verify_partial_share("alice", "swap-1", &partial, &share).is_err());
+}
+
+#[test]
+fn refuses_partial_not_matching_share() {
+    let share = share_for(Scalar::from(42u64), "swap-1");
+    let err = verify_partial_share("bob", "swap-1", &Scalar::from(43u64), &share).unwrap_err();
+    assert!(err.to_string().contains("bob partial"));
+}
+
+fn lock_keys(alice: Scalar, bob: Scalar, secret: Scalar, swap_id: &str) -> LockKeys {
+    LockKeys {
+        alice_share: share_for(alice, swap_id),
+        bob_share: share_for(bob, swap_id),
+        adaptor_point: (ED25519_BASEPOINT_POINT * secret).compress().to_bytes(),
+        view_key: Scalar::from(7u64).to_bytes(),
+    }
+}
+
+#[test]
+fn lock_keys_derive_the_claim_address() {
+    let (alice, bob, secret) = (Scalar::from(3u64), Scalar::from(5u64), Scalar::from(11u64));
+    let address = lock_keys(alice, bob, secret, "swap-1")
+        .address("swap-1", Network::Stagenet)
+        .unwrap();
+
+    let keys = derive_claim_keys(&ClaimParams {
+        alice_partial: alice,
+        bob_partial: bob,
+        revealed_secret: Zeroizing::new(secret.to_bytes()),
+        view_key: Some(Scalar::from(7u64)),
+        destination_address: String::new(),
+        network: Network::Stagenet,
+        wallet_filename: String::new(),
+        restore_height: None,
+        expected_hashlock: None,
+        journal_path: None,
+        unlock: UnlockWait::default(),
+        transfer: TransferOptions::default(),
+        wallet_password: None,
+        cleanup: WalletCleanup::KeepEncrypted,
+    })
+    .unwrap();
+    assert_eq!(address, keys.address);
+}
+
+#[test]
+fn lock_keys_refuse_shares_proven_for_another_swap() {
+    let keys = lock_keys(
+        Scalar::from(3u64),
+        Scalar::from(5u64),
+        Scalar::from(11u64),
+        "swap-2",
+    );
+    let err = keys.address("swap-1", Network::Stagenet).unwrap_err();
+    assert!(err.to_string().contains("alice key share"));
+}
+
+#[test]
+fn swap_lock_address_follows_the_recorded_mechanism() {
+    let dir = std::env::temp_dir().join(format!("swap_xmr_mechanism_{}", std::process::id()));
+    let db = JsonFileDb::new(&dir).expect("db");
+    let keys = lock_keys(
+        Scalar::from(3u64),
+        Scalar::from(5u64),
+        Scalar::from(11u64),
+        "swap-1",
+    );
+    let key_sum = keys.address("swap-1", Network::Stagenet).unwrap();
+
+    assert!(swap_lock_address(&db, "swap-1", None, Network::Stagenet).is_err());
+    let address = swap_lock_address(&db, "swap-1", Some(&keys), Network::Stagenet).unwrap();
+    assert_eq!(address, key_sum);
+    assert_eq!(
+        db.load_lock_mechanism("swap-1").unwrap(),
+        Some(LockMechanism::KeySum)
+    );
+    ensure_key_sum_lock(&db, "swap-1").unwrap();
+
+    LockMechanism::Multisig {
+        address: key_sum.to_string(),
+    }
+    .record(&db, "swap-2")
+    .unwrap();
+    let address = swap_lock_address(&db, "swap-2", None, Network::Stagenet).unwrap();
+    assert_eq!(address, key_sum);
+    assert!(swap_lock_address(&db, "swap-2", Some(&keys), Network::Stagenet).is_err());
+    assert!(swap_lock_address(&db, "swap-2", None, Network::Mainnet).is_err());
+    let err = ensure_key_sum_lock(&db, "swap-2").unwrap_err();
+    assert!(err.to_string().contains("multisig"), "{err}");
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -1,8 +1,11 @@
-use anyhow::{anyhow, Context, Result};
+use anyhow::{Context, Result};
+use dleq_verifier::{decode_hex_32, decode_hex_array};
 use ed25519_dalek::{Signature, Verifier, VerifyingKey};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
+pub use dleq_verifier::hex_encode;
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct SignedAuditLog {
     pub payload: AuditLogPayload,
@@ -57,8 +60,8 @@ pub fn verify_signature(
     signature_hex: &str,
     public_key_hex: &str,
 ) -> Result<()> {
-    let signature_bytes = decode_hex_64(signature_hex)?;
-    let public_key_bytes = decode_hex_32(public_key_hex)?;
+    let signature_bytes = decode_hex_array::<64>(signature_hex, "signature")?;
+    let public_key_bytes = decode_hex_32(public_key_hex, "public_key")?;
     let signature = Signature::from_bytes(&signature_bytes);
     let verifying_key =
         VerifyingKey::from_bytes(&public_key_bytes).context("invalid public key")?;
@@ -68,59 +71,6 @@ pub fn verify_signature(
         .context("signature verification failed")
 }
 
-pub fn hex_encode(bytes: &[u8]) -> String {
-    const HEX: &[u8; 16] = b"0123456789abcdef";
-    let mut out = String::with_capacity(bytes.len() * 2);
-    for byte in bytes {
-        out.push(HEX[(byte >> 4) as usize] as char);
-        out.push(HEX[(byte & 0x0f) as usize] as char);
-    }
-    out
-}
-
-pub fn decode_hex_32(value: &str) -> Result<[u8; 32]> {
-    let bytes = decode_hex(value)?;
-    if bytes.len() != 32 {
-        return Err(anyhow!("expected 32-byte hex, got {}", bytes.len()));
-    }
-    let mut out = [0u8; 32];
-    out.copy_from_slice(&bytes);
-    Ok(out)
-}
-
-pub fn decode_hex_64(value: &str) -> Result<[u8; 64]> {
-    let bytes = decode_hex(value)?;
-    if bytes.len() != 64 {
-        return Err(anyhow!("expected 64-byte hex, got {}", bytes.len()));
-    }
-    let mut out = [0u8; 64];
-    out.copy_from_slice(&bytes);
-    Ok(out)
-}
-
-pub fn decode_hex(value: &str) -> Result<Vec<u8>> {
-    if value.len() % 2 != 0 {
-        return Err(anyhow!("hex string has odd length"));
-    }
-    let mut out = Vec::with_capacity(value.len() / 2);
-    let mut iter = value.as_bytes().chunks_exact(2);
-    while let Some(pair) = iter.next() {
-        let hi = from_hex_digit(pair[0])?;
-        let lo = from_hex_digit(pair[1])?;
-        out.push((hi << 4) | lo);
-    }
-    Ok(out)
-}
-
-fn from_hex_digit(byte: u8) -> Result<u8> {
-    match byte {
-        b'0'..=b'9' => Ok(byte - b'0'),
-        b'a'..=b'f' => Ok(byte - b'a' + 10),
-        b'A'..=b'F' => Ok(byte - b'A' + 10),
-        _ => Err(anyhow!("invalid hex digit: 0x{byte:02x}")),
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
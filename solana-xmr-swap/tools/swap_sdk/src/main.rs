@@ -4,11 +4,10 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use anyhow::{anyhow, Context, Result};
 use clap::Parser;
-use dleq_verifier::{verify_dleq, DleqReport, DleqVector};
+use dleq_verifier::{decode_hex_32, verify_dleq, DleqInputs, DleqProof, DleqReport};
 use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
 use swap_sdk::{
-    decode_hex_32, hex_encode, payload_hash, AuditLogPayload, ReportView, SignedAuditLog,
-    TranscriptView,
+    hex_encode, payload_hash, AuditLogPayload, ReportView, SignedAuditLog, TranscriptView,
 };
 
 #[derive(Parser)]
@@ -42,9 +41,8 @@ fn main() -> Result<()> {
         .unwrap_or_else(|| PathBuf::from("test_vectors/dleq.json"));
     let raw = fs::read_to_string(&input_path)
         .with_context(|| format!("read {}", input_path.display()))?;
-    let vector: DleqVector =
-        serde_json::from_str(&raw).context("parse DLEQ vector JSON")?;
-    let inputs = vector.decode().context("decode DLEQ vector")?;
+    let proof = DleqProof::from_json(&raw).context("parse DLEQ proof JSON")?;
+    let inputs = DleqInputs::from(&proof);
 
     let (ok, report) = verify_dleq(&inputs).context("verify DLEQ")?;
     let payload = AuditLogPayload {
@@ -52,7 +50,7 @@ fn main() -> Result<()> {
         input_path: input_path.display().to_string(),
         ok,
         report: report_view(&report),
-        transcript: transcript_view(&vector),
+        transcript: transcript_view(&proof),
     };
 
     let (signature, public_key) = sign_payload(&payload, &args)?;
@@ -97,16 +95,16 @@ fn report_view(report: &DleqReport) -> ReportView {
     }
 }
 
-fn transcript_view(vector: &DleqVector) -> TranscriptView {
+fn transcript_view(proof: &DleqProof) -> TranscriptView {
     TranscriptView {
-        adaptor_point: vector.adaptor_point_compressed.clone(),
-        second_point: vector.dleq_second_point_compressed.clone(),
-        y_point: vector.y_compressed.clone(),
-        r1: vector.r1_compressed.clone(),
-        r2: vector.r2_compressed.clone(),
-        challenge: vector.challenge.clone(),
-        response: vector.response.clone(),
-        hashlock: vector.hashlock.clone(),
+        adaptor_point: proof.adaptor_point.to_string(),
+        second_point: proof.second_point.to_string(),
+        y_point: proof.y_point.to_string(),
+        r1: proof.r1.to_string(),
+        r2: proof.r2.to_string(),
+        challenge: proof.challenge.to_string(),
+        response: proof.response.to_string(),
+        hashlock: proof.hashlock.to_string(),
     }
 }
 
@@ -137,7 +135,7 @@ fn sign_payload(payload: &AuditLogPayload, args: &Cli) -> Result<(Option<String>
         return Ok((None, None));
     };
 
-    let secret = decode_hex_32(&key_hex)?;
+    let secret = decode_hex_32(&key_hex, "signing_key")?;
     let signing_key = SigningKey::from_bytes(&secret);
     let verifying_key = VerifyingKey::from(&signing_key);
     let payload_bytes = serde_json::to_vec(payload).context("serialize payload")?;
@@ -147,4 +145,3 @@ fn sign_payload(payload: &AuditLogPayload, args: &Cli) -> Result<(Option<String>
     let public_key_hex = hex_encode(&verifying_key.to_bytes());
     Ok((Some(signature_hex), Some(public_key_hex)))
 }
-
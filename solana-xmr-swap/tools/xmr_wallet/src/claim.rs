@@ -1,15 +1,21 @@
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use curve25519_dalek::scalar::Scalar;
 use monero::{Address, Network, PrivateKey, PublicKey};
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::fs::OpenOptions;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use tracing::debug;
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+use tracing::{debug, warn};
 use zeroize::Zeroizing;
 
 use crate::crypto::{derive_spend_key, derive_view_key, to_monero_private_key, SecretKey};
-use crate::rpc::XmrWallet;
+use crate::journal::{ClaimJournal, ClaimStep};
+use crate::rpc::{BuiltTx, TransferOptions, TxDescription, XmrWallet};
+use crate::storage::{read, write_new};
 use crate::types::Result;
 
 #[derive(Debug, Clone)]
@@ -17,50 +23,141 @@ pub struct ClaimParams {
     pub alice_partial: Scalar,
     pub bob_partial: Scalar,
     pub revealed_secret: Zeroizing<[u8; 32]>,
+    /// Joint view key from `combine_view_key_shares`. `None` derives the
+    /// view key from the spend key, which only works for legacy swaps.
+    pub view_key: Option<Scalar>,
     pub destination_address: String,
     pub network: Network,
     pub wallet_filename: String,
     pub restore_height: Option<u64>,
     pub expected_hashlock: Option<[u8; 32]>,
-    pub claim_guard_path: Option<PathBuf>,
+    /// Claim journal recording each completed step; a rerun resumes from
+    /// it. `None` keeps the journal in memory only.
+    pub journal_path: Option<PathBuf>,
+    pub unlock: UnlockWait,
+    pub transfer: TransferOptions,
+    /// Password encrypting the claim wallet. `None` derives one from the
+    /// claim spend key with [`claim_wallet_password`].
+    pub wallet_password: Option<Zeroizing<String>>,
+    pub cleanup: WalletCleanup,
 }
 
-#[derive(Debug, Clone)]
-pub struct ClaimKeys {
-    pub spend_key: PrivateKey,
-    pub view_key: PrivateKey,
-    pub address: Address,
+/// What happens to the claim wallet's files once the sweep is confirmed.
+/// The wallet is closed after every claim run either way.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum WalletCleanup {
+    /// Leave the password-protected wallet on the wallet-rpc host for audit.
+    #[default]
+    KeepEncrypted,
+    /// Overwrite and remove the wallet files. `wallet_dir` is wallet-rpc's
+    /// `--wallet-dir` as seen from this host.
+    SecureDelete { wallet_dir: PathBuf },
 }
 
-pub struct ClaimGuard {
-    path: PathBuf,
-    committed: bool,
+/// How long to wait for the claim wallet's outputs to become spendable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnlockWait {
+    /// Unlocked balance, in piconero, required before sweeping.
+    pub expected_amount: u64,
+    pub poll_interval: Duration,
+    pub timeout: Duration,
 }
 
-impl ClaimGuard {
-    pub fn commit(mut self) {
-        self.committed = true;
+impl UnlockWait {
+    pub fn new(expected_amount: u64) -> Self {
+        Self {
+            expected_amount,
+            ..Self::default()
+        }
     }
 }
 
-impl Drop for ClaimGuard {
-    fn drop(&mut self) {
-        if !self.committed {
-            let _ = std::fs::remove_file(&self.path);
+impl Default for UnlockWait {
+    fn default() -> Self {
+        Self {
+            expected_amount: 1,
+            poll_interval: Duration::from_secs(30),
+            timeout: Duration::from_secs(2 * 60 * 60),
         }
     }
 }
 
-pub fn prepare_claim_guard(path: &Path) -> Result<ClaimGuard> {
-    OpenOptions::new()
-        .write(true)
-        .create_new(true)
-        .open(path)
-        .map_err(anyhow::Error::from)?;
-    Ok(ClaimGuard {
-        path: path.to_path_buf(),
-        committed: false,
-    })
+/// Progress of [`execute_claim_with_progress`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClaimProgress {
+    KeysImported,
+    Refreshed {
+        height: u64,
+    },
+    WaitingForUnlock {
+        height: u64,
+        balance: u64,
+        unlocked_balance: u64,
+        expected: u64,
+    },
+    Unlocked {
+        unlocked_balance: u64,
+    },
+    /// The sweep is built but not yet relayed.
+    SweepBuilt {
+        tx_hashes: Vec<String>,
+        amount: u64,
+        fee: u64,
+    },
+    Relayed {
+        tx_hash: String,
+    },
+    /// The wallet already lists an outgoing transaction to the destination.
+    AlreadySwept {
+        tx_hash: String,
+    },
+    Confirmed {
+        tx_hash: String,
+        height: u64,
+    },
+    /// The claim wallet's files were overwritten and removed.
+    WalletDeleted {
+        paths: Vec<PathBuf>,
+    },
+}
+
+/// A sweep built with `do_not_relay`, or signed offline. The claim journal
+/// keeps it so an interrupted claim relays the same transactions instead
+/// of building new ones.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingSweep {
+    pub destination: String,
+    pub txs: Vec<BuiltTx>,
+}
+
+impl PendingSweep {
+    pub fn amount(&self) -> u64 {
+        self.txs.iter().map(|tx| tx.amount).sum()
+    }
+
+    pub fn fee(&self) -> u64 {
+        self.txs.iter().map(|tx| tx.fee).sum()
+    }
+
+    pub fn tx_hashes(&self) -> Vec<String> {
+        self.txs.iter().map(|tx| tx.tx_hash.clone()).collect()
+    }
+
+    /// Writes the sweep to `path`, refusing to replace an existing one.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_new(path, self, "pending sweep")
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        read(path, "pending sweep")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClaimKeys {
+    pub spend_key: PrivateKey,
+    pub view_key: PrivateKey,
+    pub address: Address,
 }
 
 pub fn validate_destination_address(value: &str) -> Result<Address> {
@@ -80,52 +177,452 @@ fn revealed_secret_scalar(params: &ClaimParams) -> Scalar {
 }
 
 pub fn derive_claim_keys(params: &ClaimParams) -> Result<ClaimKeys> {
-    let spend_scalar = derive_spend_key(
+    ClaimKeys::derive(
         &params.alice_partial,
         &params.bob_partial,
         &revealed_secret_scalar(params),
-    );
-    let spend_secret = SecretKey::from_scalar(spend_scalar);
-    let view_secret = derive_view_key(&spend_secret)?;
+        params.view_key,
+        params.network,
+    )
+}
+
+impl ClaimKeys {
+    /// Claim keys from both partials and the secret revealed on Solana.
+    /// `view_key` as in [`ClaimParams::view_key`].
+    pub fn derive(
+        alice_partial: &Scalar,
+        bob_partial: &Scalar,
+        revealed_secret: &Scalar,
+        view_key: Option<Scalar>,
+        network: Network,
+    ) -> Result<Self> {
+        let spend_scalar = derive_spend_key(alice_partial, bob_partial, revealed_secret);
+        let spend_secret = SecretKey::from_scalar(spend_scalar);
+        let view_secret = match view_key {
+            Some(view_key) => SecretKey::from_scalar(view_key),
+            None => derive_view_key(&spend_secret)?,
+        };
+
+        let spend_key = to_monero_private_key(&spend_secret)?;
+        let view_key = to_monero_private_key(&view_secret)?;
+
+        let public_spend = PublicKey::from_private_key(&spend_key);
+        let public_view = PublicKey::from_private_key(&view_key);
+        let address = Address::standard(network, public_spend, public_view);
+
+        Ok(Self {
+            spend_key,
+            view_key,
+            address,
+        })
+    }
+
+    /// Wallet password derived from the spend key; see
+    /// [`claim_wallet_password`].
+    pub fn wallet_password(&self) -> Zeroizing<String> {
+        let mut hasher = Sha256::new();
+        hasher.update(b"xmr-swap-claim-wallet-password");
+        hasher.update(self.spend_key.to_bytes());
+        Zeroizing::new(hex::encode(hasher.finalize()))
+    }
+}
+
+/// Password for the claim wallet: `params.wallet_password` if set,
+/// otherwise derived from the claim spend key. The derived password needs
+/// no storage, since a rerun can always recompute it, and only holders of
+/// the spend key (who could rebuild the wallet anyway) can open the file.
+pub fn claim_wallet_password(params: &ClaimParams, keys: &ClaimKeys) -> Result<Zeroizing<String>> {
+    if let Some(password) = params.wallet_password.as_ref() {
+        if password.is_empty() {
+            return Err(anyhow!("claim wallet password must not be empty").into());
+        }
+        return Ok(password.clone());
+    }
+    Ok(keys.wallet_password())
+}
+
+/// Creates the claim wallet `filename` from `keys`, or opens the one an
+/// earlier run created. An existing file for another address is refused
+/// before anything is swept from it.
+pub async fn open_claim_wallet(
+    wallet: &XmrWallet,
+    keys: &ClaimKeys,
+    filename: &str,
+    password: &str,
+    restore_height: Option<u64>,
+) -> Result<()> {
+    let imported = wallet
+        .import_spend_key(
+            keys.spend_key.to_bytes(),
+            keys.view_key.to_bytes(),
+            &keys.address.to_string(),
+            filename,
+            password,
+            restore_height,
+        )
+        .await;
+    match imported {
+        Ok(()) => Ok(()),
+        // A crash between the import and the journal write leaves the
+        // wallet file behind; opening it is the same as importing, as
+        // long as it is the wallet of these keys.
+        Err(err) if err.is_wallet_exists() => {
+            warn!(wallet = filename, "Claim wallet exists; opening it");
+            wallet
+                .open_wallet_for(filename, Some(password), &keys.address.to_string())
+                .await
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Files monero-wallet-rpc keeps for the wallet `filename`.
+fn wallet_files(wallet_dir: &Path, filename: &str) -> Vec<PathBuf> {
+    [
+        filename.to_string(),
+        format!("{filename}.keys"),
+        format!("{filename}.address.txt"),
+    ]
+    .into_iter()
+    .map(|name| wallet_dir.join(name))
+    .collect()
+}
+
+/// Overwrites each of the wallet's files with zeros, syncs and removes it,
+/// returning the paths removed. Missing files are skipped, so a repeated
+/// call is harmless. Overwriting in place does not reach copies kept by
+/// copy-on-write filesystems or SSD wear levelling; use encrypted storage
+/// for the wallet directory where that matters.
+pub fn secure_delete_wallet_files(wallet_dir: &Path, filename: &str) -> Result<Vec<PathBuf>> {
+    let mut removed = Vec::new();
+    for path in wallet_files(wallet_dir, filename) {
+        if !path.exists() {
+            continue;
+        }
+        let len = fs::metadata(&path)
+            .with_context(|| format!("stat wallet file {}", path.display()))?
+            .len();
+        let mut file = OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("open wallet file {}", path.display()))?;
+        let zeros = [0u8; 4096];
+        let mut remaining = len;
+        while remaining > 0 {
+            let chunk = remaining.min(zeros.len() as u64) as usize;
+            file.write_all(&zeros[..chunk])
+                .with_context(|| format!("overwrite wallet file {}", path.display()))?;
+            remaining -= chunk as u64;
+        }
+        file.sync_all()
+            .with_context(|| format!("sync wallet file {}", path.display()))?;
+        drop(file);
+        fs::remove_file(&path).with_context(|| format!("remove wallet file {}", path.display()))?;
+        debug!(path = %path.display(), "Securely deleted claim wallet file");
+        removed.push(path);
+    }
+    Ok(removed)
+}
+
+/// Refreshes and polls until the open wallet's unlocked balance reaches
+/// `wait.expected_amount`, reporting each poll to `on_progress`.
+pub async fn wait_for_unlocked_balance(
+    wallet: &XmrWallet,
+    wait: &UnlockWait,
+    mut on_progress: impl FnMut(ClaimProgress),
+) -> Result<u64> {
+    let deadline = Instant::now() + wait.timeout;
+    loop {
+        let height = wallet.get_height().await?;
+        let balances = wallet.get_balances().await?;
+        if balances.unlocked_balance >= wait.expected_amount {
+            on_progress(ClaimProgress::Unlocked {
+                unlocked_balance: balances.unlocked_balance,
+            });
+            return Ok(balances.unlocked_balance);
+        }
+        on_progress(ClaimProgress::WaitingForUnlock {
+            height,
+            balance: balances.balance,
+            unlocked_balance: balances.unlocked_balance,
+            expected: wait.expected_amount,
+        });
+        if Instant::now() + wait.poll_interval > deadline {
+            return Err(anyhow!(
+                "timed out waiting for unlocked balance: {} of {} piconero spendable",
+                balances.unlocked_balance,
+                wait.expected_amount
+            )
+            .into());
+        }
+        sleep(wait.poll_interval).await;
+        wallet.refresh(None).await?;
+    }
+}
+
+/// Id of an outgoing transaction from the open wallet to `destination`,
+/// if there is one.
+pub async fn find_sweep(wallet: &XmrWallet, destination: &str) -> Result<Option<String>> {
+    let transfers = wallet.get_outgoing_transfers().await?;
+    Ok(transfers
+        .into_iter()
+        .find(|transfer| {
+            transfer
+                .destinations
+                .iter()
+                .any(|dest| dest.address == destination)
+        })
+        .map(|transfer| transfer.tx_hash))
+}
+
+async fn build_sweep(
+    wallet: &XmrWallet,
+    destination: &str,
+    options: &TransferOptions,
+) -> Result<PendingSweep> {
+    let sweep = PendingSweep {
+        destination: destination.to_string(),
+        txs: wallet.build_sweep_all(destination, options).await?,
+    };
+    options.check_fee(sweep.fee())?;
+    Ok(sweep)
+}
 
-    let spend_key = to_monero_private_key(&spend_secret)?;
-    let view_key = to_monero_private_key(&view_secret)?;
+/// Checks a sweep decoded by `describe_transfer` before signing it: it
+/// must pay only `destination`, return no change and keep its fee within
+/// `options.max_fee`. `label` names the sweep in errors.
+pub(crate) fn check_described_sweep(
+    label: &str,
+    descriptions: &[TxDescription],
+    destination: &str,
+    options: &TransferOptions,
+) -> Result<()> {
+    if descriptions.is_empty() {
+        return Err(anyhow!("{label} holds no transactions").into());
+    }
+    for description in descriptions {
+        if let Some(other) = description
+            .recipients
+            .iter()
+            .find(|recipient| recipient.address != destination)
+        {
+            return Err(
+                anyhow!("{label} pays {} instead of the destination", other.address).into(),
+            );
+        }
+        if description.change_amount != 0 {
+            return Err(anyhow!(
+                "{label} returns {} in change to {}",
+                description.change_amount,
+                description.change_address
+            )
+            .into());
+        }
+    }
+    options.check_fee(descriptions.iter().map(|description| description.fee).sum())
+}
 
-    let public_spend = PublicKey::from_private_key(&spend_key);
-    let public_view = PublicKey::from_private_key(&view_key);
-    let address = Address::standard(params.network, public_spend, public_view);
+/// Relays every transaction of `sweep` and returns the first tx hash.
+async fn relay_sweep(
+    wallet: &XmrWallet,
+    sweep: &PendingSweep,
+    mut on_progress: impl FnMut(ClaimProgress),
+) -> Result<String> {
+    for tx in &sweep.txs {
+        wallet.relay_tx_checked(tx).await?;
+        on_progress(ClaimProgress::Relayed {
+            tx_hash: tx.tx_hash.clone(),
+        });
+    }
+    Ok(sweep.txs[0].tx_hash.clone())
+}
 
-    Ok(ClaimKeys {
-        spend_key,
-        view_key,
-        address,
-    })
+/// Height of the block that mined `tx_hash`, if the wallet has seen it
+/// confirmed.
+async fn mined_height(wallet: &XmrWallet, tx_hash: &str) -> Result<Option<u64>> {
+    Ok(wallet
+        .get_outgoing_transfers()
+        .await?
+        .into_iter()
+        .find(|transfer| transfer.tx_hash == tx_hash)
+        .and_then(|transfer| transfer.height))
 }
 
 pub async fn execute_claim(wallet: &XmrWallet, params: &ClaimParams) -> Result<String> {
+    execute_claim_with_progress(wallet, params, |_| {}).await
+}
+
+/// Imports the claim keys, syncs from `restore_height`, waits for the
+/// outputs to unlock and only then sweeps.
+///
+/// Every completed step is written to the claim journal at
+/// `params.journal_path`. A rerun skips what the journal records as done:
+/// it reopens the wallet instead of importing the keys again, relays the
+/// recorded sweep instead of building a new one, and once a sweep has been
+/// relayed it only reports its tx hash (and confirmation) without sweeping
+/// again.
+///
+/// The claim wallet is encrypted with [`claim_wallet_password`] and closed
+/// before returning, whether or not the claim succeeded. Its files are
+/// handled per `params.cleanup` once the sweep is confirmed.
+pub async fn execute_claim_with_progress(
+    wallet: &XmrWallet,
+    params: &ClaimParams,
+    mut on_progress: impl FnMut(ClaimProgress),
+) -> Result<String> {
     debug!("Executing Monero claim flow");
     validate_destination_address(&params.destination_address)?;
     if let Some(expected) = params.expected_hashlock.as_ref() {
-        validate_hashlock(&*params.revealed_secret, expected)?;
+        validate_hashlock(&params.revealed_secret, expected)?;
     }
-    let _guard = if let Some(path) = params.claim_guard_path.as_ref() {
-        Some(prepare_claim_guard(path)?)
-    } else {
-        None
-    };
     let keys = derive_claim_keys(params)?;
-    wallet
-        .import_spend_key(
-            keys.spend_key.to_bytes(),
-            keys.view_key.to_bytes(),
-            &keys.address.to_string(),
+    let password = claim_wallet_password(params, &keys)?;
+    let journal_path = params.journal_path.as_deref();
+    let mut journal = match journal_path.map(ClaimJournal::load).transpose()?.flatten() {
+        Some(journal) => {
+            journal.ensure_matches(&params.destination_address, &params.wallet_filename)?;
+            debug!(step = ?journal.step, "Resuming Monero claim from journal");
+            journal
+        }
+        None => {
+            let journal = ClaimJournal::new(&params.destination_address, &params.wallet_filename);
+            record(&journal, journal_path)?;
+            journal
+        }
+    };
+
+    if let (true, Some(tx_hash)) = (
+        journal.is_done(ClaimStep::Confirmed),
+        journal.tx_hash.clone(),
+    ) {
+        // The wallet may already be gone; the journal is all that is left.
+        on_progress(ClaimProgress::AlreadySwept {
+            tx_hash: tx_hash.clone(),
+        });
+        clean_up_wallet(params, &mut on_progress)?;
+        return Ok(tx_hash);
+    }
+
+    if journal.is_done(ClaimStep::KeysImported) {
+        wallet
+            .open_wallet(&params.wallet_filename, Some(&password))
+            .await?;
+    } else {
+        open_claim_wallet(
+            wallet,
+            &keys,
             &params.wallet_filename,
+            &password,
             params.restore_height,
         )
         .await?;
-    let tx_hash = wallet.sweep_all(&params.destination_address).await?;
-    if let Some(guard) = _guard {
-        guard.commit();
+        journal.record_keys_imported();
+        record(&journal, journal_path)?;
+    }
+    on_progress(ClaimProgress::KeysImported);
+
+    let claimed = claim_from_open_wallet(wallet, params, &mut journal, &mut on_progress).await;
+    let closed = wallet.close_wallet().await;
+    let tx_hash = claimed?;
+    closed?;
+    if journal.is_done(ClaimStep::Confirmed) {
+        clean_up_wallet(params, &mut on_progress)?;
+    }
+    Ok(tx_hash)
+}
+
+/// Syncs, sweeps and confirms from the opened claim wallet, resuming from
+/// the steps `journal` records.
+async fn claim_from_open_wallet(
+    wallet: &XmrWallet,
+    params: &ClaimParams,
+    journal: &mut ClaimJournal,
+    mut on_progress: impl FnMut(ClaimProgress),
+) -> Result<String> {
+    let journal_path = params.journal_path.as_deref();
+    if let Some(tx_hash) = journal.tx_hash.clone() {
+        on_progress(ClaimProgress::AlreadySwept {
+            tx_hash: tx_hash.clone(),
+        });
+        confirm(wallet, journal, journal_path, &tx_hash, &mut on_progress).await?;
+        return Ok(tx_hash);
     }
+
+    wallet.refresh(params.restore_height).await?;
+    let height = wallet.get_height().await?;
+    on_progress(ClaimProgress::Refreshed { height });
+    journal.record_synced(height);
+    record(journal, journal_path)?;
+
+    let sweep = match journal.sweep.clone() {
+        Some(sweep) => {
+            params.transfer.check_fee(sweep.fee())?;
+            sweep
+        }
+        None => {
+            if let Some(tx_hash) = find_sweep(wallet, &params.destination_address).await? {
+                on_progress(ClaimProgress::AlreadySwept {
+                    tx_hash: tx_hash.clone(),
+                });
+                journal.record_relayed(&tx_hash);
+                record(journal, journal_path)?;
+                return Ok(tx_hash);
+            }
+            wait_for_unlocked_balance(wallet, &params.unlock, &mut on_progress).await?;
+            let sweep = build_sweep(wallet, &params.destination_address, &params.transfer).await?;
+            journal.record_built(sweep.clone());
+            record(journal, journal_path)?;
+            sweep
+        }
+    };
+    on_progress(ClaimProgress::SweepBuilt {
+        tx_hashes: sweep.tx_hashes(),
+        amount: sweep.amount(),
+        fee: sweep.fee(),
+    });
+
+    let tx_hash = relay_sweep(wallet, &sweep, &mut on_progress).await?;
+    journal.record_relayed(&tx_hash);
+    record(journal, journal_path)?;
+    confirm(wallet, journal, journal_path, &tx_hash, &mut on_progress).await?;
     Ok(tx_hash)
 }
+
+fn clean_up_wallet(params: &ClaimParams, mut on_progress: impl FnMut(ClaimProgress)) -> Result<()> {
+    if let WalletCleanup::SecureDelete { wallet_dir } = &params.cleanup {
+        let paths = secure_delete_wallet_files(wallet_dir, &params.wallet_filename)?;
+        if !paths.is_empty() {
+            on_progress(ClaimProgress::WalletDeleted { paths });
+        }
+    }
+    Ok(())
+}
+
+/// Records the sweep as confirmed once the wallet has seen it mined.
+async fn confirm(
+    wallet: &XmrWallet,
+    journal: &mut ClaimJournal,
+    journal_path: Option<&Path>,
+    tx_hash: &str,
+    mut on_progress: impl FnMut(ClaimProgress),
+) -> Result<()> {
+    if journal.is_done(ClaimStep::Confirmed) {
+        return Ok(());
+    }
+    if let Some(height) = mined_height(wallet, tx_hash).await? {
+        journal.record_confirmed(height);
+        record(journal, journal_path)?;
+        on_progress(ClaimProgress::Confirmed {
+            tx_hash: tx_hash.to_string(),
+            height,
+        });
+    }
+    Ok(())
+}
+
+fn record(journal: &ClaimJournal, path: Option<&Path>) -> Result<()> {
+    match path {
+        Some(path) => journal.save(path),
+        None => Ok(()),
+    }
+}
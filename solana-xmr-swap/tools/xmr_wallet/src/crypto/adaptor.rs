@@ -17,15 +17,23 @@ pub fn derive_spend_key(
     alice_partial + bob_partial + revealed_secret
 }
 
-/// Validates an Ed25519 point and rejects small-order points.
+/// Validates an Ed25519 point, accepting only the canonical encoding of a
+/// point in the prime-order subgroup.
 pub fn validate_point(compressed: &[u8; 32]) -> Result<EdwardsPoint, CryptoError> {
     let point = CompressedEdwardsY(*compressed)
         .decompress()
         .ok_or(CryptoError::InvalidPoint)?;
-
+    // decompress() accepts y >= p and a set sign bit on x = 0; re-encoding
+    // exposes both.
+    if point.compress().to_bytes() != *compressed {
+        return Err(CryptoError::NonCanonicalPoint);
+    }
     if point.is_small_order() {
         return Err(CryptoError::SmallOrderPoint);
     }
+    if !point.is_torsion_free() {
+        return Err(CryptoError::TorsionedPoint);
+    }
 
     Ok(point)
 }
@@ -0,0 +1,154 @@
+//! Ed25519 adaptor signatures.
+//!
+//! An encrypted signature under adaptor point `T = t*G` is `(R', s')` with
+//! `R' = r*G` and `s' = r + c*a`, where the challenge commits to the final
+//! nonce `R = R' + T`: `c = SHA-512(R || A || M)`. Adding `t` to `s'` yields
+//! a standard Ed25519 signature `(R, s' + t)`, and anyone holding both
+//! signatures recovers `t = s - s'`.
+
+use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+use curve25519_dalek::edwards::EdwardsPoint;
+use curve25519_dalek::scalar::Scalar;
+use ed25519_dalek::Signature;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha512};
+use zeroize::Zeroizing;
+
+use super::adaptor::validate_point;
+use super::keys::{PublicKey, SecretKey};
+use crate::types::CryptoError;
+
+const NONCE_DOMAIN_TAG: &[u8] = b"xmr_wallet/adaptor/nonce/v1";
+
+/// A pre-signature that only becomes a valid Ed25519 signature once the
+/// adaptor secret is added to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncryptedSignature {
+    r_pre: EdwardsPoint,
+    s_pre: Scalar,
+}
+
+impl EncryptedSignature {
+    /// Encodes as `R' || s'`.
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut out = [0u8; 64];
+        out[..32].copy_from_slice(self.r_pre.compress().as_bytes());
+        out[32..].copy_from_slice(self.s_pre.as_bytes());
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8; 64]) -> Result<Self, CryptoError> {
+        let mut r_bytes = [0u8; 32];
+        r_bytes.copy_from_slice(&bytes[..32]);
+        let r_pre = validate_point(&r_bytes)?;
+
+        let mut s_bytes = [0u8; 32];
+        s_bytes.copy_from_slice(&bytes[32..]);
+        let s_pre = Option::from(Scalar::from_canonical_bytes(s_bytes))
+            .ok_or(CryptoError::InvalidScalar)?;
+
+        Ok(Self { r_pre, s_pre })
+    }
+}
+
+/// Signs `message` with `signing_key`, encrypted under `adaptor_point`.
+pub fn encrypted_sign(
+    signing_key: &SecretKey,
+    adaptor_point: &PublicKey,
+    message: &[u8],
+) -> EncryptedSignature {
+    let mut aux = Zeroizing::new([0u8; 32]);
+    OsRng.fill_bytes(aux.as_mut());
+    encrypted_sign_with_aux(signing_key, adaptor_point, message, &aux)
+}
+
+/// Deterministic variant of [`encrypted_sign`] for test vectors. `aux` should
+/// be fresh randomness in production.
+pub fn encrypted_sign_with_aux(
+    signing_key: &SecretKey,
+    adaptor_point: &PublicKey,
+    message: &[u8],
+    aux: &[u8; 32],
+) -> EncryptedSignature {
+    let a = Zeroizing::new(signing_key.as_scalar());
+    let public = (ED25519_BASEPOINT_POINT * *a).compress();
+
+    let mut hasher = Sha512::new();
+    hasher.update(NONCE_DOMAIN_TAG);
+    hasher.update(a.as_bytes());
+    hasher.update(adaptor_point.to_compressed());
+    hasher.update(public.as_bytes());
+    hasher.update(message);
+    hasher.update(aux);
+    let r = Zeroizing::new(Scalar::from_bytes_mod_order_wide(&hasher.finalize().into()));
+
+    let r_pre = ED25519_BASEPOINT_POINT * *r;
+    let c = challenge(&(r_pre + adaptor_point.point()), public.as_bytes(), message);
+
+    EncryptedSignature {
+        r_pre,
+        s_pre: *r + c * *a,
+    }
+}
+
+/// Checks that `encrypted` decrypts, with the discrete log of
+/// `adaptor_point`, to a valid signature on `message` by `public_key`.
+pub fn verify_encrypted_signature(
+    public_key: &PublicKey,
+    adaptor_point: &PublicKey,
+    message: &[u8],
+    encrypted: &EncryptedSignature,
+) -> Result<(), CryptoError> {
+    let c = challenge(
+        &(encrypted.r_pre + adaptor_point.point()),
+        &public_key.to_compressed(),
+        message,
+    );
+    let expected_r_pre = EdwardsPoint::vartime_double_scalar_mul_basepoint(
+        &-c,
+        public_key.point(),
+        &encrypted.s_pre,
+    );
+    if expected_r_pre != encrypted.r_pre {
+        return Err(CryptoError::InvalidSignature);
+    }
+    Ok(())
+}
+
+/// Completes an encrypted signature into a standard Ed25519 signature.
+pub fn decrypt_signature(adaptor_secret: &SecretKey, encrypted: &EncryptedSignature) -> Signature {
+    let t = Zeroizing::new(adaptor_secret.as_scalar());
+    let r = encrypted.r_pre + ED25519_BASEPOINT_POINT * *t;
+    let s = encrypted.s_pre + *t;
+    Signature::from_components(r.compress().to_bytes(), s.to_bytes())
+}
+
+/// Recovers the adaptor secret from an encrypted signature and the
+/// signature it was completed into.
+pub fn recover_adaptor_secret(
+    adaptor_point: &PublicKey,
+    encrypted: &EncryptedSignature,
+    signature: &Signature,
+) -> Result<SecretKey, CryptoError> {
+    let expected_r = encrypted.r_pre + adaptor_point.point();
+    if expected_r.compress().as_bytes() != signature.r_bytes() {
+        return Err(CryptoError::InvalidSignature);
+    }
+
+    let s: Scalar = Option::from(Scalar::from_canonical_bytes(*signature.s_bytes()))
+        .ok_or(CryptoError::InvalidScalar)?;
+    let t = Zeroizing::new(s - encrypted.s_pre);
+    if ED25519_BASEPOINT_POINT * *t != *adaptor_point.point() {
+        return Err(CryptoError::InvalidSignature);
+    }
+    Ok(SecretKey::from_scalar(*t))
+}
+
+fn challenge(r: &EdwardsPoint, public_key: &[u8; 32], message: &[u8]) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(r.compress().as_bytes());
+    hasher.update(public_key);
+    hasher.update(message);
+    Scalar::from_bytes_mod_order_wide(&hasher.finalize().into())
+}
@@ -0,0 +1,156 @@
+//! Partial spend-key exchange with proofs of knowledge.
+//!
+//! The joint spend key is the sum of both parties' partial keys and the
+//! adaptor secret, so its public key is the sum of the public shares. A
+//! party that publishes `X - A` instead of a key it knows would control the
+//! joint key alone; a Schnorr proof of knowledge bound to the swap id rules
+//! that out.
+
+use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+use curve25519_dalek::edwards::EdwardsPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha512};
+use zeroize::Zeroizing;
+
+use super::adaptor::validate_point;
+use super::keys::{PublicKey, SecretKey};
+use crate::types::CryptoError;
+
+const CHALLENGE_DOMAIN_TAG: &[u8] = b"xmr_wallet/key-share/pok/v1";
+const NONCE_DOMAIN_TAG: &[u8] = b"xmr_wallet/key-share/nonce/v1";
+
+/// Length of [`KeyShare::to_bytes`]: public key, commitment, response.
+pub const KEY_SHARE_LEN: usize = 96;
+
+/// A public spend share with a proof that its publisher knows the secret.
+#[derive(Clone, Debug)]
+pub struct KeyShare {
+    public: PublicKey,
+    commitment: EdwardsPoint,
+    response: Scalar,
+}
+
+impl KeyShare {
+    pub fn public(&self) -> &PublicKey {
+        &self.public
+    }
+
+    pub fn to_bytes(&self) -> [u8; KEY_SHARE_LEN] {
+        let mut out = [0u8; KEY_SHARE_LEN];
+        out[..32].copy_from_slice(&self.public.to_compressed());
+        out[32..64].copy_from_slice(self.commitment.compress().as_bytes());
+        out[64..].copy_from_slice(self.response.as_bytes());
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8; KEY_SHARE_LEN]) -> Result<Self, CryptoError> {
+        let mut public = [0u8; 32];
+        public.copy_from_slice(&bytes[..32]);
+        let mut commitment = [0u8; 32];
+        commitment.copy_from_slice(&bytes[32..64]);
+        let mut response = [0u8; 32];
+        response.copy_from_slice(&bytes[64..]);
+
+        Ok(Self {
+            public: PublicKey::from_compressed(public)?,
+            commitment: validate_point(&commitment)?,
+            response: Option::from(Scalar::from_canonical_bytes(response))
+                .ok_or(CryptoError::InvalidScalar)?,
+        })
+    }
+}
+
+/// A share whose proof of knowledge has been checked. Only
+/// [`verify_key_share`] creates these.
+#[derive(Clone, Debug)]
+pub struct VerifiedKeyShare(PublicKey);
+
+impl VerifiedKeyShare {
+    pub fn public(&self) -> &PublicKey {
+        &self.0
+    }
+}
+
+/// Publishes the public share of `secret` with a proof of knowledge bound to
+/// `swap_id`.
+pub fn create_key_share(secret: &SecretKey, swap_id: &[u8]) -> Result<KeyShare, CryptoError> {
+    let x = Zeroizing::new(secret.as_scalar());
+    let public = PublicKey::from_compressed((ED25519_BASEPOINT_POINT * *x).compress().to_bytes())?;
+
+    let mut aux = Zeroizing::new([0u8; 32]);
+    OsRng.fill_bytes(aux.as_mut());
+    let mut hasher = Sha512::new();
+    hasher.update(NONCE_DOMAIN_TAG);
+    hasher.update(x.as_bytes());
+    hasher.update(public.to_compressed());
+    hasher.update(swap_id);
+    hasher.update(aux.as_ref());
+    let k = Zeroizing::new(Scalar::from_bytes_mod_order_wide(&hasher.finalize().into()));
+
+    let commitment = ED25519_BASEPOINT_POINT * *k;
+    let c = challenge(swap_id, &public, &commitment);
+    Ok(KeyShare {
+        public,
+        commitment,
+        response: *k + c * *x,
+    })
+}
+
+/// Checks the proof of knowledge on `share` for `swap_id`.
+pub fn verify_key_share(share: &KeyShare, swap_id: &[u8]) -> Result<VerifiedKeyShare, CryptoError> {
+    let c = challenge(swap_id, &share.public, &share.commitment);
+    let expected = EdwardsPoint::vartime_double_scalar_mul_basepoint(
+        &-c,
+        share.public.point(),
+        &share.response,
+    );
+    if expected != share.commitment {
+        return Err(CryptoError::InvalidProof);
+    }
+    Ok(VerifiedKeyShare(share.public.clone()))
+}
+
+/// Sums verified shares and the adaptor point into the joint public spend
+/// key. The same share may not appear twice.
+pub fn joint_public_spend_key(
+    shares: &[VerifiedKeyShare],
+    adaptor_point: &PublicKey,
+) -> Result<PublicKey, CryptoError> {
+    for (index, share) in shares.iter().enumerate() {
+        if shares[..index]
+            .iter()
+            .any(|other| other.public().point() == share.public().point())
+        {
+            return Err(CryptoError::DuplicateKeyShare);
+        }
+    }
+    let sum = shares.iter().fold(*adaptor_point.point(), |acc, share| {
+        acc + share.public().point()
+    });
+    PublicKey::from_compressed(sum.compress().to_bytes())
+}
+
+/// Sums view-key shares into the joint private view key.
+///
+/// Both parties exchange their shares before the lock so either side can
+/// watch the joint address with a view-only wallet; the view key never
+/// grants spending.
+pub fn combine_view_key_shares(shares: &[SecretKey]) -> Result<SecretKey, CryptoError> {
+    let sum = Zeroizing::new(shares.iter().map(SecretKey::as_scalar).sum::<Scalar>());
+    if *sum == Scalar::ZERO {
+        return Err(CryptoError::InvalidScalar);
+    }
+    Ok(SecretKey::from_scalar(*sum))
+}
+
+fn challenge(swap_id: &[u8], public: &PublicKey, commitment: &EdwardsPoint) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(CHALLENGE_DOMAIN_TAG);
+    hasher.update((swap_id.len() as u64).to_le_bytes());
+    hasher.update(swap_id);
+    hasher.update(public.to_compressed());
+    hasher.update(commitment.compress().as_bytes());
+    Scalar::from_bytes_mod_order_wide(&hasher.finalize().into())
+}
@@ -1,4 +1,4 @@
-use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::edwards::EdwardsPoint;
 use curve25519_dalek::scalar::Scalar;
 use monero::cryptonote::hash::Hash;
 use monero::util::key::PrivateKey as MoneroPrivateKey;
@@ -6,6 +6,7 @@ use rand::rngs::OsRng;
 use rand::RngCore;
 use zeroize::Zeroize;
 
+use super::adaptor::validate_point;
 use crate::types::CryptoError;
 
 #[derive(Clone, Zeroize)]
@@ -50,15 +51,7 @@ pub struct PublicKey(EdwardsPoint);
 
 impl PublicKey {
     pub fn from_compressed(bytes: [u8; 32]) -> Result<Self, CryptoError> {
-        let point = CompressedEdwardsY(bytes)
-            .decompress()
-            .ok_or(CryptoError::InvalidPoint)?;
-
-        if point.is_small_order() {
-            return Err(CryptoError::SmallOrderPoint);
-        }
-
-        Ok(Self(point))
+        validate_point(&bytes).map(Self)
     }
 
     pub fn point(&self) -> &EdwardsPoint {
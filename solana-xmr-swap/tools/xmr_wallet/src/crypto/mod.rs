@@ -5,7 +5,17 @@
 //! - No custom scalar/point arithmetic.
 
 mod adaptor;
+mod adaptor_sig;
+mod key_exchange;
 mod keys;
 
 pub use adaptor::{complete_adaptor, derive_spend_key, validate_point};
+pub use adaptor_sig::{
+    decrypt_signature, encrypted_sign, encrypted_sign_with_aux, recover_adaptor_secret,
+    verify_encrypted_signature, EncryptedSignature,
+};
+pub use key_exchange::{
+    combine_view_key_shares, create_key_share, joint_public_spend_key, verify_key_share, KeyShare,
+    VerifiedKeyShare, KEY_SHARE_LEN,
+};
 pub use keys::{derive_view_key, to_monero_private_key, PublicKey, SecretKey};
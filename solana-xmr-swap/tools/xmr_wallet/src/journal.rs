@@ -0,0 +1,110 @@
+//! Durable record of a claim's progress.
+//!
+//! The journal is rewritten after every step, so a claim interrupted at any
+//! point resumes where it stopped: a built sweep is relayed rather than
+//! rebuilt, and a relayed one is never swept again.
+
+use std::path::Path;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::claim::PendingSweep;
+use crate::storage::{read, replace};
+use crate::types::Result;
+
+/// Claim steps, in the order they complete.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimStep {
+    Started,
+    KeysImported,
+    Synced,
+    TxBuilt,
+    TxRelayed,
+    Confirmed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClaimJournal {
+    pub destination: String,
+    pub wallet_filename: String,
+    pub step: ClaimStep,
+    pub synced_height: Option<u64>,
+    /// The sweep as built, kept until it is confirmed.
+    pub sweep: Option<PendingSweep>,
+    pub tx_hash: Option<String>,
+    pub confirmed_height: Option<u64>,
+}
+
+impl ClaimJournal {
+    pub fn new(destination: &str, wallet_filename: &str) -> Self {
+        Self {
+            destination: destination.to_string(),
+            wallet_filename: wallet_filename.to_string(),
+            step: ClaimStep::Started,
+            synced_height: None,
+            sweep: None,
+            tx_hash: None,
+            confirmed_height: None,
+        }
+    }
+
+    /// Loads the journal at `path`, or `None` if no claim was started.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        read(path, "claim journal").map(Some)
+    }
+
+    /// Replaces the journal at `path` atomically.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        replace(path, self, "claim journal")
+    }
+
+    /// Refuses to resume a journal written for another claim.
+    pub fn ensure_matches(&self, destination: &str, wallet_filename: &str) -> Result<()> {
+        if self.destination != destination || self.wallet_filename != wallet_filename {
+            return Err(anyhow!(
+                "claim journal is for wallet {} paying {}, not wallet {wallet_filename} paying {destination}",
+                self.wallet_filename,
+                self.destination
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    pub fn is_done(&self, step: ClaimStep) -> bool {
+        self.step >= step
+    }
+
+    pub fn record_keys_imported(&mut self) {
+        self.advance(ClaimStep::KeysImported);
+    }
+
+    pub fn record_synced(&mut self, height: u64) {
+        self.synced_height = Some(height);
+        self.advance(ClaimStep::Synced);
+    }
+
+    pub fn record_built(&mut self, sweep: PendingSweep) {
+        self.sweep = Some(sweep);
+        self.advance(ClaimStep::TxBuilt);
+    }
+
+    pub fn record_relayed(&mut self, tx_hash: &str) {
+        self.tx_hash = Some(tx_hash.to_string());
+        self.advance(ClaimStep::TxRelayed);
+    }
+
+    pub fn record_confirmed(&mut self, height: u64) {
+        self.confirmed_height = Some(height);
+        self.advance(ClaimStep::Confirmed);
+    }
+
+    fn advance(&mut self, step: ClaimStep) {
+        self.step = self.step.max(step);
+    }
+}
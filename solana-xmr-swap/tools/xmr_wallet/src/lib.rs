@@ -3,22 +3,61 @@
 //! Auditor notes:
 //! - All crypto primitives are isolated under `crypto/`.
 //! - Only audited libraries are used for curve operations.
-//! - RPC access is a thin wrapper over `monero-rpc`.
+//! - RPC access is a thin JSON-RPC client with optional digest auth and
+//!   fingerprint-pinned TLS (`rpc::WalletConnectionConfig`).
 
 pub mod claim;
 pub mod crypto;
+pub mod journal;
+pub mod lock;
+pub mod multisig;
+pub mod offline;
+pub mod restore;
 pub mod rpc;
+pub mod scanner;
+mod storage;
 pub mod types;
 pub mod watcher;
 
 pub use claim::{
-    derive_claim_keys, execute_claim, prepare_claim_guard, validate_destination_address,
-    validate_hashlock, ClaimGuard, ClaimKeys, ClaimParams,
+    claim_wallet_password, derive_claim_keys, execute_claim, execute_claim_with_progress,
+    find_sweep, open_claim_wallet, secure_delete_wallet_files, validate_destination_address,
+    validate_hashlock, wait_for_unlocked_balance, ClaimKeys, ClaimParams, ClaimProgress,
+    PendingSweep, UnlockWait, WalletCleanup,
 };
 pub use crypto::{
-    complete_adaptor, derive_spend_key, derive_view_key, to_monero_private_key, validate_point,
-    PublicKey, SecretKey,
+    combine_view_key_shares, complete_adaptor, create_key_share, decrypt_signature,
+    derive_spend_key, derive_view_key, encrypted_sign, encrypted_sign_with_aux,
+    joint_public_spend_key, recover_adaptor_secret, to_monero_private_key, validate_point,
+    verify_encrypted_signature, verify_key_share, EncryptedSignature, KeyShare, PublicKey,
+    SecretKey, VerifiedKeyShare, KEY_SHARE_LEN,
 };
-pub use rpc::{RetryConfig, XmrWallet};
+pub use journal::{ClaimJournal, ClaimStep};
+pub use lock::{derive_lock_address, lock_xmr, LockReceipt, PendingLock};
+pub use multisig::{
+    advance_multisig, cosign_multisig_sweep, export_multisig_sync, import_multisig_sync,
+    prepare_multisig_wallet, propose_multisig_sweep, submit_multisig_sweep, LockMechanism,
+    LockMechanismStore, MultisigLock, MultisigRound, MULTISIG_SIGNERS, MULTISIG_THRESHOLD,
+};
+pub use offline::{
+    export_cold_sync, export_sweep, import_cold_sync, relay_exported_sweep, sign_sweep,
+    ColdSyncFile, ColdSyncImport, ColdSyncKind, UnsignedSweep,
+};
+pub use restore::{
+    estimate_restore_height, height_at_timestamp, resolve_restore_height, RestoreHeight,
+    RestoreHeightStore, RestoreSource, DEFAULT_RESTORE_MARGIN,
+};
+pub use rpc::{
+    ring_size_for_hard_fork, BlockHeader, BuiltTx, CertFingerprint, DaemonBlock, DaemonInfo,
+    DaemonTransaction, HardForkInfo, IncomingTransfer, KeyImageImport, MultisigKexRound,
+    MultisigStatus, MultisigTxSet, OutgoingTransfer, RetryConfig, RpcLogin, SignedKeyImage,
+    SignedTransfer, TransferDestination, TransferOptions, TransferPriority, TransferReceipt,
+    TxDescription, TxKeyCheck, WalletBalance, WalletConnectionConfig, XmrDaemon, XmrWallet,
+    DEFAULT_RING_SIZE, MIN_RING_SIZE,
+};
+pub use scanner::{ScannedOutput, ViewKeyScanner};
 pub use types::{CryptoError, Result, XmrWalletError};
-pub use watcher::{LockWatcher, WatcherConfig, WatcherEvent, WatcherState};
+pub use watcher::{
+    open_view_only_wallet, ChainView, LockBackend, LockWatcher, TxProofLock, WatcherConfig,
+    WatcherEvent, WatcherState, WatcherStore,
+};
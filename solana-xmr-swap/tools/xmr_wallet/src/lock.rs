@@ -0,0 +1,139 @@
+use std::path::Path;
+
+use anyhow::anyhow;
+use monero::{Address, Network, PublicKey};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::crypto::{joint_public_spend_key, to_monero_private_key, SecretKey, VerifiedKeyShare};
+use crate::rpc::{BuiltTx, TransferOptions, XmrWallet};
+use crate::storage::{read, write_new};
+use crate::types::{CryptoError, Result};
+
+/// Record of a lock transfer. `tx_id` and `tx_key` are what the counterparty
+/// needs to check the payment to `address` with `check_tx_key`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockReceipt {
+    pub tx_id: String,
+    pub tx_key: String,
+    pub address: String,
+    pub amount: u64,
+    pub fee: u64,
+}
+
+impl LockReceipt {
+    /// Writes the receipt to `path`, refusing to replace an existing one.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_new(path, self, "lock receipt")
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        read(path, "lock receipt")
+    }
+}
+
+/// A lock transfer built with `do_not_relay`, persisted at the receipt path
+/// before it is relayed. The receipt fields sit at the top level, so
+/// [`LockReceipt::load`] reads the same file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingLock {
+    #[serde(flatten)]
+    pub receipt: LockReceipt,
+    pub tx: BuiltTx,
+}
+
+impl PendingLock {
+    /// Writes the lock to `path`, refusing to replace an existing one.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_new(path, self, "lock receipt")
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        read(path, "lock receipt")
+    }
+}
+
+/// Sends `amount` piconero to the joint lock address without ever sending
+/// twice.
+///
+/// The transfer is built with `do_not_relay` and persisted with its receipt
+/// at `receipt_path` before it is relayed, so the tx key survives a relay
+/// that times out. When the receipt already exists, the lock is looked up
+/// in the wallet's outgoing transfers and the persisted transaction is
+/// relayed only if it is not there.
+pub async fn lock_xmr(
+    wallet: &XmrWallet,
+    amount: u64,
+    joint_address: &Address,
+    options: &TransferOptions,
+    receipt_path: &Path,
+) -> Result<LockReceipt> {
+    if amount == 0 {
+        return Err(anyhow!("lock amount must be greater than zero").into());
+    }
+    let address = joint_address.to_string();
+
+    let pending = if receipt_path.exists() {
+        let pending = PendingLock::load(receipt_path)?;
+        if pending.receipt.address != address || pending.receipt.amount != amount {
+            return Err(anyhow!(
+                "lock receipt {} pays {} to {}, not {amount} to {address}",
+                receipt_path.display(),
+                pending.receipt.amount,
+                pending.receipt.address
+            )
+            .into());
+        }
+        let sent = wallet
+            .get_outgoing_transfers()
+            .await?
+            .iter()
+            .any(|transfer| transfer.tx_hash == pending.receipt.tx_id);
+        if sent {
+            debug!(tx_id = %pending.receipt.tx_id, "Lock already sent");
+            return Ok(pending.receipt);
+        }
+        debug!(path = %receipt_path.display(), "Relaying persisted Monero lock");
+        pending
+    } else {
+        let tx = wallet.build_transfer(&address, amount, options).await?;
+        options.check_fee(tx.fee)?;
+        let pending = PendingLock {
+            receipt: LockReceipt {
+                tx_id: tx.tx_hash.clone(),
+                tx_key: tx.tx_key.clone(),
+                address,
+                amount: tx.amount,
+                fee: tx.fee,
+            },
+            tx,
+        };
+        pending.save(receipt_path)?;
+        pending
+    };
+
+    wallet.relay_tx_checked(&pending.tx).await?;
+    debug!(tx_id = %pending.receipt.tx_id, fee = pending.receipt.fee, "Lock transfer sent");
+    Ok(pending.receipt)
+}
+
+/// Derives the joint lock address from public data only.
+///
+/// The public spend key is [`joint_public_spend_key`] of the verified
+/// shares and `adaptor_point` (A + B + T). Once every private share is
+/// known, `derive_claim_keys` yields the same address.
+pub fn derive_lock_address(
+    shares: &[VerifiedKeyShare],
+    adaptor_point: &crate::crypto::PublicKey,
+    view_key: &SecretKey,
+    network: Network,
+) -> Result<Address> {
+    if shares.is_empty() {
+        return Err(anyhow!("at least one verified key share is required").into());
+    }
+    let spend = joint_public_spend_key(shares, adaptor_point)?;
+    let public_spend =
+        PublicKey::from_slice(&spend.to_compressed()).map_err(|_| CryptoError::InvalidPoint)?;
+    let public_view = PublicKey::from_private_key(&to_monero_private_key(view_key)?);
+    Ok(Address::standard(network, public_spend, public_view))
+}
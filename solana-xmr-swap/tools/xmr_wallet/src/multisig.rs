@@ -0,0 +1,285 @@
+//! 2-of-2 wallet-rpc multisig as an alternative to the key-sum lock.
+//!
+//! The key-sum lock pays an address whose spend key is `alice + bob + t`,
+//! and whoever claims ends up holding that summed key. Here each party runs
+//! its own wallet-rpc instead: the two wallets exchange multisig messages
+//! until both hold the same joint address, and spending from it takes a
+//! signature from each. Neither side ever handles a full private spend key.
+//!
+//! Setup: [`prepare_multisig_wallet`] creates the wallet and returns the
+//! first message; each party passes the other's latest message to
+//! [`advance_multisig`] until it reports the joint address. Spending: once
+//! the lock is mined both parties swap [`export_multisig_sync`] output
+//! through [`import_multisig_sync`], one builds the sweep with
+//! [`propose_multisig_sweep`] and the other checks, signs and broadcasts it
+//! with [`cosign_multisig_sweep`] and [`submit_multisig_sweep`].
+//!
+//! Which of the two locks a swap uses is recorded once per swap as a
+//! [`LockMechanism`] in a [`LockMechanismStore`], so locking, watching and
+//! claiming all follow the same one.
+
+use std::path::Path;
+
+use anyhow::anyhow;
+use monero::Address;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::claim::check_described_sweep;
+use crate::rpc::{MultisigTxSet, TransferOptions, TxKeyCheck, XmrDaemon, XmrWallet};
+use crate::storage::{read, write_new};
+use crate::types::Result;
+use crate::watcher::LockBackend;
+
+/// Signatures needed to spend.
+pub const MULTISIG_THRESHOLD: u32 = 2;
+
+/// Signers in total: one wallet per party.
+pub const MULTISIG_SIGNERS: u32 = 2;
+
+/// How the XMR side of a swap is locked.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mechanism", rename_all = "snake_case")]
+pub enum LockMechanism {
+    /// Address from the summed key shares; claimed with the combined key.
+    #[default]
+    KeySum,
+    /// 2-of-2 wallet-rpc multisig on `address`; spent with a signature
+    /// from each party.
+    Multisig { address: String },
+}
+
+/// Where each swap's [`LockMechanism`] is kept, keyed by swap id.
+pub trait LockMechanismStore {
+    fn save_lock_mechanism(&self, swap_id: &str, mechanism: &LockMechanism) -> Result<()>;
+    fn load_lock_mechanism(&self, swap_id: &str) -> Result<Option<LockMechanism>>;
+}
+
+impl LockMechanism {
+    /// The mechanism recorded for `swap_id`. Swaps with none recorded use
+    /// the key-sum lock, as every swap did before multisig.
+    pub fn load<S: LockMechanismStore>(store: &S, swap_id: &str) -> Result<Self> {
+        Ok(store.load_lock_mechanism(swap_id)?.unwrap_or_default())
+    }
+
+    /// Records the mechanism for `swap_id`. A swap keeps the mechanism it
+    /// was first recorded with; recording another one is refused.
+    pub fn record<S: LockMechanismStore>(&self, store: &S, swap_id: &str) -> Result<()> {
+        match store.load_lock_mechanism(swap_id)? {
+            Some(recorded) if recorded == *self => Ok(()),
+            Some(recorded) => Err(anyhow!(
+                "swap {swap_id} is recorded with the {} lock, not the {} lock",
+                recorded.name(),
+                self.name()
+            )
+            .into()),
+            None => store.save_lock_mechanism(swap_id, self),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LockMechanism::KeySum => "key-sum",
+            LockMechanism::Multisig { .. } => "multisig",
+        }
+    }
+}
+
+/// Where one party stands after [`advance_multisig`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultisigRound {
+    /// Message for the counterparty, if it still needs one from us.
+    pub info: Option<String>,
+    /// The joint address, once this wallet's key exchange is complete.
+    pub address: Option<String>,
+}
+
+/// Creates the multisig wallet `filename`, encrypted with `password`, and
+/// returns the message to send the counterparty. The wallet will hold a
+/// multisig key share, so an empty password is refused.
+pub async fn prepare_multisig_wallet(
+    wallet: &XmrWallet,
+    filename: &str,
+    password: &str,
+) -> Result<String> {
+    if password.is_empty() {
+        return Err(anyhow!("refusing to create a multisig wallet without a password").into());
+    }
+    wallet
+        .create_wallet(filename, Some(password), "English")
+        .await?;
+    wallet.prepare_multisig().await
+}
+
+/// Feeds the counterparty's latest message to the open wallet: the first
+/// makes it a 2-of-2 multisig wallet, later ones run the remaining key
+/// exchange rounds.
+pub async fn advance_multisig(
+    wallet: &XmrWallet,
+    peer_info: &str,
+    password: &str,
+) -> Result<MultisigRound> {
+    let status = wallet.is_multisig().await?;
+    if status.ready {
+        return Err(anyhow!("multisig key exchange is already complete").into());
+    }
+    let peers = [peer_info.to_string()];
+    let round = if status.multisig {
+        wallet.exchange_multisig_keys(&peers, password).await?
+    } else {
+        wallet
+            .make_multisig(&peers, MULTISIG_THRESHOLD, password)
+            .await?
+    };
+    let status = wallet.is_multisig().await?;
+    if !status.multisig
+        || status.threshold != MULTISIG_THRESHOLD
+        || status.total != MULTISIG_SIGNERS
+    {
+        return Err(anyhow!(
+            "wallet became {}-of-{} multisig, expected {MULTISIG_THRESHOLD}-of-{MULTISIG_SIGNERS}",
+            status.threshold,
+            status.total
+        )
+        .into());
+    }
+    debug!(
+        ready = status.ready,
+        "Advanced Monero multisig key exchange"
+    );
+    Ok(MultisigRound {
+        info: (!round.multisig_info.is_empty()).then_some(round.multisig_info),
+        address: (status.ready && !round.address.is_empty()).then_some(round.address),
+    })
+}
+
+/// Refreshes the open multisig wallet and exports its partial key images
+/// for the counterparty.
+pub async fn export_multisig_sync(wallet: &XmrWallet) -> Result<String> {
+    ensure_ready(wallet).await?;
+    wallet.refresh(None).await?;
+    wallet.export_multisig_info().await
+}
+
+/// Imports the counterparty's [`export_multisig_sync`] output.
+pub async fn import_multisig_sync(wallet: &XmrWallet, peer_info: &str) -> Result<u64> {
+    ensure_ready(wallet).await?;
+    wallet.import_multisig_info(&[peer_info.to_string()]).await
+}
+
+/// Builds the sweep of the joint address to `destination`, carrying this
+/// wallet's signature, for the counterparty to cosign.
+pub async fn propose_multisig_sweep(
+    wallet: &XmrWallet,
+    destination: &str,
+    options: &TransferOptions,
+) -> Result<MultisigTxSet> {
+    ensure_ready(wallet).await?;
+    let txset = wallet.build_multisig_sweep(destination, options).await?;
+    check_multisig_sweep(wallet, &txset, destination, options).await?;
+    debug!(
+        txs = txset.tx_hashes.len(),
+        "Proposed Monero multisig sweep"
+    );
+    Ok(txset)
+}
+
+impl MultisigTxSet {
+    /// Writes the set to `path` for the counterparty, refusing to replace an
+    /// existing file.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_new(path, self, "multisig set")
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        read(path, "multisig set")
+    }
+}
+
+/// Signs the counterparty's proposed sweep after checking that it pays
+/// only `destination` and that its fee is within `options.max_fee`.
+pub async fn cosign_multisig_sweep(
+    wallet: &XmrWallet,
+    txset: &MultisigTxSet,
+    destination: &str,
+    options: &TransferOptions,
+) -> Result<MultisigTxSet> {
+    ensure_ready(wallet).await?;
+    check_multisig_sweep(wallet, txset, destination, options).await?;
+    wallet.sign_multisig(txset).await
+}
+
+/// Broadcasts a sweep signed by both parties.
+pub async fn submit_multisig_sweep(
+    wallet: &XmrWallet,
+    txset: &MultisigTxSet,
+) -> Result<Vec<String>> {
+    ensure_ready(wallet).await?;
+    let tx_hashes = wallet.submit_multisig(txset).await?;
+    debug!(?tx_hashes, "Submitted Monero multisig sweep");
+    Ok(tx_hashes)
+}
+
+async fn ensure_ready(wallet: &XmrWallet) -> Result<()> {
+    let status = wallet.is_multisig().await?;
+    if !status.ready {
+        return Err(anyhow!("wallet is not a complete multisig wallet").into());
+    }
+    Ok(())
+}
+
+async fn check_multisig_sweep(
+    wallet: &XmrWallet,
+    txset: &MultisigTxSet,
+    destination: &str,
+    options: &TransferOptions,
+) -> Result<()> {
+    let descriptions = wallet.describe_multisig_transfer(txset).await?;
+    check_described_sweep("multisig sweep", &descriptions, destination, options)
+}
+
+/// Checks the lock from the open multisig wallet's own transfers. Both
+/// parties' multisig wallets see payments to the joint address, so the
+/// sender's tx key is not needed.
+pub struct MultisigLock<'a> {
+    wallet: &'a XmrWallet,
+}
+
+impl<'a> MultisigLock<'a> {
+    pub fn new(wallet: &'a XmrWallet) -> Self {
+        Self { wallet }
+    }
+}
+
+impl LockBackend for MultisigLock<'_> {
+    async fn check_lock(
+        &self,
+        _daemon: &XmrDaemon,
+        tx_id: &str,
+        _tx_key: Option<&str>,
+        joint_address: &Address,
+        current_height: u64,
+    ) -> Result<TxKeyCheck> {
+        let address = self.wallet.get_primary_address().await?;
+        if address != joint_address.to_string() {
+            return Err(
+                anyhow!("open multisig wallet holds {address}, not {joint_address}").into(),
+            );
+        }
+        self.wallet.refresh(None).await?;
+        let transfers: Vec<_> = self
+            .wallet
+            .get_incoming_transfers_with_pool()
+            .await?
+            .into_iter()
+            .filter(|transfer| transfer.tx_hash == tx_id)
+            .collect();
+        // Every output of the lock shares its block; a pool entry has none.
+        let height = transfers.iter().find_map(|transfer| transfer.height);
+        Ok(TxKeyCheck {
+            received: transfers.iter().map(|transfer| transfer.amount).sum(),
+            confirmations: height.map_or(0, |height| current_height.saturating_sub(height)),
+            in_pool: !transfers.is_empty() && height.is_none(),
+        })
+    }
+}
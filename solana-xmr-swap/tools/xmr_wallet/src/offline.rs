@@ -0,0 +1,239 @@
+//! Claims whose spend key never touches an internet-connected host.
+//!
+//! This follows wallet-rpc's cold-signing flow. Outputs and key images move
+//! between a view-only online wallet and the offline full wallet as
+//! [`ColdSyncFile`]s. Once it has the key images, the view-only wallet
+//! builds the sweep as an [`UnsignedSweep`] ([`export_sweep`]); the offline
+//! wallet signs it into a [`PendingSweep`] ([`sign_sweep`]), and
+//! [`relay_exported_sweep`] later broadcasts the signed blobs through any
+//! daemon.
+
+use std::path::Path;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::claim::{
+    check_described_sweep, wait_for_unlocked_balance, ClaimProgress, PendingSweep, UnlockWait,
+};
+use crate::rpc::{BuiltTx, KeyImageImport, SignedKeyImage, TransferOptions, XmrDaemon, XmrWallet};
+use crate::storage::{read, write_new};
+use crate::types::Result;
+
+/// What a [`ColdSyncFile`] carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColdSyncKind {
+    /// Outputs seen by the view-only wallet, for the offline wallet.
+    Outputs,
+    /// Signed key images from the offline wallet, for the view-only wallet.
+    KeyImages,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ColdSyncFile {
+    Outputs {
+        outputs_data_hex: String,
+    },
+    KeyImages {
+        signed_key_images: Vec<SignedKeyImage>,
+    },
+}
+
+/// A sweep built by the view-only wallet, waiting for the offline wallet's
+/// signature.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnsignedSweep {
+    pub destination: String,
+    pub unsigned_txset: String,
+}
+
+impl UnsignedSweep {
+    /// Writes the sweep to `path`, refusing to replace an existing one.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_new(path, self, "unsigned sweep")
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        read(path, "unsigned sweep")
+    }
+}
+
+/// Outcome of [`import_cold_sync`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColdSyncImport {
+    Outputs { imported: u64 },
+    KeyImages(KeyImageImport),
+}
+
+impl ColdSyncFile {
+    pub fn kind(&self) -> ColdSyncKind {
+        match self {
+            ColdSyncFile::Outputs { .. } => ColdSyncKind::Outputs,
+            ColdSyncFile::KeyImages { .. } => ColdSyncKind::KeyImages,
+        }
+    }
+
+    /// Writes the file to `path`, refusing to replace an existing one.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_new(path, self, "cold sync file")
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        read(path, "cold sync file")
+    }
+}
+
+/// Exports everything of `kind` from the open wallet.
+pub async fn export_cold_sync(wallet: &XmrWallet, kind: ColdSyncKind) -> Result<ColdSyncFile> {
+    Ok(match kind {
+        ColdSyncKind::Outputs => ColdSyncFile::Outputs {
+            outputs_data_hex: wallet.export_outputs(true).await?,
+        },
+        ColdSyncKind::KeyImages => ColdSyncFile::KeyImages {
+            signed_key_images: wallet.export_key_images(true).await?,
+        },
+    })
+}
+
+pub async fn import_cold_sync(wallet: &XmrWallet, file: &ColdSyncFile) -> Result<ColdSyncImport> {
+    Ok(match file {
+        ColdSyncFile::Outputs { outputs_data_hex } => ColdSyncImport::Outputs {
+            imported: wallet.import_outputs(outputs_data_hex).await?,
+        },
+        ColdSyncFile::KeyImages { signed_key_images } => {
+            ColdSyncImport::KeyImages(wallet.import_key_images(signed_key_images).await?)
+        }
+    })
+}
+
+/// Online half of the claim: imports the offline wallet's `key_images`
+/// into the open view-only wallet, waits for the outputs to unlock and
+/// writes the unsigned sweep to `destination` to `path`.
+///
+/// An existing file at `path` is refused before anything else happens:
+/// sign and relay it rather than building another sweep of the same
+/// outputs.
+pub async fn export_sweep(
+    wallet: &XmrWallet,
+    key_images: &ColdSyncFile,
+    destination: &str,
+    unlock: &UnlockWait,
+    options: &TransferOptions,
+    path: &Path,
+    on_progress: impl FnMut(ClaimProgress),
+) -> Result<UnsignedSweep> {
+    refuse_existing(path)?;
+    let ColdSyncFile::KeyImages { signed_key_images } = key_images else {
+        return Err(anyhow!("expected key images from the offline wallet, got outputs").into());
+    };
+    wallet.refresh(None).await?;
+    let imported = wallet.import_key_images(signed_key_images).await?;
+    debug!(?imported, "Imported key images from offline wallet");
+    wait_for_unlocked_balance(wallet, unlock, on_progress).await?;
+    let sweep = UnsignedSweep {
+        destination: destination.to_string(),
+        unsigned_txset: wallet.build_unsigned_sweep(destination, options).await?,
+    };
+    sweep.save(path)?;
+    debug!(path = %path.display(), "Exported unsigned Monero sweep");
+    Ok(sweep)
+}
+
+/// Offline half of the claim: signs `unsigned` with the open full wallet
+/// and writes the signed blobs to `path` for [`relay_exported_sweep`].
+///
+/// The set is decoded first and refused unless it pays only
+/// `destination`, with no change and a fee within `options.max_fee`. An
+/// existing file at `path` is refused, as in [`export_sweep`].
+pub async fn sign_sweep(
+    wallet: &XmrWallet,
+    unsigned: &UnsignedSweep,
+    destination: &str,
+    options: &TransferOptions,
+    path: &Path,
+) -> Result<PendingSweep> {
+    refuse_existing(path)?;
+    if unsigned.destination != destination {
+        return Err(anyhow!(
+            "unsigned sweep pays {}, not {destination}",
+            unsigned.destination
+        )
+        .into());
+    }
+    let descriptions = wallet
+        .describe_unsigned_transfer(&unsigned.unsigned_txset)
+        .await?;
+    check_described_sweep("unsigned sweep", &descriptions, destination, options)?;
+    let signed = wallet.sign_transfer(&unsigned.unsigned_txset).await?;
+    let count = descriptions.len();
+    if signed.tx_hash_list.len() != count || signed.tx_raw_list.len() != count {
+        return Err(anyhow!("sign_transfer returned incomplete transaction lists").into());
+    }
+    let txs = descriptions
+        .iter()
+        .enumerate()
+        .map(|(index, description)| BuiltTx {
+            tx_hash: signed.tx_hash_list[index].clone(),
+            tx_key: signed.tx_key_list.get(index).cloned().unwrap_or_default(),
+            amount: description
+                .recipients
+                .iter()
+                .map(|recipient| recipient.amount)
+                .sum(),
+            fee: description.fee,
+            tx_blob: signed.tx_raw_list[index].clone(),
+            // Relayed through monerod from the blob, never by wallet-rpc.
+            tx_metadata: String::new(),
+        })
+        .collect();
+    let sweep = PendingSweep {
+        destination: destination.to_string(),
+        txs,
+    };
+    sweep.save(path)?;
+    debug!(path = %path.display(), fee = sweep.fee(), "Signed exported Monero sweep");
+    Ok(sweep)
+}
+
+/// Broadcasts an exported sweep through `daemon`. Transactions the daemon
+/// already has, in the pool or mined, are not submitted again, so an
+/// interrupted relay can be rerun. A submission that fails is looked up
+/// once more: its answer may have been lost after the daemon took it.
+pub async fn relay_exported_sweep(daemon: &XmrDaemon, sweep: &PendingSweep) -> Result<Vec<String>> {
+    let tx_hashes = sweep.tx_hashes();
+    let known = daemon.get_transactions(&tx_hashes).await?;
+    for tx in &sweep.txs {
+        if known.iter().any(|known| known.tx_hash == tx.tx_hash) {
+            debug!(tx_hash = %tx.tx_hash, "Daemon already has exported transaction");
+            continue;
+        }
+        if tx.tx_blob.is_empty() {
+            return Err(anyhow!("exported transaction {} has no tx blob", tx.tx_hash).into());
+        }
+        if let Err(err) = daemon.send_raw_transaction(&tx.tx_blob).await {
+            let known = daemon
+                .get_transactions(std::slice::from_ref(&tx.tx_hash))
+                .await?;
+            if known.is_empty() {
+                return Err(err);
+            }
+            warn!(tx_hash = %tx.tx_hash, error = %err, "send_raw_transaction failed but the daemon has the transaction");
+            continue;
+        }
+        debug!(tx_hash = %tx.tx_hash, "Relayed exported transaction");
+    }
+    Ok(tx_hashes)
+}
+
+fn refuse_existing(path: &Path) -> Result<()> {
+    if path.exists() {
+        return Err(anyhow!(
+            "sweep export {} already exists; relay it instead of building another",
+            path.display()
+        )
+        .into());
+    }
+    Ok(())
+}
@@ -0,0 +1,127 @@
+//! Restore heights for claim wallets.
+//!
+//! Restoring from genesis scans the whole chain, and restoring above the
+//! lock misses it without any error. The height is taken from the lock's
+//! block as the watcher saw it, less a safety margin, or else estimated from
+//! the time the lock was first seen using daemon block timestamps.
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::rpc::XmrDaemon;
+use crate::types::Result;
+use crate::watcher::{WatcherState, WatcherStore};
+
+/// Blocks subtracted from the lock height: about a day of two-minute blocks,
+/// which also covers block timestamps that run ahead of the clock.
+pub const DEFAULT_RESTORE_MARGIN: u64 = 720;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreSource {
+    /// The watcher's observed lock height.
+    LockHeight,
+    /// Estimated from the lock timestamp with daemon block headers.
+    LockTimestamp,
+    /// Given by the operator.
+    Manual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestoreHeight {
+    pub height: u64,
+    pub source: RestoreSource,
+}
+
+impl RestoreHeight {
+    pub fn from_lock_height(lock_height: u64, margin: u64) -> Self {
+        Self {
+            height: lock_height.saturating_sub(margin),
+            source: RestoreSource::LockHeight,
+        }
+    }
+
+    pub fn manual(height: u64) -> Self {
+        Self {
+            height,
+            source: RestoreSource::Manual,
+        }
+    }
+}
+
+/// Where the chosen restore height is kept, keyed by swap id.
+pub trait RestoreHeightStore {
+    fn save_restore_height(&self, swap_id: &str, height: &RestoreHeight) -> Result<()>;
+    fn load_restore_height(&self, swap_id: &str) -> Result<Option<RestoreHeight>>;
+}
+
+/// Height of the last block whose timestamp is at or before `timestamp`,
+/// found by bisecting daemon block headers.
+pub async fn height_at_timestamp(daemon: &XmrDaemon, timestamp: u64) -> Result<u64> {
+    let tip = daemon.get_info().await?.height.saturating_sub(1);
+    let (mut low, mut high) = (0, tip);
+    while low < high {
+        let mid = low + (high - low).div_ceil(2);
+        if daemon.get_block_header_by_height(mid).await?.timestamp <= timestamp {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    debug!(
+        timestamp,
+        height = low,
+        "Estimated Monero height for timestamp"
+    );
+    Ok(low)
+}
+
+/// Restore height estimated from the time the lock was sent or first seen.
+pub async fn estimate_restore_height(
+    daemon: &XmrDaemon,
+    lock_timestamp: u64,
+    margin: u64,
+) -> Result<RestoreHeight> {
+    let height = height_at_timestamp(daemon, lock_timestamp).await?;
+    Ok(RestoreHeight {
+        height: height.saturating_sub(margin),
+        source: RestoreSource::LockTimestamp,
+    })
+}
+
+/// Picks the restore height for `swap_id` and persists it.
+///
+/// A height already stored for the swap wins, so every claim attempt
+/// restores from the same place. Otherwise the watcher's lock height is
+/// used, then its first sighting of the lock (or `lock_timestamp`) estimated
+/// through `daemon`. With none of these there is nothing safe to pick.
+pub async fn resolve_restore_height<S: WatcherStore + RestoreHeightStore>(
+    store: &S,
+    daemon: &XmrDaemon,
+    swap_id: &str,
+    lock_timestamp: Option<u64>,
+    margin: u64,
+) -> Result<RestoreHeight> {
+    if let Some(stored) = store.load_restore_height(swap_id)? {
+        return Ok(stored);
+    }
+    let watcher = store
+        .load_watcher_state(swap_id)?
+        .unwrap_or_else(WatcherState::new);
+    let height = match (
+        watcher.lock_height(),
+        lock_timestamp.or(watcher.first_seen_at()),
+    ) {
+        (Some(lock_height), _) => RestoreHeight::from_lock_height(lock_height, margin),
+        (None, Some(timestamp)) => estimate_restore_height(daemon, timestamp, margin).await?,
+        (None, None) => {
+            return Err(anyhow!(
+                "no restore height for swap {swap_id}: the lock was never observed"
+            )
+            .into())
+        }
+    };
+    store.save_restore_height(swap_id, &height)?;
+    Ok(height)
+}
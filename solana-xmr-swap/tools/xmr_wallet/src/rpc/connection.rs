@@ -0,0 +1,293 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use anyhow::anyhow;
+use rustls::client::{ServerCertVerified, ServerCertVerifier};
+use rustls::{Certificate, ClientConfig, PrivateKey, ServerName};
+use serde::{Deserialize, Deserializer};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+use crate::types::Result;
+
+/// `--rpc-login` credentials for HTTP digest authentication.
+#[derive(Clone, PartialEq, Eq)]
+pub struct RpcLogin {
+    pub username: String,
+    pub password: Zeroizing<String>,
+}
+
+impl FromStr for RpcLogin {
+    type Err = crate::types::XmrWalletError;
+
+    /// Parses `username:password`, the form monerod and wallet-rpc take.
+    fn from_str(value: &str) -> Result<Self> {
+        let (username, password) = value
+            .split_once(':')
+            .ok_or_else(|| anyhow!("RPC login must be username:password"))?;
+        if username.is_empty() {
+            return Err(anyhow!("RPC login has an empty username").into());
+        }
+        Ok(Self {
+            username: username.to_string(),
+            password: Zeroizing::new(password.to_string()),
+        })
+    }
+}
+
+impl fmt::Debug for RpcLogin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RpcLogin")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+impl<'de> Deserialize<'de> for RpcLogin {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = Zeroizing::new(String::deserialize(deserializer)?);
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// SHA-256 fingerprint of a server's leaf certificate, as printed by
+/// `openssl x509 -noout -fingerprint -sha256`. Colons are optional.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct CertFingerprint(pub [u8; 32]);
+
+impl CertFingerprint {
+    pub fn of(der: &[u8]) -> Self {
+        Self(Sha256::digest(der).into())
+    }
+}
+
+impl FromStr for CertFingerprint {
+    type Err = crate::types::XmrWalletError;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let digits: String = value.chars().filter(|c| *c != ':').collect();
+        let bytes = hex::decode(digits.trim())
+            .map_err(|err| anyhow!("invalid certificate fingerprint: {err}"))?;
+        let bytes = <[u8; 32]>::try_from(bytes.as_slice())
+            .map_err(|_| anyhow!("certificate fingerprint must be 32 bytes of SHA-256"))?;
+        Ok(Self(bytes))
+    }
+}
+
+impl fmt::Debug for CertFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CertFingerprint({})", hex::encode(self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for CertFingerprint {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Accepts exactly the server certificate with the pinned fingerprint. The
+/// chain and host name are not checked, so wallet-rpc's self-signed
+/// certificate works; the handshake signature is still verified against it.
+struct PinnedCert(CertFingerprint);
+
+impl ServerCertVerifier for PinnedCert {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        if CertFingerprint::of(&end_entity.0) == self.0 {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(
+                "server certificate does not match the pinned fingerprint".to_string(),
+            ))
+        }
+    }
+}
+
+/// How to reach a wallet-rpc or monerod endpoint that is not an open
+/// localhost port: digest credentials, the certificates to trust and an
+/// HTTP timeout. The default is a plain connection.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WalletConnectionConfig {
+    /// Answered when the server asks for digest authentication.
+    #[serde(default)]
+    pub login: Option<RpcLogin>,
+    /// PEM CA certificates to trust for `https` URLs instead of the system
+    /// roots. The host name is still checked against the certificate.
+    #[serde(default)]
+    pub ca_cert: Option<PathBuf>,
+    /// Pins the server to the certificate with this SHA-256 fingerprint,
+    /// whoever issued it and whatever names it holds. Excludes `ca_cert`.
+    #[serde(default)]
+    pub cert_sha256: Option<CertFingerprint>,
+    /// PEM client certificate, for servers started with
+    /// `--rpc-ssl-allowed-certificates`. Needs `client_key`.
+    #[serde(default)]
+    pub client_cert: Option<PathBuf>,
+    /// PKCS#8 PEM key of `client_cert`.
+    #[serde(default)]
+    pub client_key: Option<PathBuf>,
+    /// Caps each HTTP request, including the TLS handshake and the digest
+    /// challenge. The retry timeout still bounds the whole attempt.
+    #[serde(default, rename = "timeout_ms", deserialize_with = "millis")]
+    pub timeout: Option<Duration>,
+}
+
+impl WalletConnectionConfig {
+    /// Reads the wallet-rpc connection from `XMR_WALLET_RPC_LOGIN`,
+    /// `XMR_WALLET_RPC_CA_CERT`, `XMR_WALLET_RPC_CERT_SHA256`,
+    /// `XMR_WALLET_RPC_CLIENT_CERT`, `XMR_WALLET_RPC_CLIENT_KEY` and
+    /// `XMR_WALLET_RPC_REQUEST_TIMEOUT_MS`.
+    pub fn from_env() -> Result<Self> {
+        Self::from_env_prefix("XMR_WALLET_RPC")
+    }
+
+    /// Like [`Self::from_env`], for monerod: `XMR_DAEMON_RPC_LOGIN` and so on.
+    pub fn daemon_from_env() -> Result<Self> {
+        Self::from_env_prefix("XMR_DAEMON_RPC")
+    }
+
+    fn from_env_prefix(prefix: &str) -> Result<Self> {
+        let var = |name: &str| {
+            std::env::var(format!("{prefix}_{name}"))
+                .ok()
+                .filter(|value| !value.is_empty())
+        };
+        let login = var("LOGIN")
+            .map(Zeroizing::new)
+            .map(|value| value.parse())
+            .transpose()?;
+        let timeout = var("REQUEST_TIMEOUT_MS")
+            .map(|value| {
+                value
+                    .parse()
+                    .map(Duration::from_millis)
+                    .map_err(|err| anyhow!("invalid {prefix}_REQUEST_TIMEOUT_MS: {err}"))
+            })
+            .transpose()?;
+        let config = Self {
+            login,
+            ca_cert: var("CA_CERT").map(PathBuf::from),
+            cert_sha256: var("CERT_SHA256").map(|value| value.parse()).transpose()?,
+            client_cert: var("CLIENT_CERT").map(PathBuf::from),
+            client_key: var("CLIENT_KEY").map(PathBuf::from),
+            timeout,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Loads the connection from a JSON file with the fields of this struct;
+    /// `login` is `"username:password"` and the timeout is `timeout_ms`.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let raw = Zeroizing::new(
+            std::fs::read_to_string(path)
+                .map_err(|err| anyhow!("read connection config {}: {err}", path.display()))?,
+        );
+        let config: Self = serde_json::from_str(&raw)
+            .map_err(|err| anyhow!("parse connection config {}: {err}", path.display()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.ca_cert.is_some() && self.cert_sha256.is_some() {
+            return Err(anyhow!("ca_cert and cert_sha256 cannot both be set").into());
+        }
+        if self.client_cert.is_some() != self.client_key.is_some() {
+            return Err(anyhow!("client_cert and client_key must be set together").into());
+        }
+        if self.timeout == Some(Duration::ZERO) {
+            return Err(anyhow!("request timeout must be positive").into());
+        }
+        Ok(())
+    }
+
+    /// Builds the HTTP client, reading the certificate files.
+    pub(crate) fn http_client(&self) -> Result<reqwest::Client> {
+        self.validate()?;
+        let mut builder = reqwest::Client::builder();
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(fingerprint) = self.cert_sha256 {
+            builder = builder.use_preconfigured_tls(self.pinned_tls(fingerprint)?);
+            return Ok(builder.build().map_err(anyhow::Error::from)?);
+        }
+        if let Some(path) = &self.ca_cert {
+            let certs = reqwest::Certificate::from_pem_bundle(&read(path)?)
+                .map_err(|err| anyhow!("invalid CA certificate {}: {err}", path.display()))?;
+            if certs.is_empty() {
+                return Err(anyhow!("no certificates in {}", path.display()).into());
+            }
+            builder = certs
+                .into_iter()
+                .fold(builder.tls_built_in_root_certs(false), |builder, cert| {
+                    builder.add_root_certificate(cert)
+                });
+        }
+        if let (Some(cert), Some(key)) = (&self.client_cert, &self.client_key) {
+            let key_pem = Zeroizing::new(read(key)?);
+            let identity = reqwest::Identity::from_pkcs8_pem(&read(cert)?, &key_pem)
+                .map_err(|err| anyhow!("invalid client certificate {}: {err}", cert.display()))?;
+            builder = builder.identity(identity);
+        }
+        Ok(builder.build().map_err(anyhow::Error::from)?)
+    }
+
+    /// rustls settings that trust only the pinned certificate, with the
+    /// client certificate if one is configured.
+    fn pinned_tls(&self, fingerprint: CertFingerprint) -> Result<ClientConfig> {
+        let tls = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_custom_certificate_verifier(Arc::new(PinnedCert(fingerprint)));
+        let (Some(cert), Some(key)) = (&self.client_cert, &self.client_key) else {
+            return Ok(tls.with_no_client_auth());
+        };
+        let chain = rustls_pemfile::certs(&mut read(cert)?.as_slice())
+            .map_err(|err| anyhow!("invalid client certificate {}: {err}", cert.display()))?;
+        let key_pem = Zeroizing::new(read(key)?);
+        let key_der = rustls_pemfile::pkcs8_private_keys(&mut key_pem.as_slice())
+            .map_err(|err| anyhow!("invalid client key {}: {err}", key.display()))?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("no PKCS#8 key in {}", key.display()))?;
+        Ok(tls
+            .with_client_auth_cert(
+                chain.into_iter().map(Certificate).collect(),
+                PrivateKey(key_der),
+            )
+            .map_err(|err| anyhow!("invalid client certificate {}: {err}", cert.display()))?)
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+    std::fs::read(path).map_err(|err| anyhow!("read {}: {err}", path.display()).into())
+}
+
+fn millis<'de, D>(deserializer: D) -> std::result::Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_millis))
+}
@@ -0,0 +1,199 @@
+use serde::Deserialize;
+use serde_json::json;
+use tracing::debug;
+
+use crate::rpc::json_rpc::JsonRpcClient;
+use crate::rpc::{RetryConfig, WalletConnectionConfig};
+use crate::types::Result;
+
+/// Subset of daemon `get_info`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DaemonInfo {
+    /// Number of blocks in the daemon's chain (tip height + 1).
+    pub height: u64,
+    /// Height the daemon is syncing towards; 0 once synchronized.
+    #[serde(default)]
+    pub target_height: u64,
+    #[serde(default)]
+    pub synchronized: bool,
+    pub top_block_hash: String,
+    #[serde(default)]
+    pub tx_pool_size: u64,
+    #[serde(default)]
+    pub nettype: String,
+}
+
+impl DaemonInfo {
+    /// Blocks the daemon still has to download.
+    pub fn sync_lag(&self) -> u64 {
+        self.target_height.saturating_sub(self.height)
+    }
+}
+
+/// Subset of a daemon block header.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BlockHeader {
+    pub height: u64,
+    pub hash: String,
+    pub prev_hash: String,
+    pub depth: u64,
+    pub orphan_status: bool,
+    pub timestamp: u64,
+}
+
+/// Where the daemon knows a transaction to be.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DaemonTransaction {
+    pub tx_hash: String,
+    pub in_pool: bool,
+    #[serde(default)]
+    pub block_height: Option<u64>,
+    #[serde(default)]
+    pub double_spend_seen: bool,
+    /// Serialized transaction, for scanning it locally.
+    #[serde(default)]
+    pub as_hex: String,
+}
+
+/// A block as returned by `get_block`, without its blob.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DaemonBlock {
+    pub block_header: BlockHeader,
+    pub miner_tx_hash: String,
+    /// Non-coinbase transactions in the block.
+    #[serde(default)]
+    pub tx_hashes: Vec<String>,
+}
+
+/// Subset of daemon `hard_fork_info`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct HardForkInfo {
+    /// Hard fork version the daemon currently enforces.
+    pub version: u8,
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub earliest_height: u64,
+}
+
+/// Client for monerod's RPC interface. `XmrWallet::get_height` is the
+/// wallet's sync height; this reads the daemon's own chain and pool.
+pub struct XmrDaemon {
+    rpc: JsonRpcClient,
+}
+
+impl XmrDaemon {
+    /// Connects with the retry settings and the `XMR_DAEMON_RPC_*`
+    /// connection settings from the environment.
+    pub async fn connect(url: &str) -> Result<Self> {
+        let retry = RetryConfig::from_env();
+        let connection = WalletConnectionConfig::daemon_from_env()?;
+        Self::connect_with_connection(url, retry, &connection).await
+    }
+
+    pub async fn connect_with_config(url: &str, retry: RetryConfig) -> Result<Self> {
+        Self::connect_with_connection(url, retry, &WalletConnectionConfig::default()).await
+    }
+
+    pub async fn connect_with_connection(
+        url: &str,
+        retry: RetryConfig,
+        connection: &WalletConnectionConfig,
+    ) -> Result<Self> {
+        debug!(
+            rpc_url = url,
+            login = connection.login.is_some(),
+            "Connecting to Monero daemon RPC"
+        );
+        Ok(Self {
+            rpc: JsonRpcClient::new(url, retry, connection)?,
+        })
+    }
+
+    pub async fn get_info(&self) -> Result<DaemonInfo> {
+        debug!("Fetching Monero daemon info");
+        self.rpc.call_retried("get_info", json!({})).await
+    }
+
+    pub async fn hard_fork_info(&self) -> Result<HardForkInfo> {
+        debug!("Fetching Monero hard fork info");
+        self.rpc.call_retried("hard_fork_info", json!({})).await
+    }
+
+    pub async fn get_block_header_by_height(&self, height: u64) -> Result<BlockHeader> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            block_header: BlockHeader,
+        }
+
+        debug!(height, "Fetching Monero block header");
+        let rsp: Rsp = self
+            .rpc
+            .call_retried("get_block_header_by_height", json!({ "height": height }))
+            .await?;
+        Ok(rsp.block_header)
+    }
+
+    pub async fn get_block(&self, height: u64) -> Result<DaemonBlock> {
+        debug!(height, "Fetching Monero block");
+        self.rpc
+            .call_retried("get_block", json!({ "height": height }))
+            .await
+    }
+
+    /// Looks up transactions by id. Ids the daemon does not know are left
+    /// out of the result.
+    pub async fn get_transactions(&self, tx_ids: &[String]) -> Result<Vec<DaemonTransaction>> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            #[serde(default)]
+            txs: Vec<DaemonTransaction>,
+        }
+
+        debug!(count = tx_ids.len(), "Fetching Monero transactions");
+        let rsp: Rsp = self
+            .rpc
+            .other_retried("get_transactions", json!({ "txs_hashes": tx_ids }))
+            .await?;
+        Ok(rsp.txs)
+    }
+
+    /// Ids of the transactions currently in the daemon's pool.
+    pub async fn get_transaction_pool_hashes(&self) -> Result<Vec<String>> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            #[serde(default)]
+            tx_hashes: Vec<String>,
+        }
+
+        debug!("Fetching Monero transaction pool");
+        let rsp: Rsp = self
+            .rpc
+            .other_retried("get_transaction_pool_hashes", json!({}))
+            .await?;
+        Ok(rsp.tx_hashes)
+    }
+
+    /// Broadcasts a signed transaction blob. No wallet is involved, so a
+    /// transaction built elsewhere can be relayed from any host.
+    ///
+    /// Attempted once: a blob that reached the daemon but whose answer was
+    /// lost is refused as a double spend when sent again. Callers look the
+    /// transaction up with [`Self::get_transactions`] before resending.
+    pub async fn send_raw_transaction(&self, tx_blob: &str) -> Result<()> {
+        debug!("Submitting raw Monero transaction");
+        let _: serde_json::Value = self
+            .rpc
+            .other(
+                "send_raw_transaction",
+                json!({ "tx_as_hex": tx_blob, "do_not_relay": false }),
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn is_in_pool(&self, tx_id: &str) -> Result<bool> {
+        let pool = self.get_transaction_pool_hashes().await?;
+        Ok(pool.iter().any(|hash| hash == tx_id))
+    }
+}
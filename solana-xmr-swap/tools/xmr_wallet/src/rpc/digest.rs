@@ -0,0 +1,56 @@
+//! HTTP digest authentication as used by monerod and wallet-rpc
+//! `--rpc-login`, on top of the `digest_auth` crate.
+
+use anyhow::anyhow;
+use digest_auth::{AuthContext, WwwAuthenticateHeader};
+use reqwest::header::HeaderValue;
+
+use crate::rpc::RpcLogin;
+use crate::types::Result;
+
+/// A server challenge, reused for later requests with an increasing nonce
+/// count until the server rejects the nonce.
+#[derive(Debug, Clone)]
+pub(crate) struct DigestChallenge(WwwAuthenticateHeader);
+
+impl DigestChallenge {
+    /// Picks a digest challenge out of the server's `WWW-Authenticate`
+    /// headers. epee offers both `MD5` and `MD5-sess`; plain `MD5` wins.
+    pub(crate) fn from_headers<'a>(
+        headers: impl IntoIterator<Item = &'a HeaderValue>,
+    ) -> Result<Self> {
+        let mut challenges: Vec<WwwAuthenticateHeader> = headers
+            .into_iter()
+            .filter_map(|value| value.to_str().ok())
+            .filter(|value| {
+                value
+                    .trim_start()
+                    .get(..6)
+                    .is_some_and(|scheme| scheme.eq_ignore_ascii_case("digest"))
+            })
+            .filter_map(|value| digest_auth::parse(value).ok())
+            .collect();
+        challenges.sort_by_key(|challenge| challenge.algorithm.sess);
+        challenges
+            .into_iter()
+            .next()
+            .map(Self)
+            .ok_or_else(|| anyhow!("server did not offer digest authentication").into())
+    }
+
+    /// The `Authorization` header for a POST to `uri`, taking the next
+    /// nonce count.
+    pub(crate) fn authorize(&mut self, login: &RpcLogin, uri: &str) -> Result<String> {
+        let context = AuthContext::new_post::<_, _, _, &[u8]>(
+            login.username.as_str(),
+            login.password.as_str(),
+            uri,
+            None,
+        );
+        let header = self
+            .0
+            .respond(&context)
+            .map_err(|err| anyhow!("answer digest challenge: {err}"))?;
+        Ok(header.to_header_string())
+    }
+}
@@ -0,0 +1,204 @@
+use std::sync::{Mutex, PoisonError};
+
+use anyhow::anyhow;
+use reqwest::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::rpc::digest::DigestChallenge;
+use crate::rpc::{retry_with_timeout, RetryConfig, RpcLogin, WalletConnectionConfig};
+use crate::types::{Result, XmrWalletError};
+
+const STATUS_OK: &str = "OK";
+
+/// Plain HTTP JSON client for monerod and wallet-rpc, answering digest
+/// challenges when a login is configured.
+pub(crate) struct JsonRpcClient {
+    http: reqwest::Client,
+    url: String,
+    retry: RetryConfig,
+    login: Option<RpcLogin>,
+    challenge: Mutex<Option<DigestChallenge>>,
+}
+
+impl JsonRpcClient {
+    pub(crate) fn new(
+        url: &str,
+        retry: RetryConfig,
+        connection: &WalletConnectionConfig,
+    ) -> Result<Self> {
+        Ok(Self {
+            http: connection.http_client()?,
+            url: url.trim_end_matches('/').to_string(),
+            retry,
+            login: connection.login.clone(),
+            challenge: Mutex::new(None),
+        })
+    }
+
+    /// Calls `method` on the `/json_rpc` endpoint once. A call whose answer
+    /// is lost may still have reached the server, so methods that change
+    /// wallet state must not be sent twice.
+    pub(crate) async fn call<T>(&self, method: &'static str, params: Value) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        self.call_with(method, params, &self.once()).await
+    }
+
+    /// Like [`Self::call`], but retried. Only for reads, which are safe to
+    /// repeat.
+    pub(crate) async fn call_retried<T>(&self, method: &'static str, params: Value) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        self.call_with(method, params, &self.retry).await
+    }
+
+    async fn call_with<T>(
+        &self,
+        method: &'static str,
+        params: Value,
+        retry: &RetryConfig,
+    ) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        #[derive(Deserialize)]
+        struct RpcError {
+            code: i64,
+            message: String,
+        }
+
+        #[derive(Deserialize)]
+        struct Envelope {
+            result: Option<Value>,
+            error: Option<RpcError>,
+        }
+
+        let url = format!("{}/json_rpc", self.url);
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": "0",
+            "method": method,
+            "params": params,
+        });
+        retry_with_timeout(method, retry, || async {
+            let envelope: Envelope = serde_json::from_value(self.post(&url, &body).await?)
+                .map_err(|err| anyhow!("decode {method} response: {err}"))?;
+            if let Some(err) = envelope.error {
+                return Err(XmrWalletError::Rpc {
+                    method,
+                    code: err.code,
+                    message: err.message,
+                });
+            }
+            let result = envelope
+                .result
+                .ok_or_else(|| anyhow!("{method} returned no result"))?;
+            decode(method, result)
+        })
+        .await
+    }
+
+    /// Calls one of monerod's non-JSON-RPC endpoints, such as
+    /// `/send_raw_transaction`, once like [`Self::call`].
+    pub(crate) async fn other<T>(&self, path: &'static str, body: Value) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        self.other_with(path, body, &self.once()).await
+    }
+
+    /// Like [`Self::other`], but retried. Only for reads such as
+    /// `/get_transactions`.
+    pub(crate) async fn other_retried<T>(&self, path: &'static str, body: Value) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        self.other_with(path, body, &self.retry).await
+    }
+
+    async fn other_with<T>(&self, path: &'static str, body: Value, retry: &RetryConfig) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let url = format!("{}/{path}", self.url);
+        retry_with_timeout(path, retry, || async {
+            decode(path, self.post(&url, &body).await?)
+        })
+        .await
+    }
+
+    fn once(&self) -> RetryConfig {
+        RetryConfig {
+            max_retries: 0,
+            ..self.retry.clone()
+        }
+    }
+
+    /// Posts `body`, answering a digest challenge once. The challenge is
+    /// kept, so later requests authenticate without a round trip until the
+    /// server asks again.
+    async fn post(&self, url: &str, body: &Value) -> Result<Value> {
+        let mut response = self.send(url, body).await?;
+        if response.status() == StatusCode::UNAUTHORIZED {
+            let Some(login) = &self.login else {
+                return Err(anyhow!("{url} requires an RPC login").into());
+            };
+            let challenge =
+                DigestChallenge::from_headers(response.headers().get_all(WWW_AUTHENTICATE))?;
+            *self
+                .challenge
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = Some(challenge);
+            response = self.send(url, body).await?;
+            if response.status() == StatusCode::UNAUTHORIZED {
+                return Err(anyhow!("{url} rejected the RPC login for {}", login.username).into());
+            }
+        }
+        Ok(response
+            .error_for_status()
+            .map_err(anyhow::Error::from)?
+            .json()
+            .await
+            .map_err(anyhow::Error::from)?)
+    }
+
+    async fn send(&self, url: &str, body: &Value) -> Result<reqwest::Response> {
+        let mut request = self.http.post(url).json(body);
+        if let Some(login) = &self.login {
+            let mut challenge = self
+                .challenge
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            if let Some(challenge) = challenge.as_mut() {
+                let uri = reqwest::Url::parse(url).map_err(anyhow::Error::from)?;
+                request = request.header(AUTHORIZATION, challenge.authorize(login, uri.path())?);
+            }
+        }
+        Ok(request.send().await.map_err(anyhow::Error::from)?)
+    }
+}
+
+/// Checks the daemon's `status` field before decoding the response.
+fn decode<T>(label: &str, value: Value) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    match value.get("status").and_then(Value::as_str) {
+        Some(STATUS_OK) | None => {}
+        Some(status) => {
+            let reason = value
+                .get("reason")
+                .and_then(Value::as_str)
+                .filter(|reason| !reason.is_empty())
+                .map(|reason| format!(": {reason}"))
+                .unwrap_or_default();
+            return Err(anyhow!("{label} returned status {status}{reason}").into());
+        }
+    }
+    serde_json::from_value(value).map_err(|err| anyhow!("decode {label} response: {err}").into())
+}
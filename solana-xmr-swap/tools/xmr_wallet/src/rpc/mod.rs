@@ -1,5 +1,21 @@
+mod connection;
+mod daemon;
+mod digest;
+mod json_rpc;
+mod options;
 mod wallet;
 mod retry;
 
-pub use wallet::XmrWallet;
+pub use connection::{CertFingerprint, RpcLogin, WalletConnectionConfig};
+pub use daemon::{
+    BlockHeader, DaemonBlock, DaemonInfo, DaemonTransaction, HardForkInfo, XmrDaemon,
+};
+pub use options::{
+    ring_size_for_hard_fork, TransferOptions, TransferPriority, DEFAULT_RING_SIZE, MIN_RING_SIZE,
+};
+pub use wallet::{
+    BuiltTx, IncomingTransfer, KeyImageImport, MultisigKexRound, MultisigStatus, MultisigTxSet,
+    OutgoingTransfer, SignedKeyImage, SignedTransfer, TransferDestination, TransferReceipt,
+    TxDescription, TxKeyCheck, WalletBalance, XmrWallet,
+};
 pub use retry::{retry_with_timeout, RetryConfig};
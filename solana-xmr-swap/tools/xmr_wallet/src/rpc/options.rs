@@ -0,0 +1,102 @@
+use anyhow::anyhow;
+
+use crate::rpc::XmrDaemon;
+use crate::types::Result;
+
+/// Ring size required since hard fork 15.
+pub const DEFAULT_RING_SIZE: u64 = 16;
+
+/// Smallest ring [`TransferOptions::check`] accepts.
+pub const MIN_RING_SIZE: u64 = 2;
+
+/// Ring size consensus requires at hard fork `version`.
+pub fn ring_size_for_hard_fork(version: u8) -> u64 {
+    match version {
+        0..=5 => 3,
+        6 => 5,
+        7 => 7,
+        8..=14 => 11,
+        _ => DEFAULT_RING_SIZE,
+    }
+}
+
+/// wallet-rpc fee priority, sent as its number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum TransferPriority {
+    Default = 0,
+    Unimportant = 1,
+    Elevated = 2,
+    Priority = 3,
+}
+
+/// How lock and claim transactions are built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferOptions {
+    pub priority: TransferPriority,
+    /// `None` uses [`DEFAULT_RING_SIZE`]; [`Self::validate_with_daemon`]
+    /// fills in the daemon's requirement.
+    pub ring_size: Option<u64>,
+    /// Largest total fee, in piconero, the transaction may pay. A built
+    /// transaction over the cap is dropped before relay.
+    pub max_fee: Option<u64>,
+}
+
+impl Default for TransferOptions {
+    fn default() -> Self {
+        Self {
+            priority: TransferPriority::Default,
+            ring_size: None,
+            max_fee: None,
+        }
+    }
+}
+
+impl TransferOptions {
+    pub fn ring_size(&self) -> u64 {
+        self.ring_size.unwrap_or(DEFAULT_RING_SIZE)
+    }
+
+    /// Rejects a ring size below [`MIN_RING_SIZE`]; a ring needs the real
+    /// input and at least one decoy. Checked before any transaction is built.
+    pub fn check(&self) -> Result<()> {
+        match self.ring_size {
+            Some(ring_size) if ring_size < MIN_RING_SIZE => {
+                Err(anyhow!("ring size {ring_size} is below the minimum of {MIN_RING_SIZE}").into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the ring size against `required`, filling it in if unset.
+    pub fn with_required_ring_size(mut self, required: u64) -> Result<Self> {
+        self.check()?;
+        match self.ring_size {
+            Some(ring_size) if ring_size != required => Err(anyhow!(
+                "ring size {ring_size} does not match the {required} consensus requires"
+            )
+            .into()),
+            _ => {
+                self.ring_size = Some(required);
+                Ok(self)
+            }
+        }
+    }
+
+    /// Like [`Self::with_required_ring_size`], with the requirement read
+    /// from the daemon's current hard fork.
+    pub async fn validate_with_daemon(self, daemon: &XmrDaemon) -> Result<Self> {
+        let info = daemon.hard_fork_info().await?;
+        self.with_required_ring_size(ring_size_for_hard_fork(info.version))
+    }
+
+    pub fn check_fee(&self, fee: u64) -> Result<()> {
+        match self.max_fee {
+            Some(max_fee) if fee > max_fee => Err(anyhow!(
+                "fee {fee} piconero exceeds the maximum of {max_fee}; not relaying"
+            )
+            .into()),
+            _ => Ok(()),
+        }
+    }
+}
@@ -1,42 +1,220 @@
-use std::collections::HashMap;
 use std::str::FromStr;
 
-use monero_rpc::{
-    monero::{Address, PrivateKey},
-    GetTransfersCategory, GetTransfersSelector, GotTransfer, RpcClientBuilder, SweepAllArgs,
-    TransferPriority, WalletClient,
-};
-use tracing::debug;
+use monero::{cryptonote::hash::Hash, Address, PrivateKey};
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{debug, warn};
 
 use anyhow::anyhow;
 
-use crate::rpc::{retry_with_timeout, RetryConfig};
+use crate::rpc::json_rpc::JsonRpcClient;
+use crate::rpc::{RetryConfig, TransferOptions, WalletConnectionConfig};
 use crate::types::Result;
 
+/// Password of view-only wallets: they hold no spend key and are reopened
+/// unattended, so there is nothing to prompt for.
+const VIEW_ONLY_PASSWORD: &str = "";
+
+/// Outcome of [`XmrWallet::transfer`]. `tx_key` is the transaction secret
+/// key; with `tx_hash` it lets the recipient verify the payment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferReceipt {
+    pub tx_hash: String,
+    pub tx_key: String,
+    pub amount: u64,
+    pub fee: u64,
+}
+
+/// Total and spendable balance of account 0, in piconero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct WalletBalance {
+    pub balance: u64,
+    pub unlocked_balance: u64,
+}
+
+/// Result of [`XmrWallet::check_tx_key`]: what a transaction paid to one
+/// address, as computed by the wallet from the tx key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct TxKeyCheck {
+    pub received: u64,
+    pub confirmations: u64,
+    pub in_pool: bool,
+}
+
+/// One transaction built with `do_not_relay`. `tx_blob` is the signed
+/// transaction and `tx_metadata` is what `relay_tx` broadcasts, so the fee
+/// and the exact transaction can be checked before anything is sent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuiltTx {
+    pub tx_hash: String,
+    pub tx_key: String,
+    pub amount: u64,
+    pub fee: u64,
+    pub tx_blob: String,
+    pub tx_metadata: String,
+}
+
+/// A sent transaction as listed by wallet-rpc `get_transfers`. `height` is
+/// `None` while it waits in the pool.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct OutgoingTransfer {
+    #[serde(rename = "txid")]
+    pub tx_hash: String,
+    pub amount: u64,
+    pub fee: u64,
+    #[serde(default, deserialize_with = "height_or_pool")]
+    pub height: Option<u64>,
+    #[serde(default)]
+    pub destinations: Vec<TransferDestination>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TransferDestination {
+    pub address: String,
+    pub amount: u64,
+}
+
+fn height_or_pool<'de, D>(deserializer: D) -> std::result::Result<Option<u64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let height = u64::deserialize(deserializer)?;
+    Ok((height > 0).then_some(height))
+}
+
+/// A key image with the offline wallet's signature over it, as exchanged
+/// by `export_key_images` and `import_key_images`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedKeyImage {
+    pub key_image: String,
+    pub signature: String,
+}
+
+/// Result of [`XmrWallet::import_key_images`], in piconero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct KeyImageImport {
+    pub height: u64,
+    pub spent: u64,
+    pub unspent: u64,
+}
+
+/// A received transaction as listed by wallet-rpc `get_transfers`.
+/// `height` is `None` while it waits in the pool.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct IncomingTransfer {
+    #[serde(rename = "txid")]
+    pub tx_hash: String,
+    pub amount: u64,
+    #[serde(default, deserialize_with = "height_or_pool")]
+    pub height: Option<u64>,
+}
+
+/// One multisig key exchange step: the joint address (once known) and the
+/// info to hand to the other signers, empty when no round is left.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct MultisigKexRound {
+    #[serde(default)]
+    pub address: String,
+    #[serde(default)]
+    pub multisig_info: String,
+}
+
+/// Result of wallet-rpc `is_multisig`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct MultisigStatus {
+    pub multisig: bool,
+    pub ready: bool,
+    pub threshold: u32,
+    pub total: u32,
+}
+
+/// A multisig transaction set as passed between signers. `tx_hashes` is
+/// filled in once the set has been built or signed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultisigTxSet {
+    pub tx_data_hex: String,
+    #[serde(default)]
+    pub tx_hashes: Vec<String>,
+}
+
+/// One transaction of a multisig or unsigned set as decoded by
+/// `describe_transfer`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TxDescription {
+    pub recipients: Vec<TransferDestination>,
+    #[serde(default)]
+    pub change_amount: u64,
+    #[serde(default)]
+    pub change_address: String,
+    pub fee: u64,
+}
+
+/// Result of wallet-rpc `sweep_all`; the lists hold one entry per built
+/// transaction.
+#[derive(Deserialize)]
+struct SweepAllRsp {
+    #[serde(default)]
+    tx_hash_list: Vec<String>,
+    #[serde(default)]
+    tx_key_list: Vec<String>,
+    #[serde(default)]
+    amount_list: Vec<u64>,
+    #[serde(default)]
+    fee_list: Vec<u64>,
+    #[serde(default)]
+    tx_blob_list: Vec<String>,
+    #[serde(default)]
+    tx_metadata_list: Vec<String>,
+    #[serde(default)]
+    multisig_txset: String,
+    #[serde(default)]
+    unsigned_txset: String,
+}
+
+/// Result of [`XmrWallet::sign_transfer`]: the signed set and, per
+/// transaction, its hash, raw blob and tx key.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SignedTransfer {
+    pub signed_txset: String,
+    #[serde(default)]
+    pub tx_hash_list: Vec<String>,
+    #[serde(default)]
+    pub tx_raw_list: Vec<String>,
+    #[serde(default)]
+    pub tx_key_list: Vec<String>,
+}
+
 pub struct XmrWallet {
-    client: WalletClient,
-    retry: RetryConfig,
+    rpc: JsonRpcClient,
 }
 
 impl XmrWallet {
+    /// Connects with the retry settings and the `XMR_WALLET_RPC_*`
+    /// connection settings from the environment.
     pub async fn connect(url: &str) -> Result<Self> {
         let retry = RetryConfig::from_env();
-        Self::connect_with_config(url, retry).await
+        let connection = WalletConnectionConfig::from_env()?;
+        Self::connect_with_connection(url, retry, &connection).await
     }
 
     pub async fn connect_with_config(url: &str, retry: RetryConfig) -> Result<Self> {
-        debug!(rpc_url = url, "Connecting to Monero wallet RPC");
-        let rpc_client = RpcClientBuilder::new().build(url)?;
-        let client = rpc_client.wallet();
-        Ok(Self { client, retry })
+        Self::connect_with_connection(url, retry, &WalletConnectionConfig::default()).await
     }
 
-    async fn with_retry<T, F, Fut>(&self, label: &'static str, action: F) -> Result<T>
-    where
-        F: FnMut() -> Fut,
-        Fut: std::future::Future<Output = Result<T>>,
-    {
-        retry_with_timeout(label, &self.retry, action).await
+    pub async fn connect_with_connection(
+        url: &str,
+        retry: RetryConfig,
+        connection: &WalletConnectionConfig,
+    ) -> Result<Self> {
+        debug!(
+            rpc_url = url,
+            login = connection.login.is_some(),
+            "Connecting to Monero wallet RPC"
+        );
+        Ok(Self {
+            rpc: JsonRpcClient::new(url, retry, connection)?,
+        })
     }
 
     pub async fn create_wallet(
@@ -46,157 +224,722 @@ impl XmrWallet {
         language: &str,
     ) -> Result<()> {
         debug!(wallet = filename, "Creating Monero wallet");
-        let filename = filename.to_string();
-        let password = password.map(|value| value.to_string());
-        let language = language.to_string();
-        self.with_retry("create_wallet", || async {
-            self.client
-                .create_wallet(filename.clone(), password.clone(), language.clone())
-                .await?;
-            Ok(())
-        })
-        .await
+        let mut params = json!({ "filename": filename, "language": language });
+        if let Some(password) = password {
+            params["password"] = json!(password);
+        }
+        self.rpc.call::<IgnoredAny>("create_wallet", params).await?;
+        Ok(())
     }
 
     pub async fn open_wallet(&self, filename: &str, password: Option<&str>) -> Result<()> {
         debug!(wallet = filename, "Opening Monero wallet");
-        let filename = filename.to_string();
-        let password = password.map(|value| value.to_string());
-        self.with_retry("open_wallet", || async {
-            self.client
-                .open_wallet(filename.clone(), password.clone())
-                .await?;
-            Ok(())
-        })
-        .await
+        let mut params = json!({ "filename": filename });
+        if let Some(password) = password {
+            params["password"] = json!(password);
+        }
+        self.rpc.call::<IgnoredAny>("open_wallet", params).await?;
+        Ok(())
+    }
+
+    /// Opens an existing wallet and refuses it, closing it again, unless
+    /// its primary address is `address`: a stale file left under the same
+    /// name must not be mistaken for this swap's wallet.
+    pub async fn open_wallet_for(
+        &self,
+        filename: &str,
+        password: Option<&str>,
+        address: &str,
+    ) -> Result<()> {
+        self.open_wallet(filename, password).await?;
+        let opened = self.get_primary_address().await?;
+        if opened != address {
+            self.close_wallet().await?;
+            return Err(anyhow!("wallet {filename} belongs to {opened}, not {address}").into());
+        }
+        Ok(())
+    }
+
+    /// Opens a wallet created by [`Self::generate_view_only`] for `address`.
+    pub async fn open_view_only(&self, filename: &str, address: &str) -> Result<()> {
+        self.open_wallet_for(filename, Some(VIEW_ONLY_PASSWORD), address)
+            .await
     }
 
     pub async fn close_wallet(&self) -> Result<()> {
         debug!("Closing Monero wallet");
-        self.with_retry("close_wallet", || async {
-            self.client.close_wallet().await?;
-            Ok(())
-        })
-        .await
+        self.rpc
+            .call::<IgnoredAny>("close_wallet", json!({}))
+            .await?;
+        Ok(())
     }
 
     pub async fn get_balance(&self) -> Result<u64> {
         debug!("Fetching Monero wallet balance");
-        self.with_retry("get_balance", || async {
-            let balance = self.client.get_balance(0, None).await?;
-            Ok(balance.balance.as_pico())
-        })
-        .await
+        let balance: WalletBalance = self
+            .rpc
+            .call_retried("get_balance", json!({ "account_index": 0 }))
+            .await?;
+        Ok(balance.balance)
+    }
+
+    /// Like [`Self::get_balance`], but also reports how much is spendable.
+    /// Received outputs stay locked for 10 blocks.
+    pub async fn get_balances(&self) -> Result<WalletBalance> {
+        debug!("Fetching Monero wallet balances");
+        self.rpc
+            .call_retried("get_balance", json!({ "account_index": 0 }))
+            .await
     }
 
     pub async fn get_primary_address(&self) -> Result<String> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            address: String,
+        }
+
         debug!("Fetching Monero primary address");
-        self.with_retry("get_primary_address", || async {
-            let addresses = self.client.get_address(0, None).await?;
-            Ok(addresses.address.to_string())
-        })
-        .await
+        let rsp: Rsp = self
+            .rpc
+            .call_retried("get_address", json!({ "account_index": 0 }))
+            .await?;
+        Ok(rsp.address)
     }
 
     pub async fn refresh(&self, start_height: Option<u64>) -> Result<()> {
         debug!(start_height, "Refreshing Monero wallet");
-        self.with_retry("refresh", || async {
-            self.client.refresh(start_height).await?;
-            Ok(())
-        })
-        .await
+        let params = match start_height {
+            Some(start_height) => json!({ "start_height": start_height }),
+            None => json!({}),
+        };
+        self.rpc
+            .call_retried::<IgnoredAny>("refresh", params)
+            .await?;
+        Ok(())
     }
 
     pub async fn get_height(&self) -> Result<u64> {
-        debug!("Fetching Monero wallet height");
-        self.with_retry("get_height", || async {
-            let height = self.client.get_height().await?;
-            Ok(height.get())
-        })
-        .await
-    }
+        #[derive(Deserialize)]
+        struct Rsp {
+            height: u64,
+        }
 
-    pub async fn get_incoming_transfers(&self) -> Result<Vec<GotTransfer>> {
-        self.with_retry("get_incoming_transfers", || async {
-            let mut selector = GetTransfersSelector::default();
-            let mut category_selector = HashMap::new();
-            category_selector.insert(GetTransfersCategory::In, true);
-            selector.category_selector = category_selector;
-            let transfers = self.client.get_transfers(selector).await?;
-            let mut results = Vec::new();
-            for values in transfers.values() {
-                results.extend(values.clone());
-            }
-            Ok(results)
-        })
-        .await
+        debug!("Fetching Monero wallet height");
+        let rsp: Rsp = self.rpc.call_retried("get_height", json!({})).await?;
+        Ok(rsp.height)
     }
 
+    /// Creates a spendable wallet from the claim keys, encrypted with
+    /// `password`. An empty password is refused: the wallet file holds the
+    /// full spend key.
     pub async fn import_spend_key(
         &self,
         spend_key_bytes: [u8; 32],
         view_key_bytes: [u8; 32],
         address: &str,
         filename: &str,
+        password: &str,
         restore_height: Option<u64>,
     ) -> Result<()> {
         debug!(wallet = filename, "Importing spend/view keys into wallet");
-        let address_str = address.to_string();
-        PrivateKey::from_slice(&spend_key_bytes)
+        let spend_key = PrivateKey::from_slice(&spend_key_bytes)
             .map_err(|err| anyhow!("invalid spend key: {err}"))?;
-        PrivateKey::from_slice(&view_key_bytes).map_err(|err| anyhow!("invalid view key: {err}"))?;
-        Address::from_str(&address_str).map_err(|err| anyhow!("invalid address: {err}"))?;
-        self.with_retry("generate_from_keys", || async {
-            let spend_key = PrivateKey::from_slice(&spend_key_bytes)
-                .map_err(|err| anyhow!("invalid spend key: {err}"))?;
-            let view_key = PrivateKey::from_slice(&view_key_bytes)
-                .map_err(|err| anyhow!("invalid view key: {err}"))?;
-            let address =
-                Address::from_str(&address_str).map_err(|err| anyhow!("invalid address: {err}"))?;
-            let args = monero_rpc::GenerateFromKeysArgs {
-                restore_height,
-                filename: filename.to_string(),
-                address,
-                spendkey: Some(spend_key),
-                viewkey: view_key,
-                password: String::new(),
-                autosave_current: Some(true),
-            };
-            self.client.generate_from_keys(args).await?;
-            Ok(())
-        })
+        let view_key = PrivateKey::from_slice(&view_key_bytes)
+            .map_err(|err| anyhow!("invalid view key: {err}"))?;
+        let address =
+            Address::from_str(address).map_err(|err| anyhow!("invalid address: {err}"))?;
+        if password.is_empty() {
+            return Err(anyhow!("refusing to store spend key in an unencrypted wallet").into());
+        }
+        self.generate_from_keys(
+            filename,
+            &address,
+            Some(&spend_key),
+            &view_key,
+            password,
+            restore_height,
+        )
         .await
     }
 
-    pub async fn sweep_all(&self, destination: &str) -> Result<String> {
-        debug!("Sweeping wallet balance");
-        Address::from_str(destination).map_err(|err| anyhow!("invalid destination address: {err}"))?;
-        let destination = destination.to_string();
-        self.with_retry("sweep_all", || async {
-            let address = Address::from_str(&destination)
-                .map_err(|err| anyhow!("invalid destination address: {err}"))?;
-            let args = SweepAllArgs {
-                address,
-                account_index: 0,
-                subaddr_indices: None,
-                priority: TransferPriority::Default,
-                mixin: 15,
-                ring_size: 16,
-                unlock_time: 0,
-                get_tx_keys: Some(true),
-                below_amount: None,
-                do_not_relay: None,
-                get_tx_hex: None,
-                get_tx_metadata: None,
-            };
-            let result = self.client.sweep_all(args).await?;
-            let first = result
-                .tx_hash_list
-                .first()
-                .map(|hash| hash.to_string())
-                .unwrap_or_default();
-            Ok(first)
-        })
+    /// Creates a view-only wallet for `address`; it sees incoming transfers
+    /// but cannot spend.
+    pub async fn generate_view_only(
+        &self,
+        view_key_bytes: [u8; 32],
+        address: &str,
+        filename: &str,
+        restore_height: Option<u64>,
+    ) -> Result<()> {
+        debug!(wallet = filename, "Generating view-only wallet");
+        let view_key = PrivateKey::from_slice(&view_key_bytes)
+            .map_err(|err| anyhow!("invalid view key: {err}"))?;
+        let address =
+            Address::from_str(address).map_err(|err| anyhow!("invalid address: {err}"))?;
+        self.generate_from_keys(
+            filename,
+            &address,
+            None,
+            &view_key,
+            VIEW_ONLY_PASSWORD,
+            restore_height,
+        )
         .await
     }
+
+    async fn generate_from_keys(
+        &self,
+        filename: &str,
+        address: &Address,
+        spend_key: Option<&PrivateKey>,
+        view_key: &PrivateKey,
+        password: &str,
+        restore_height: Option<u64>,
+    ) -> Result<()> {
+        let mut params = json!({
+            "filename": filename,
+            "address": address.to_string(),
+            "viewkey": view_key.to_string(),
+            "password": password,
+            "autosave_current": true,
+        });
+        if let Some(spend_key) = spend_key {
+            params["spendkey"] = json!(spend_key.to_string());
+        }
+        if let Some(restore_height) = restore_height {
+            params["restore_height"] = json!(restore_height);
+        }
+        self.rpc
+            .call::<IgnoredAny>("generate_from_keys", params)
+            .await?;
+        Ok(())
+    }
+
+    /// Calls `sweep_all` for the unlocked balance of account 0 without
+    /// relaying the result.
+    async fn sweep_all(
+        &self,
+        destination: &str,
+        options: &TransferOptions,
+        with_txs: bool,
+    ) -> Result<SweepAllRsp> {
+        options.check()?;
+        let address = Address::from_str(destination)
+            .map_err(|err| anyhow!("invalid destination address: {err}"))?;
+        let mut params = json!({
+            "address": address.to_string(),
+            "account_index": 0,
+            "priority": options.priority as u8,
+            "ring_size": options.ring_size(),
+            "unlock_time": 0,
+            "do_not_relay": true,
+        });
+        if with_txs {
+            params["get_tx_keys"] = json!(true);
+            params["get_tx_hex"] = json!(true);
+            params["get_tx_metadata"] = json!(true);
+        }
+        self.rpc.call("sweep_all", params).await
+    }
+
+    /// Builds, but does not relay, the transactions sweeping the unlocked
+    /// balance to `destination`. Nothing is broadcast, so this is safe to
+    /// retry; [`Self::relay_tx`] sends the result.
+    pub async fn build_sweep_all(
+        &self,
+        destination: &str,
+        options: &TransferOptions,
+    ) -> Result<Vec<BuiltTx>> {
+        debug!("Building Monero sweep");
+        let result = self.sweep_all(destination, options, true).await?;
+        let count = result.tx_hash_list.len();
+        if count == 0 {
+            return Err(anyhow!("sweep_all built no transactions").into());
+        }
+        if [
+            result.tx_key_list.len(),
+            result.tx_blob_list.len(),
+            result.tx_metadata_list.len(),
+            result.amount_list.len(),
+            result.fee_list.len(),
+        ]
+        .iter()
+        .any(|len| *len != count)
+        {
+            return Err(anyhow!("sweep_all returned incomplete transaction lists").into());
+        }
+        Ok((0..count)
+            .map(|index| BuiltTx {
+                tx_hash: result.tx_hash_list[index].clone(),
+                tx_key: result.tx_key_list[index].clone(),
+                amount: result.amount_list[index],
+                fee: result.fee_list[index],
+                tx_blob: result.tx_blob_list[index].clone(),
+                tx_metadata: result.tx_metadata_list[index].clone(),
+            })
+            .collect())
+    }
+
+    /// Builds the unsigned set sweeping the unlocked balance to
+    /// `destination` from a view-only wallet. Only the offline wallet
+    /// holding the spend key can sign it, with [`Self::sign_transfer`].
+    pub async fn build_unsigned_sweep(
+        &self,
+        destination: &str,
+        options: &TransferOptions,
+    ) -> Result<String> {
+        debug!("Building unsigned Monero sweep");
+        let result = self.sweep_all(destination, options, false).await?;
+        if result.unsigned_txset.is_empty() {
+            return Err(
+                anyhow!("sweep_all returned no unsigned set; is the wallet view-only?").into(),
+            );
+        }
+        Ok(result.unsigned_txset)
+    }
+
+    /// Decodes an unsigned set so its recipients and fee can be checked
+    /// before signing.
+    pub async fn describe_unsigned_transfer(
+        &self,
+        unsigned_txset: &str,
+    ) -> Result<Vec<TxDescription>> {
+        self.describe_transfer(json!({ "unsigned_txset": unsigned_txset }))
+            .await
+    }
+
+    /// Signs an unsigned set from a view-only wallet. The signed
+    /// transactions are returned as raw blobs and are not broadcast.
+    pub async fn sign_transfer(&self, unsigned_txset: &str) -> Result<SignedTransfer> {
+        debug!("Signing Monero transfer set");
+        self.rpc
+            .call(
+                "sign_transfer",
+                json!({ "unsigned_txset": unsigned_txset, "export_raw": true }),
+            )
+            .await
+    }
+
+    /// Broadcasts a transaction built with `do_not_relay`.
+    ///
+    /// Attempted once like [`Self::transfer`]: callers find out whether a
+    /// timed-out relay went through from [`Self::get_outgoing_transfers`].
+    pub async fn relay_tx(&self, tx: &BuiltTx) -> Result<String> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            tx_hash: String,
+        }
+
+        debug!(tx_hash = %tx.tx_hash, fee = tx.fee, "Relaying Monero transaction");
+        let rsp: Rsp = self
+            .rpc
+            .call("relay_tx", json!({ "hex": tx.tx_metadata }))
+            .await?;
+        Ok(rsp.tx_hash)
+    }
+
+    /// Relays `tx` and, if that fails, counts it as sent when the wallet
+    /// lists it anyway: a relay whose answer was lost may still have gone
+    /// through, and relaying it again would be refused as a double spend.
+    pub(crate) async fn relay_tx_checked(&self, tx: &BuiltTx) -> Result<()> {
+        let Err(err) = self.relay_tx(tx).await else {
+            return Ok(());
+        };
+        let relayed = self
+            .get_outgoing_transfers()
+            .await?
+            .iter()
+            .any(|transfer| transfer.tx_hash == tx.tx_hash);
+        if !relayed {
+            return Err(err);
+        }
+        warn!(tx_hash = %tx.tx_hash, error = %err, "relay_tx failed but the wallet lists the transaction");
+        Ok(())
+    }
+
+    /// Sent transactions of account 0, confirmed and pending, with their
+    /// destinations.
+    pub async fn get_outgoing_transfers(&self) -> Result<Vec<OutgoingTransfer>> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            #[serde(default)]
+            out: Vec<OutgoingTransfer>,
+            #[serde(default)]
+            pending: Vec<OutgoingTransfer>,
+        }
+
+        debug!("Fetching outgoing Monero transfers");
+        let rsp: Rsp = self
+            .rpc
+            .call_retried(
+                "get_transfers",
+                json!({ "out": true, "pending": true, "account_index": 0 }),
+            )
+            .await?;
+        Ok(rsp.out.into_iter().chain(rsp.pending).collect())
+    }
+
+    /// Builds, but does not relay, a transfer of `amount` piconero to
+    /// `destination`.
+    pub async fn build_transfer(
+        &self,
+        destination: &str,
+        amount: u64,
+        options: &TransferOptions,
+    ) -> Result<BuiltTx> {
+        debug!(amount, "Building Monero transfer");
+        options.check()?;
+        let destination = Address::from_str(destination)
+            .map_err(|err| anyhow!("invalid destination address: {err}"))?;
+        self.rpc
+            .call(
+                "transfer",
+                json!({
+                    "destinations": [{ "address": destination.to_string(), "amount": amount }],
+                    "priority": options.priority as u8,
+                    "ring_size": options.ring_size(),
+                    "do_not_relay": true,
+                    "get_tx_key": true,
+                    "get_tx_hex": true,
+                    "get_tx_metadata": true,
+                }),
+            )
+            .await
+    }
+
+    /// Sends `amount` piconero to `destination` and returns the tx key.
+    ///
+    /// The transfer is built first and dropped if its fee exceeds
+    /// `options.max_fee`; only [`Self::relay_tx`] broadcasts it.
+    pub async fn transfer(
+        &self,
+        destination: &str,
+        amount: u64,
+        options: &TransferOptions,
+    ) -> Result<TransferReceipt> {
+        let tx = self.build_transfer(destination, amount, options).await?;
+        options.check_fee(tx.fee)?;
+        self.relay_tx(&tx).await?;
+        Ok(TransferReceipt {
+            tx_hash: tx.tx_hash,
+            tx_key: tx.tx_key,
+            amount: tx.amount,
+            fee: tx.fee,
+        })
+    }
+
+    /// Exports the open wallet's outputs for an offline wallet to import.
+    /// `all` includes outputs exported before.
+    pub async fn export_outputs(&self, all: bool) -> Result<String> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            outputs_data_hex: String,
+        }
+
+        debug!(all, "Exporting Monero outputs");
+        let rsp: Rsp = self
+            .rpc
+            .call("export_outputs", json!({ "all": all }))
+            .await?;
+        Ok(rsp.outputs_data_hex)
+    }
+
+    /// Imports outputs exported by a view-only wallet and returns how many
+    /// were new.
+    pub async fn import_outputs(&self, outputs_data_hex: &str) -> Result<u64> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            num_imported: u64,
+        }
+
+        debug!("Importing Monero outputs");
+        let rsp: Rsp = self
+            .rpc
+            .call(
+                "import_outputs",
+                json!({ "outputs_data_hex": outputs_data_hex }),
+            )
+            .await?;
+        Ok(rsp.num_imported)
+    }
+
+    /// Signed key images of the open wallet's outputs. Only a wallet holding
+    /// the spend key can produce them.
+    pub async fn export_key_images(&self, all: bool) -> Result<Vec<SignedKeyImage>> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            #[serde(default)]
+            signed_key_images: Vec<SignedKeyImage>,
+        }
+
+        debug!(all, "Exporting Monero key images");
+        let rsp: Rsp = self
+            .rpc
+            .call("export_key_images", json!({ "all": all }))
+            .await?;
+        Ok(rsp.signed_key_images)
+    }
+
+    /// Imports key images from the offline wallet so a view-only wallet can
+    /// tell which of its outputs are spent.
+    pub async fn import_key_images(&self, images: &[SignedKeyImage]) -> Result<KeyImageImport> {
+        debug!(count = images.len(), "Importing Monero key images");
+        self.rpc
+            .call("import_key_images", json!({ "signed_key_images": images }))
+            .await
+    }
+
+    /// Received transactions of account 0, mined and in the pool.
+    pub async fn get_incoming_transfers_with_pool(&self) -> Result<Vec<IncomingTransfer>> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            #[serde(default, rename = "in")]
+            incoming: Vec<IncomingTransfer>,
+            #[serde(default)]
+            pool: Vec<IncomingTransfer>,
+        }
+
+        debug!("Fetching incoming Monero transfers");
+        let rsp: Rsp = self
+            .rpc
+            .call_retried(
+                "get_transfers",
+                json!({ "in": true, "pool": true, "account_index": 0 }),
+            )
+            .await?;
+        Ok(rsp.incoming.into_iter().chain(rsp.pool).collect())
+    }
+
+    /// First multisig key exchange message of the open wallet.
+    pub async fn prepare_multisig(&self) -> Result<String> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            multisig_info: String,
+        }
+
+        debug!("Preparing Monero multisig");
+        let rsp: Rsp = self.rpc.call("prepare_multisig", json!({})).await?;
+        Ok(rsp.multisig_info)
+    }
+
+    /// Turns the open wallet into a `threshold`-of-(`infos` + 1) multisig
+    /// wallet from the other signers' `prepare_multisig` messages.
+    pub async fn make_multisig(
+        &self,
+        infos: &[String],
+        threshold: u32,
+        password: &str,
+    ) -> Result<MultisigKexRound> {
+        debug!(
+            signers = infos.len() + 1,
+            threshold, "Making Monero multisig wallet"
+        );
+        self.rpc
+            .call(
+                "make_multisig",
+                json!({
+                    "multisig_info": infos,
+                    "threshold": threshold,
+                    "password": password,
+                }),
+            )
+            .await
+    }
+
+    /// Runs one further key exchange round with the other signers' latest
+    /// messages.
+    pub async fn exchange_multisig_keys(
+        &self,
+        infos: &[String],
+        password: &str,
+    ) -> Result<MultisigKexRound> {
+        debug!(signers = infos.len() + 1, "Exchanging Monero multisig keys");
+        self.rpc
+            .call(
+                "exchange_multisig_keys",
+                json!({ "multisig_info": infos, "password": password }),
+            )
+            .await
+    }
+
+    pub async fn is_multisig(&self) -> Result<MultisigStatus> {
+        self.rpc.call_retried("is_multisig", json!({})).await
+    }
+
+    /// Partial key images of the open multisig wallet's outputs. The other
+    /// signers import them before they can build or sign a spend.
+    pub async fn export_multisig_info(&self) -> Result<String> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            info: String,
+        }
+
+        debug!("Exporting Monero multisig info");
+        let rsp: Rsp = self.rpc.call("export_multisig_info", json!({})).await?;
+        Ok(rsp.info)
+    }
+
+    /// Imports the other signers' multisig info and returns how many outputs
+    /// it covered.
+    pub async fn import_multisig_info(&self, infos: &[String]) -> Result<u64> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            n_outputs: u64,
+        }
+
+        debug!(count = infos.len(), "Importing Monero multisig info");
+        let rsp: Rsp = self
+            .rpc
+            .call("import_multisig_info", json!({ "info": infos }))
+            .await?;
+        Ok(rsp.n_outputs)
+    }
+
+    /// Builds the multisig set sweeping the unlocked balance to
+    /// `destination`, signed by this wallet only.
+    pub async fn build_multisig_sweep(
+        &self,
+        destination: &str,
+        options: &TransferOptions,
+    ) -> Result<MultisigTxSet> {
+        debug!("Building Monero multisig sweep");
+        let result = self.sweep_all(destination, options, false).await?;
+        if result.multisig_txset.is_empty() {
+            return Err(
+                anyhow!("sweep_all returned no multisig set; is the wallet multisig?").into(),
+            );
+        }
+        Ok(MultisigTxSet {
+            tx_data_hex: result.multisig_txset,
+            tx_hashes: result.tx_hash_list,
+        })
+    }
+
+    /// Decodes a multisig set so its recipients and fee can be checked
+    /// before signing.
+    pub async fn describe_multisig_transfer(
+        &self,
+        txset: &MultisigTxSet,
+    ) -> Result<Vec<TxDescription>> {
+        self.describe_transfer(json!({ "multisig_txset": txset.tx_data_hex }))
+            .await
+    }
+
+    async fn describe_transfer(&self, params: serde_json::Value) -> Result<Vec<TxDescription>> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            #[serde(default)]
+            desc: Vec<TxDescription>,
+        }
+
+        let rsp: Rsp = self.rpc.call_retried("describe_transfer", params).await?;
+        Ok(rsp.desc)
+    }
+
+    /// Adds this wallet's signature to a multisig set.
+    pub async fn sign_multisig(&self, txset: &MultisigTxSet) -> Result<MultisigTxSet> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            tx_data_hex: String,
+            #[serde(default)]
+            tx_hash_list: Vec<String>,
+        }
+
+        debug!("Signing Monero multisig set");
+        let rsp: Rsp = self
+            .rpc
+            .call("sign_multisig", json!({ "tx_data_hex": txset.tx_data_hex }))
+            .await?;
+        Ok(MultisigTxSet {
+            tx_data_hex: rsp.tx_data_hex,
+            tx_hashes: rsp.tx_hash_list,
+        })
+    }
+
+    /// Broadcasts a fully signed multisig set.
+    ///
+    /// Attempted once like [`Self::relay_tx`].
+    pub async fn submit_multisig(&self, txset: &MultisigTxSet) -> Result<Vec<String>> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            tx_hash_list: Vec<String>,
+        }
+
+        debug!("Submitting Monero multisig set");
+        let rsp: Rsp = self
+            .rpc
+            .call(
+                "submit_multisig",
+                json!({ "tx_data_hex": txset.tx_data_hex }),
+            )
+            .await?;
+        Ok(rsp.tx_hash_list)
+    }
+
+    /// Checks how much transaction `tx_id` paid to `address`, using its tx
+    /// key. This needs no wallet keys for `address`.
+    pub async fn check_tx_key(
+        &self,
+        tx_id: &str,
+        tx_key: &str,
+        address: &str,
+    ) -> Result<TxKeyCheck> {
+        debug!(tx_id, "Checking transaction key");
+        let txid = Hash::from_str(tx_id).map_err(|err| anyhow!("invalid tx id: {err}"))?;
+        let tx_key = hex::decode(tx_key).map_err(|err| anyhow!("invalid tx key: {err}"))?;
+        if tx_key.len() % 32 != 0 || tx_key.is_empty() {
+            return Err(anyhow!("tx key must be one or more 32-byte keys").into());
+        }
+        let address =
+            Address::from_str(address).map_err(|err| anyhow!("invalid address: {err}"))?;
+        self.rpc
+            .call_retried(
+                "check_tx_key",
+                json!({
+                    "txid": hex::encode(txid.as_bytes()),
+                    "tx_key": hex::encode(tx_key),
+                    "address": address.to_string(),
+                }),
+            )
+            .await
+    }
+
+    /// Like [`Self::check_tx_key`], but from a proof that the sender made
+    /// with `get_tx_proof` over `message` instead of the tx key itself. A
+    /// proof that does not verify is an error.
+    pub async fn check_tx_proof(
+        &self,
+        tx_id: &str,
+        address: &str,
+        message: &str,
+        signature: &str,
+    ) -> Result<TxKeyCheck> {
+        #[derive(Deserialize)]
+        struct Rsp {
+            good: bool,
+            #[serde(flatten)]
+            check: TxKeyCheck,
+        }
+
+        debug!(tx_id, "Checking transaction proof");
+        let txid = Hash::from_str(tx_id).map_err(|err| anyhow!("invalid tx id: {err}"))?;
+        let address =
+            Address::from_str(address).map_err(|err| anyhow!("invalid address: {err}"))?;
+        let rsp: Rsp = self
+            .rpc
+            .call_retried(
+                "check_tx_proof",
+                json!({
+                    "txid": hex::encode(txid.as_bytes()),
+                    "address": address.to_string(),
+                    "message": message,
+                    "signature": signature,
+                }),
+            )
+            .await?;
+        if !rsp.good {
+            return Err(anyhow!("tx proof for {tx_id} does not verify for {address}").into());
+        }
+        Ok(rsp.check)
+    }
 }
@@ -0,0 +1,188 @@
+//! Native view-key scanning against monerod, without wallet-rpc.
+//!
+//! Transactions are fetched from the daemon and scanned locally with the
+//! `monero` crate: output derivations computed from the view key find the
+//! outputs paying the address, and their RingCT amounts are decrypted from
+//! the ECDH info. Only the private view key is needed, so no wallet process
+//! has to hold keys.
+
+use std::ops::Range;
+
+use anyhow::anyhow;
+use monero::consensus::encode::deserialize;
+use monero::cryptonote::hash::Hashable;
+use monero::util::key::ViewPair;
+use monero::{Address, PublicKey, Transaction};
+use tracing::debug;
+
+use crate::crypto::{to_monero_private_key, SecretKey};
+use crate::rpc::{DaemonTransaction, TxKeyCheck, XmrDaemon};
+use crate::types::Result;
+use crate::watcher::LockBackend;
+
+/// One output found by [`ViewKeyScanner`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScannedOutput {
+    pub tx_hash: String,
+    /// Position of the output in its transaction.
+    pub index: usize,
+    /// Decrypted amount, in piconero.
+    pub amount: u64,
+    /// `None` while the transaction is in the pool.
+    pub block_height: Option<u64>,
+}
+
+/// Finds outputs paying one address using its private view key.
+pub struct ViewKeyScanner {
+    pair: ViewPair,
+    major: Range<u32>,
+    minor: Range<u32>,
+}
+
+impl ViewKeyScanner {
+    /// Scans for outputs to `address`. The view key must be the one whose
+    /// public key is in the address.
+    pub fn new(address: &Address, view_key: &SecretKey) -> Result<Self> {
+        let view = to_monero_private_key(view_key)?;
+        if PublicKey::from_private_key(&view) != address.public_view {
+            return Err(anyhow!("view key does not belong to {address}").into());
+        }
+        Ok(Self {
+            pair: ViewPair {
+                view,
+                spend: address.public_spend,
+            },
+            major: 0..1,
+            minor: 0..1,
+        })
+    }
+
+    /// Also scans the address's subaddresses in these index ranges. Only
+    /// the main address (0/0) is scanned by default.
+    pub fn with_subaddresses(mut self, major: Range<u32>, minor: Range<u32>) -> Self {
+        self.major = major;
+        self.minor = minor;
+        self
+    }
+
+    fn pays(&self, address: &Address) -> bool {
+        address.public_spend == self.pair.spend
+            && address.public_view == PublicKey::from_private_key(&self.pair.view)
+    }
+
+    /// Outputs of `tx` paying the scanned address, as (index, amount).
+    pub fn scan_transaction(&self, tx: &Transaction) -> Result<Vec<(usize, u64)>> {
+        let owned = tx
+            .check_outputs(&self.pair, self.major.clone(), self.minor.clone())
+            .map_err(|err| anyhow!("scan transaction outputs: {err}"))?;
+        owned
+            .iter()
+            .map(|output| {
+                let amount = output
+                    .amount()
+                    .ok_or_else(|| anyhow!("cannot decrypt amount of output {}", output.index()))?;
+                Ok((output.index(), amount.as_pico()))
+            })
+            .collect()
+    }
+
+    fn scan_daemon_transaction(&self, tx: &DaemonTransaction) -> Result<Vec<ScannedOutput>> {
+        let decoded = decode_transaction(tx)?;
+        Ok(self
+            .scan_transaction(&decoded)?
+            .into_iter()
+            .map(|(index, amount)| ScannedOutput {
+                tx_hash: tx.tx_hash.clone(),
+                index,
+                amount,
+                block_height: tx.block_height.filter(|_| !tx.in_pool),
+            })
+            .collect())
+    }
+
+    /// Outputs to the scanned address in the block at `height`. Coinbase
+    /// outputs are not scanned.
+    pub async fn scan_block(&self, daemon: &XmrDaemon, height: u64) -> Result<Vec<ScannedOutput>> {
+        let block = daemon.get_block(height).await?;
+        if block.tx_hashes.is_empty() {
+            return Ok(Vec::new());
+        }
+        let txs = daemon.get_transactions(&block.tx_hashes).await?;
+        if txs.len() != block.tx_hashes.len() {
+            return Err(anyhow!(
+                "daemon returned {} of {} transactions in block {height}",
+                txs.len(),
+                block.tx_hashes.len()
+            )
+            .into());
+        }
+        let mut found = Vec::new();
+        for tx in &txs {
+            found.extend(self.scan_daemon_transaction(tx)?.into_iter().map(|output| {
+                ScannedOutput {
+                    block_height: Some(height),
+                    ..output
+                }
+            }));
+        }
+        debug!(height, outputs = found.len(), "Scanned Monero block");
+        Ok(found)
+    }
+
+    /// What transaction `tx_id` paid the scanned address, with its
+    /// confirmations at `current_height` (the daemon's block count).
+    pub async fn check_transaction(
+        &self,
+        daemon: &XmrDaemon,
+        tx_id: &str,
+        current_height: u64,
+    ) -> Result<TxKeyCheck> {
+        let txs = daemon.get_transactions(&[tx_id.to_string()]).await?;
+        let tx = txs
+            .iter()
+            .find(|tx| tx.tx_hash == tx_id)
+            .ok_or_else(|| anyhow!("daemon does not know transaction {tx_id}"))?;
+        let outputs = self.scan_daemon_transaction(tx)?;
+        let confirmations = match tx.block_height.filter(|_| !tx.in_pool) {
+            Some(height) => current_height.saturating_sub(height),
+            None => 0,
+        };
+        Ok(TxKeyCheck {
+            received: outputs.iter().map(|output| output.amount).sum(),
+            confirmations,
+            in_pool: tx.in_pool,
+        })
+    }
+}
+
+/// Deserializes the daemon's hex blob and checks it hashes to the id the
+/// daemon reported.
+fn decode_transaction(tx: &DaemonTransaction) -> Result<Transaction> {
+    let bytes = hex::decode(&tx.as_hex)
+        .map_err(|err| anyhow!("invalid transaction hex for {}: {err}", tx.tx_hash))?;
+    let decoded: Transaction = deserialize(&bytes)
+        .map_err(|err| anyhow!("cannot parse transaction {}: {err}", tx.tx_hash))?;
+    let hash = hex::encode(decoded.hash().as_bytes());
+    if hash != tx.tx_hash {
+        return Err(anyhow!("transaction {} hashes to {hash}", tx.tx_hash).into());
+    }
+    Ok(decoded)
+}
+
+/// The view-key scanner stands in for wallet-rpc's `check_tx_key`; the tx
+/// key is not needed.
+impl LockBackend for ViewKeyScanner {
+    async fn check_lock(
+        &self,
+        daemon: &XmrDaemon,
+        tx_id: &str,
+        _tx_key: Option<&str>,
+        joint_address: &Address,
+        current_height: u64,
+    ) -> Result<TxKeyCheck> {
+        if !self.pays(joint_address) {
+            return Err(anyhow!("scanner is not keyed for {joint_address}").into());
+        }
+        self.check_transaction(daemon, tx_id, current_height).await
+    }
+}
@@ -0,0 +1,63 @@
+//! JSON files written next to the wallet: lock receipts, claim journals and
+//! cold-signing exports. They carry tx keys and signed transactions, so
+//! they are created readable by their owner only.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::Context;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::types::Result;
+
+/// Writes `value` to `path`, refusing to replace an existing file. `label`
+/// names the file in errors.
+pub(crate) fn write_new<T: Serialize>(path: &Path, value: &T, label: &str) -> Result<()> {
+    let json = serde_json::to_string_pretty(value).map_err(anyhow::Error::from)?;
+    let mut file = private(OpenOptions::new().write(true).create_new(true))
+        .open(path)
+        .with_context(|| format!("create {label} {}", path.display()))?;
+    sync_json(&mut file, &json).with_context(|| format!("write {label} {}", path.display()))?;
+    Ok(())
+}
+
+/// Replaces the file at `path` with `value` atomically, through a
+/// temporary file beside it.
+pub(crate) fn replace<T: Serialize>(path: &Path, value: &T, label: &str) -> Result<()> {
+    let json = serde_json::to_string_pretty(value).map_err(anyhow::Error::from)?;
+    let tmp = path.with_extension("json.tmp");
+    // A leftover temporary file would keep its own permissions.
+    let _ = fs::remove_file(&tmp);
+    let mut file = private(OpenOptions::new().write(true).create(true).truncate(true))
+        .open(&tmp)
+        .with_context(|| format!("create {label} {}", tmp.display()))?;
+    sync_json(&mut file, &json).with_context(|| format!("write {label} {}", tmp.display()))?;
+    fs::rename(&tmp, path).with_context(|| format!("replace {label} {}", path.display()))?;
+    Ok(())
+}
+
+pub(crate) fn read<T: DeserializeOwned>(path: &Path, label: &str) -> Result<T> {
+    let json =
+        fs::read_to_string(path).with_context(|| format!("read {label} {}", path.display()))?;
+    let value =
+        serde_json::from_str(&json).with_context(|| format!("parse {label} {}", path.display()))?;
+    Ok(value)
+}
+
+fn sync_json(file: &mut File, json: &str) -> std::io::Result<()> {
+    file.write_all(json.as_bytes())?;
+    file.sync_all()
+}
+
+#[cfg(unix)]
+fn private(options: &mut OpenOptions) -> &mut OpenOptions {
+    use std::os::unix::fs::OpenOptionsExt;
+    options.mode(0o600)
+}
+
+#[cfg(not(unix))]
+fn private(options: &mut OpenOptions) -> &mut OpenOptions {
+    options
+}
@@ -1,4 +1,3 @@
-use jsonrpc_core::types::error::Error as RpcError;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -7,8 +6,18 @@ pub enum CryptoError {
     InvalidPoint,
     #[error("small-order point")]
     SmallOrderPoint,
+    #[error("non-canonical point encoding")]
+    NonCanonicalPoint,
+    #[error("point not in prime-order subgroup")]
+    TorsionedPoint,
     #[error("invalid scalar encoding")]
     InvalidScalar,
+    #[error("invalid adaptor signature")]
+    InvalidSignature,
+    #[error("invalid proof of knowledge")]
+    InvalidProof,
+    #[error("duplicate key share")]
+    DuplicateKeyShare,
 }
 
 #[derive(Debug, Error)]
@@ -16,13 +25,29 @@ pub enum XmrWalletError {
     #[error(transparent)]
     Anyhow(#[from] anyhow::Error),
     #[error(transparent)]
-    Rpc(#[from] RpcError),
-    #[error(transparent)]
     Address(#[from] monero::util::address::Error),
     #[error(transparent)]
     Crypto(#[from] CryptoError),
     #[error("unsupported operation: {0}")]
     Unsupported(&'static str),
+    /// An error object returned by a JSON-RPC method.
+    #[error("{method} failed ({code}): {message}")]
+    Rpc {
+        method: &'static str,
+        code: i64,
+        message: String,
+    },
+}
+
+/// wallet-rpc's `WALLET_RPC_ERROR_CODE_WALLET_ALREADY_EXISTS`.
+const WALLET_ALREADY_EXISTS: i64 = -21;
+
+impl XmrWalletError {
+    /// Whether wallet-rpc refused to create a wallet file because one with
+    /// that name already exists.
+    pub fn is_wallet_exists(&self) -> bool {
+        matches!(self, XmrWalletError::Rpc { code, .. } if *code == WALLET_ALREADY_EXISTS)
+    }
 }
 
 impl From<monero::util::key::Error> for CryptoError {
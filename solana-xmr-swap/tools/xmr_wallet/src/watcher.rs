@@ -1,7 +1,13 @@
-use monero_rpc::TransferHeight;
+use std::future::Future;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use monero::Address;
+use serde::{Deserialize, Serialize};
 use tracing::{debug, warn};
 
-use crate::rpc::XmrWallet;
+use crate::crypto::SecretKey;
+use crate::rpc::{TxKeyCheck, XmrDaemon, XmrWallet};
 use crate::types::Result;
 
 #[derive(Debug, Clone, Copy)]
@@ -19,18 +25,75 @@ impl Default for WatcherConfig {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Everything [`LockWatcher`] has learned so far. Persist it through a
+/// [`WatcherStore`] and hand it to [`LockWatcher::resume`] after a restart.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WatcherState {
     last_seen_height: Option<u64>,
     lock_height: Option<u64>,
+    lock_amount: Option<u64>,
+    /// Unix time the lock transaction was first seen, in the pool or mined.
+    /// Kept across reorgs; it bounds the claim wallet's restore height.
+    #[serde(default)]
+    first_seen_at: Option<u64>,
+    /// Hashes of the lock's block and its successors, lowest first.
+    #[serde(default, with = "block_hashes_hex")]
+    block_hashes: Vec<(u64, [u8; 32])>,
 }
 
 impl WatcherState {
     pub fn new() -> Self {
-        Self {
-            last_seen_height: None,
-            lock_height: None,
-        }
+        Self::default()
+    }
+
+    pub fn last_seen_height(&self) -> Option<u64> {
+        self.last_seen_height
+    }
+
+    pub fn lock_height(&self) -> Option<u64> {
+        self.lock_height
+    }
+
+    pub fn lock_amount(&self) -> Option<u64> {
+        self.lock_amount
+    }
+
+    pub fn first_seen_at(&self) -> Option<u64> {
+        self.first_seen_at
+    }
+}
+
+/// Where watcher state survives restarts, keyed by swap id.
+pub trait WatcherStore {
+    fn save_watcher_state(&self, swap_id: &str, state: &WatcherState) -> Result<()>;
+    fn load_watcher_state(&self, swap_id: &str) -> Result<Option<WatcherState>>;
+}
+
+mod block_hashes_hex {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        hashes: &[(u64, [u8; 32])],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        hashes
+            .iter()
+            .map(|(height, hash)| (*height, hex::encode(hash)))
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(u64, [u8; 32])>, D::Error> {
+        Vec::<(u64, String)>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(height, hash)| {
+                let mut bytes = [0u8; 32];
+                hex::decode_to_slice(&hash, &mut bytes).map_err(serde::de::Error::custom)?;
+                Ok((height, bytes))
+            })
+            .collect()
     }
 }
 
@@ -38,21 +101,142 @@ impl WatcherState {
 pub enum WatcherEvent {
     NoLockObserved,
     LockInPool { amount: u64 },
+    /// The daemon is still catching up; its heights cannot be trusted yet.
+    DaemonSyncing {
+        height: u64,
+        target_height: u64,
+    },
+    /// The transaction pays the lock address less than expected.
+    Underpaid {
+        expected: u64,
+        received: u64,
+    },
     AwaitingConfirmations {
         observed_height: u64,
         current_height: u64,
         remaining: u64,
+        amount: u64,
     },
     Confirmed {
         observed_height: u64,
         confirmations: u64,
+        amount: u64,
     },
+    /// The chain shrank past `reorg_buffer`, or the block holding the lock
+    /// was replaced. The lock has to be observed again.
     ReorgDetected {
         previous_height: u64,
         current_height: u64,
     },
 }
 
+/// Block hashes by height. [`XmrDaemon`] is the production source; tests
+/// drive the watcher with a scripted chain.
+pub trait ChainView {
+    fn block_hash(&self, height: u64) -> impl Future<Output = Result<[u8; 32]>> + Send;
+}
+
+impl ChainView for XmrDaemon {
+    async fn block_hash(&self, height: u64) -> Result<[u8; 32]> {
+        let header = self.get_block_header_by_height(height).await?;
+        let bytes = hex::decode(&header.hash)
+            .map_err(|err| anyhow!("invalid block hash at {height}: {err}"))?;
+        let hash: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| anyhow!("block hash at {height} is not 32 bytes"))?;
+        Ok(hash)
+    }
+}
+
+/// Checks what lock transaction `tx_id` paid the joint address. wallet-rpc's
+/// `check_tx_key` is the default and needs the sender's `tx_key`;
+/// [`TxProofLock`] checks a tx proof instead, and [`crate::ViewKeyScanner`]
+/// and [`crate::MultisigLock`] need neither.
+pub trait LockBackend {
+    fn check_lock(
+        &self,
+        daemon: &XmrDaemon,
+        tx_id: &str,
+        tx_key: Option<&str>,
+        joint_address: &Address,
+        current_height: u64,
+    ) -> impl Future<Output = Result<TxKeyCheck>> + Send;
+}
+
+impl LockBackend for XmrWallet {
+    async fn check_lock(
+        &self,
+        _daemon: &XmrDaemon,
+        tx_id: &str,
+        tx_key: Option<&str>,
+        joint_address: &Address,
+        _current_height: u64,
+    ) -> Result<TxKeyCheck> {
+        let tx_key = tx_key.ok_or_else(|| anyhow!("check_tx_key needs the lock's tx key"))?;
+        self.check_tx_key(tx_id, tx_key, &joint_address.to_string())
+            .await
+    }
+}
+
+/// Checks the lock with a proof the sender made with wallet-rpc's
+/// `get_tx_proof`, for a sender who hands over a proof rather than the tx
+/// key itself.
+pub struct TxProofLock<'a> {
+    wallet: &'a XmrWallet,
+    signature: String,
+    message: String,
+}
+
+impl<'a> TxProofLock<'a> {
+    /// `message` is the one the proof was signed over; empty if none.
+    pub fn new(wallet: &'a XmrWallet, signature: &str, message: &str) -> Self {
+        Self {
+            wallet,
+            signature: signature.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl LockBackend for TxProofLock<'_> {
+    async fn check_lock(
+        &self,
+        _daemon: &XmrDaemon,
+        tx_id: &str,
+        _tx_key: Option<&str>,
+        joint_address: &Address,
+        _current_height: u64,
+    ) -> Result<TxKeyCheck> {
+        self.wallet
+            .check_tx_proof(
+                tx_id,
+                &joint_address.to_string(),
+                &self.message,
+                &self.signature,
+            )
+            .await
+    }
+}
+
+/// Opens a view-only wallet on the joint lock address so its incoming funds
+/// can be watched before any spend key is known.
+pub async fn open_view_only_wallet(
+    wallet: &XmrWallet,
+    lock_address: &Address,
+    view_key: &SecretKey,
+    filename: &str,
+    restore_height: Option<u64>,
+) -> Result<()> {
+    wallet
+        .generate_view_only(
+            view_key.to_bytes(),
+            &lock_address.to_string(),
+            filename,
+            restore_height,
+        )
+        .await
+}
+
 pub struct LockWatcher {
     config: WatcherConfig,
     state: WatcherState,
@@ -60,18 +244,35 @@ pub struct LockWatcher {
 
 impl LockWatcher {
     pub fn new(config: WatcherConfig) -> Self {
-        Self {
-            config,
-            state: WatcherState::new(),
-        }
+        Self::resume(config, WatcherState::new())
     }
 
-    pub fn observe_lock(&mut self, height: u64) {
-        let should_update = self.state.lock_height.map_or(true, |current| height < current);
+    /// Continues from a previously saved state, keeping confirmation
+    /// progress and the recorded block hashes.
+    pub fn resume(config: WatcherConfig, state: WatcherState) -> Self {
+        Self { config, state }
+    }
+
+    /// Resumes the state stored for `swap_id`, or starts fresh.
+    pub fn load<S: WatcherStore>(config: WatcherConfig, store: &S, swap_id: &str) -> Result<Self> {
+        let state = store.load_watcher_state(swap_id)?.unwrap_or_default();
+        Ok(Self::resume(config, state))
+    }
+
+    pub fn save<S: WatcherStore>(&self, store: &S, swap_id: &str) -> Result<()> {
+        store.save_watcher_state(swap_id, &self.state)
+    }
+
+    pub fn observe_lock(&mut self, height: u64, amount: u64) {
+        let should_update = self
+            .state
+            .lock_height
+            .is_none_or(|current| height < current);
         if should_update {
-            debug!(lock_height = height, "Observed lock height");
+            debug!(lock_height = height, amount, "Observed lock height");
             self.state.lock_height = Some(height);
         }
+        self.state.lock_amount = Some(amount);
     }
 
     pub fn update_height(&mut self, current_height: u64) -> Option<WatcherEvent> {
@@ -83,7 +284,7 @@ impl LockWatcher {
                     "Reorg detected; clearing observed lock height"
                 );
                 self.state.last_seen_height = Some(current_height);
-                self.state.lock_height = None;
+                self.clear_lock();
                 return Some(WatcherEvent::ReorgDetected {
                     previous_height: previous,
                     current_height,
@@ -95,22 +296,86 @@ impl LockWatcher {
         None
     }
 
+    /// Compares the stored hashes of the lock's block and its successors
+    /// with `chain`, up to the tip below `current_height`. A changed hash at
+    /// the lock height means the lock's block was orphaned, even if the chain
+    /// did not get shorter.
+    pub async fn check_block_hashes<C: ChainView>(
+        &mut self,
+        chain: &C,
+        current_height: u64,
+    ) -> Result<Option<WatcherEvent>> {
+        let Some(lock_height) = self.state.lock_height else {
+            self.state.block_hashes.clear();
+            return Ok(None);
+        };
+        let window = self.config.confirmations_required + self.config.reorg_buffer;
+        let last = current_height
+            .saturating_sub(1)
+            .min(lock_height.saturating_add(window));
+
+        let mut hashes = Vec::new();
+        for height in lock_height..=last {
+            let hash = chain.block_hash(height).await?;
+            let known = self
+                .state
+                .block_hashes
+                .iter()
+                .find(|(known_height, _)| *known_height == height);
+            if let Some((_, known_hash)) = known {
+                if *known_hash != hash {
+                    if height == lock_height {
+                        warn!(lock_height, "Lock block orphaned; clearing observed lock");
+                        let previous_height = self.state.last_seen_height.unwrap_or(current_height);
+                        self.clear_lock();
+                        return Ok(Some(WatcherEvent::ReorgDetected {
+                            previous_height,
+                            current_height,
+                        }));
+                    }
+                    debug!(height, "Block above lock replaced");
+                }
+            }
+            hashes.push((height, hash));
+        }
+        self.state.block_hashes = hashes;
+        Ok(None)
+    }
+
+    fn mark_first_seen(&mut self) {
+        if self.state.first_seen_at.is_none() {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs());
+            self.state.first_seen_at = Some(now);
+        }
+    }
+
+    fn clear_lock(&mut self) {
+        self.state.lock_height = None;
+        self.state.lock_amount = None;
+        self.state.block_hashes.clear();
+    }
+
     pub fn evaluate(&self, current_height: u64) -> WatcherEvent {
         match self.state.lock_height {
             None => WatcherEvent::NoLockObserved,
             Some(observed_height) => {
                 let required = self.config.confirmations_required;
                 let confirmations = current_height.saturating_sub(observed_height);
+                let amount = self.state.lock_amount.unwrap_or_default();
                 if confirmations >= required {
                     WatcherEvent::Confirmed {
                         observed_height,
                         confirmations,
+                        amount,
                     }
                 } else {
                     WatcherEvent::AwaitingConfirmations {
                         observed_height,
                         current_height,
                         remaining: required - confirmations,
+                        amount,
                     }
                 }
             }
@@ -118,39 +383,95 @@ impl LockWatcher {
     }
 
     pub fn state(&self) -> WatcherState {
-        self.state
+        self.state.clone()
     }
 
-    pub async fn poll_for_lock(
+    /// Turns a tx-key check of the lock transaction into an event. Only the
+    /// amount the transaction pays the joint address counts, so unrelated
+    /// deposits cannot stand in for the lock.
+    pub fn apply_tx_key_check(
         &mut self,
-        wallet: &XmrWallet,
+        check: TxKeyCheck,
+        current_height: u64,
+        expected_amount: u64,
+    ) -> WatcherEvent {
+        if check.received < expected_amount {
+            warn!(
+                expected = expected_amount,
+                received = check.received,
+                "Lock transaction underpays the joint address"
+            );
+            return WatcherEvent::Underpaid {
+                expected: expected_amount,
+                received: check.received,
+            };
+        }
+        if check.in_pool || check.confirmations > 0 {
+            self.mark_first_seen();
+        }
+        if check.in_pool {
+            debug!(amount = check.received, "Lock transaction in pool");
+            return WatcherEvent::LockInPool {
+                amount: check.received,
+            };
+        }
+        if check.confirmations == 0 {
+            return WatcherEvent::NoLockObserved;
+        }
+        self.observe_lock(
+            current_height.saturating_sub(check.confirmations),
+            check.received,
+        );
+        self.evaluate(current_height)
+    }
+
+    /// Verifies the counterparty's lock transaction `tx_id` against
+    /// `joint_address`, handing `tx_key` to backends that need it. Pass the
+    /// locally derived joint address, never one the counterparty sent.
+    /// Heights come from `daemon`, not from the wallet's sync height.
+    pub async fn poll_for_lock<B: LockBackend>(
+        &mut self,
+        backend: &B,
+        daemon: &XmrDaemon,
+        tx_id: &str,
+        tx_key: Option<&str>,
+        joint_address: &Address,
         expected_amount: u64,
     ) -> Result<Option<WatcherEvent>> {
-        let current_height = wallet.get_height().await?;
+        let info = daemon.get_info().await?;
+        if info.sync_lag() > 0 {
+            debug!(
+                height = info.height,
+                target_height = info.target_height,
+                "Daemon syncing"
+            );
+            return Ok(Some(WatcherEvent::DaemonSyncing {
+                height: info.height,
+                target_height: info.target_height,
+            }));
+        }
+        let current_height = info.height;
         if let Some(event) = self.update_height(current_height) {
             return Ok(Some(event));
         }
+        if let Some(event) = self.check_block_hashes(daemon, current_height).await? {
+            return Ok(Some(event));
+        }
 
-        wallet.refresh(self.state.last_seen_height).await?;
-        let transfers = wallet.get_incoming_transfers().await?;
-        for transfer in transfers {
-            let amount = transfer.amount.as_pico();
-            if amount < expected_amount {
-                continue;
-            }
-            match transfer.height {
-                TransferHeight::InPool => {
-                    debug!(amount, "Observed lock transfer in pool");
-                    return Ok(Some(WatcherEvent::LockInPool { amount }));
-                }
-                TransferHeight::Confirmed(height) => {
-                    let observed_height = height.get();
-                    self.observe_lock(observed_height);
-                    return Ok(Some(self.evaluate(current_height)));
-                }
-            }
+        let known = daemon.get_transactions(&[tx_id.to_string()]).await?;
+        if known.is_empty() {
+            debug!(tx_id, "Lock transaction unknown to daemon");
+            return Ok(Some(WatcherEvent::NoLockObserved));
         }
 
-        Ok(None)
+        let check = backend
+            .check_lock(daemon, tx_id, tx_key, joint_address, current_height)
+            .await?;
+        let event = self.apply_tx_key_check(check, current_height, expected_amount);
+        // Record the lock block's hash as soon as it is first observed.
+        if let Some(reorg) = self.check_block_hashes(daemon, current_height).await? {
+            return Ok(Some(reorg));
+        }
+        Ok(Some(event))
     }
 }
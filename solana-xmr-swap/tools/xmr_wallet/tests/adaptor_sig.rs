@@ -0,0 +1,163 @@
+use curve25519_dalek::constants::{ED25519_BASEPOINT_POINT, EIGHT_TORSION};
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::Deserialize;
+use xmr_wallet::{
+    decrypt_signature, encrypted_sign, encrypted_sign_with_aux, recover_adaptor_secret,
+    verify_encrypted_signature, CryptoError, EncryptedSignature, PublicKey, SecretKey,
+};
+
+#[derive(Deserialize)]
+struct VectorFile {
+    vectors: Vec<AdaptorVector>,
+}
+
+#[derive(Deserialize)]
+struct AdaptorVector {
+    signing_key: String,
+    public_key: String,
+    adaptor_secret: String,
+    adaptor_point: String,
+    message: String,
+    aux: String,
+    encrypted_signature: String,
+    signature: String,
+}
+
+fn decode<const N: usize>(value: &str) -> [u8; N] {
+    hex::decode(value).expect("hex").try_into().expect("length")
+}
+
+fn public_of(secret: &SecretKey) -> PublicKey {
+    PublicKey::from_compressed(
+        (ED25519_BASEPOINT_POINT * secret.as_scalar())
+            .compress()
+            .to_bytes(),
+    )
+    .expect("public key")
+}
+
+#[test]
+fn matches_test_vectors() {
+    let manifest = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+    let path = manifest.join("../../test_vectors/ed25519_adaptor.json");
+    let raw = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("read vectors at {}", path.display()));
+    let file: VectorFile = serde_json::from_str(&raw).expect("parse vectors");
+    assert!(!file.vectors.is_empty());
+
+    for vector in file.vectors {
+        let signing_key = SecretKey::from_bytes(decode(&vector.signing_key));
+        let adaptor_secret = SecretKey::from_bytes(decode(&vector.adaptor_secret));
+        let public_key = PublicKey::from_compressed(decode(&vector.public_key)).unwrap();
+        let adaptor_point = PublicKey::from_compressed(decode(&vector.adaptor_point)).unwrap();
+        let message = hex::decode(&vector.message).unwrap();
+        assert_eq!(
+            public_of(&signing_key).to_compressed(),
+            public_key.to_compressed()
+        );
+        assert_eq!(
+            public_of(&adaptor_secret).to_compressed(),
+            adaptor_point.to_compressed()
+        );
+
+        let encrypted =
+            encrypted_sign_with_aux(&signing_key, &adaptor_point, &message, &decode(&vector.aux));
+        assert_eq!(encrypted.to_bytes(), decode(&vector.encrypted_signature));
+        verify_encrypted_signature(&public_key, &adaptor_point, &message, &encrypted)
+            .expect("encrypted signature verifies");
+
+        let signature = decrypt_signature(&adaptor_secret, &encrypted);
+        assert_eq!(signature.to_bytes(), decode(&vector.signature));
+        VerifyingKey::from_bytes(&public_key.to_compressed())
+            .unwrap()
+            .verify_strict(&message, &signature)
+            .expect("decrypted signature is a standard Ed25519 signature");
+
+        let recovered = recover_adaptor_secret(&adaptor_point, &encrypted, &signature).unwrap();
+        assert_eq!(recovered.to_bytes(), adaptor_secret.to_bytes());
+    }
+}
+
+#[test]
+fn round_trips_with_random_keys() {
+    let signing_key = SecretKey::from_scalar(SecretKey::random().as_scalar());
+    let adaptor_secret = SecretKey::from_scalar(SecretKey::random().as_scalar());
+    let adaptor_point = public_of(&adaptor_secret);
+    let message = b"claim swap 42";
+
+    let encrypted = encrypted_sign(&signing_key, &adaptor_point, message);
+    let parsed = EncryptedSignature::from_bytes(&encrypted.to_bytes()).unwrap();
+    assert_eq!(parsed, encrypted);
+    verify_encrypted_signature(&public_of(&signing_key), &adaptor_point, message, &parsed).unwrap();
+
+    let signature = decrypt_signature(&adaptor_secret, &parsed);
+    let recovered = recover_adaptor_secret(&adaptor_point, &parsed, &signature).unwrap();
+    assert_eq!(recovered.to_bytes(), adaptor_secret.to_bytes());
+}
+
+#[test]
+fn rejects_wrong_message_or_adaptor_point() {
+    let signing_key = SecretKey::from_scalar(SecretKey::random().as_scalar());
+    let adaptor_point = public_of(&SecretKey::from_scalar(SecretKey::random().as_scalar()));
+    let other_point = public_of(&SecretKey::from_scalar(SecretKey::random().as_scalar()));
+    let public_key = public_of(&signing_key);
+    let encrypted = encrypted_sign(&signing_key, &adaptor_point, b"message");
+
+    assert!(matches!(
+        verify_encrypted_signature(&public_key, &adaptor_point, b"other", &encrypted),
+        Err(CryptoError::InvalidSignature)
+    ));
+    assert!(matches!(
+        verify_encrypted_signature(&public_key, &other_point, b"message", &encrypted),
+        Err(CryptoError::InvalidSignature)
+    ));
+}
+
+#[test]
+fn recovery_rejects_unrelated_signature() {
+    let signing_key = SecretKey::from_scalar(SecretKey::random().as_scalar());
+    let adaptor_secret = SecretKey::from_scalar(SecretKey::random().as_scalar());
+    let adaptor_point = public_of(&adaptor_secret);
+    let encrypted = encrypted_sign(&signing_key, &adaptor_point, b"message");
+    let other = encrypted_sign(&signing_key, &adaptor_point, b"message");
+    let signature = decrypt_signature(&adaptor_secret, &other);
+
+    assert!(matches!(
+        recover_adaptor_secret(&adaptor_point, &encrypted, &signature),
+        Err(CryptoError::InvalidSignature)
+    ));
+
+    let mut forged = signature.to_bytes();
+    forged[32..].copy_from_slice(&[0xff; 32]);
+    let forged = Signature::from_bytes(&forged);
+    assert!(recover_adaptor_secret(&adaptor_point, &other, &forged).is_err());
+}
+
+#[test]
+fn parsing_rejects_non_canonical_or_torsioned_nonce() {
+    let signing_key = SecretKey::from_scalar(SecretKey::random().as_scalar());
+    let adaptor_point = public_of(&SecretKey::from_scalar(SecretKey::random().as_scalar()));
+    let bytes = encrypted_sign(&signing_key, &adaptor_point, b"message").to_bytes();
+    let r_pre = CompressedEdwardsY(bytes[..32].try_into().unwrap())
+        .decompress()
+        .unwrap();
+
+    let mut torsioned = bytes;
+    torsioned[..32].copy_from_slice((r_pre + EIGHT_TORSION[1]).compress().as_bytes());
+    assert!(matches!(
+        EncryptedSignature::from_bytes(&torsioned),
+        Err(CryptoError::TorsionedPoint)
+    ));
+
+    // y = 1 with the sign bit set decompresses to the identity but does not
+    // round-trip.
+    let mut non_canonical = bytes;
+    non_canonical[..32].copy_from_slice(&[0; 32]);
+    non_canonical[0] = 1;
+    non_canonical[31] = 0x80;
+    assert!(matches!(
+        EncryptedSignature::from_bytes(&non_canonical),
+        Err(CryptoError::NonCanonicalPoint)
+    ));
+}
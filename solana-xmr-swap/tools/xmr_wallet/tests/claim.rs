@@ -1,13 +1,20 @@
+mod common;
+
+use common::{fast_retry, json_rpc, method, serve_scripted};
 use curve25519_dalek::scalar::Scalar;
 use monero::Network;
+use serde_json::{json, Value};
 use sha2::{Digest, Sha256};
 use std::env;
-use std::path::PathBuf;
-use zeroize::Zeroizing;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use xmr_wallet::{
-    derive_claim_keys, prepare_claim_guard, validate_destination_address, validate_hashlock,
-    ClaimParams,
+    claim_wallet_password, derive_claim_keys, execute_claim_with_progress,
+    validate_destination_address, validate_hashlock, wait_for_unlocked_balance, BuiltTx,
+    ClaimJournal, ClaimParams, ClaimProgress, ClaimStep, PendingSweep, TransferOptions, UnlockWait,
+    WalletCleanup, XmrWallet,
 };
+use zeroize::Zeroizing;
 
 #[test]
 fn derives_claim_keys_for_network() {
@@ -15,12 +22,17 @@ fn derives_claim_keys_for_network() {
         alice_partial: Scalar::from(2u64),
         bob_partial: Scalar::from(3u64),
         revealed_secret: Zeroizing::new([5u8; 32]),
+        view_key: None,
         destination_address: "stagenet_placeholder".to_string(),
         network: Network::Stagenet,
         wallet_filename: "claim_wallet".to_string(),
         restore_height: Some(0),
         expected_hashlock: None,
-        claim_guard_path: None,
+        unlock: UnlockWait::default(),
+        journal_path: None,
+        transfer: TransferOptions::default(),
+        wallet_password: None,
+        cleanup: WalletCleanup::KeepEncrypted,
     };
 
     let keys = derive_claim_keys(&params).expect("derive claim keys");
@@ -42,12 +54,576 @@ fn rejects_wrong_secret_for_hashlock() {
 }
 
 #[test]
-fn prevents_replay_with_guard_file() {
-    let mut path: PathBuf = env::temp_dir();
-    path.push(format!("xmr_claim_guard_{}.lock", std::process::id()));
-    let guard = prepare_claim_guard(&path).expect("create guard");
-    guard.commit();
-    let second = prepare_claim_guard(&path);
-    assert!(second.is_err());
+fn claim_journal_round_trips_and_only_moves_forward() {
+    let path = env::temp_dir().join(format!("xmr_claim_journal_{}.json", std::process::id()));
+    let mut journal = ClaimJournal::new("destination", "claim_wallet");
+    journal.record_keys_imported();
+    journal.record_synced(1200);
+    journal.record_relayed("ab");
+    journal.record_synced(1201);
+    assert_eq!(journal.step, ClaimStep::TxRelayed);
+    assert!(journal.is_done(ClaimStep::TxBuilt));
+    assert!(!journal.is_done(ClaimStep::Confirmed));
+
+    journal.save(&path).expect("save journal");
+    journal.save(&path).expect("replace journal");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).expect("stat").permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+    let loaded = ClaimJournal::load(&path)
+        .expect("load journal")
+        .expect("journal exists");
+    assert_eq!(loaded, journal);
+    assert!(loaded
+        .ensure_matches("destination", "other_wallet")
+        .is_err());
+    let _ = std::fs::remove_file(&path);
+}
+
+fn balance(balance: u64, unlocked_balance: u64) -> Value {
+    json_rpc(json!({
+        "balance": balance,
+        "unlocked_balance": unlocked_balance,
+        "multisig_import_needed": false,
+        "per_subaddress": [],
+    }))
+}
+
+fn quick_wait(expected_amount: u64, timeout: Duration) -> UnlockWait {
+    UnlockWait {
+        expected_amount,
+        poll_interval: Duration::from_millis(10),
+        timeout,
+    }
+}
+
+#[tokio::test]
+async fn waits_until_unlocked_balance_covers_amount() {
+    let url = serve_scripted(vec![
+        (
+            method("get_height"),
+            vec![
+                json_rpc(json!({ "height": 100 })),
+                json_rpc(json!({ "height": 109 })),
+                json_rpc(json!({ "height": 110 })),
+            ],
+        ),
+        (
+            method("get_balance"),
+            vec![balance(500, 0), balance(500, 0), balance(500, 500)],
+        ),
+        (
+            method("refresh"),
+            vec![json_rpc(
+                json!({ "blocks_fetched": 1, "received_money": false }),
+            )],
+        ),
+    ])
+    .await;
+    let wallet = XmrWallet::connect_with_config(&url, fast_retry())
+        .await
+        .expect("connect");
+
+    let mut events = Vec::new();
+    let unlocked =
+        wait_for_unlocked_balance(&wallet, &quick_wait(500, Duration::from_secs(5)), |event| {
+            events.push(event)
+        })
+        .await
+        .expect("unlocked");
+
+    assert_eq!(unlocked, 500);
+    assert_eq!(
+        events,
+        vec![
+            ClaimProgress::WaitingForUnlock {
+                height: 100,
+                balance: 500,
+                unlocked_balance: 0,
+                expected: 500,
+            },
+            ClaimProgress::WaitingForUnlock {
+                height: 109,
+                balance: 500,
+                unlocked_balance: 0,
+                expected: 500,
+            },
+            ClaimProgress::Unlocked {
+                unlocked_balance: 500
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn times_out_while_balance_stays_locked() {
+    let url = serve_scripted(vec![
+        (
+            method("get_height"),
+            vec![json_rpc(json!({ "height": 100 }))],
+        ),
+        (method("get_balance"), vec![balance(500, 0)]),
+        (
+            method("refresh"),
+            vec![json_rpc(
+                json!({ "blocks_fetched": 0, "received_money": false }),
+            )],
+        ),
+    ])
+    .await;
+    let wallet = XmrWallet::connect_with_config(&url, fast_retry())
+        .await
+        .expect("connect");
+
+    let result =
+        wait_for_unlocked_balance(&wallet, &quick_wait(500, Duration::from_millis(50)), |_| {})
+            .await;
+
+    let err = result.expect_err("locked balance times out").to_string();
+    assert!(err.contains("timed out"), "{err}");
+}
+
+const SWEEP_TX: &str = "1111111111111111111111111111111111111111111111111111111111111111";
+
+fn stagenet_destination() -> String {
+    let params = ClaimParams {
+        alice_partial: Scalar::from(7u64),
+        bob_partial: Scalar::from(11u64),
+        revealed_secret: Zeroizing::new([13u8; 32]),
+        view_key: None,
+        destination_address: String::new(),
+        network: Network::Stagenet,
+        wallet_filename: String::new(),
+        restore_height: None,
+        expected_hashlock: None,
+        unlock: UnlockWait::default(),
+        journal_path: None,
+        transfer: TransferOptions::default(),
+        wallet_password: None,
+        cleanup: WalletCleanup::KeepEncrypted,
+    };
+    derive_claim_keys(&params)
+        .expect("derive destination")
+        .address
+        .to_string()
+}
+
+fn built_sweep() -> Value {
+    json_rpc(json!({
+        "tx_hash_list": [SWEEP_TX],
+        "tx_key_list": ["2222222222222222222222222222222222222222222222222222222222222222"],
+        "amount_list": [990],
+        "fee_list": [10],
+        "tx_blob_list": ["beef"],
+        "tx_metadata_list": ["cafe"],
+        "multisig_txset": "",
+        "unsigned_txset": "",
+    }))
+}
+
+fn relayed() -> Value {
+    json_rpc(json!({ "tx_hash": SWEEP_TX }))
+}
+
+fn no_transfers() -> Value {
+    json_rpc(json!({}))
+}
+
+fn pending_sweep_to(destination: &str) -> Value {
+    json_rpc(json!({
+        "pending": [{
+            "txid": SWEEP_TX,
+            "amount": 990,
+            "fee": 10,
+            "height": 0,
+            "destinations": [{ "address": destination, "amount": 990 }],
+        }],
+    }))
+}
+
+fn sweep_path(label: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("xmr_sweep_{label}_{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+/// Answers for a claim run from the key import up to the relay.
+fn fresh_claim_routes() -> Vec<(&'static str, Vec<Value>)> {
+    vec![
+        (method("generate_from_keys"), vec![json_rpc(json!({}))]),
+        (method("close_wallet"), vec![json_rpc(json!({}))]),
+        (
+            method("refresh"),
+            vec![json_rpc(
+                json!({ "blocks_fetched": 0, "received_money": false }),
+            )],
+        ),
+        (
+            method("get_height"),
+            vec![json_rpc(json!({ "height": 1301 }))],
+        ),
+        (
+            method("get_balance"),
+            vec![json_rpc(
+                json!({ "balance": 1000, "unlocked_balance": 1000 }),
+            )],
+        ),
+    ]
+}
+
+fn load_journal(path: &Path) -> ClaimJournal {
+    ClaimJournal::load(path)
+        .expect("load journal")
+        .expect("journal exists")
+}
+
+#[tokio::test]
+async fn claim_journals_sweep_before_relay() {
+    let destination = stagenet_destination();
+    let path = sweep_path("journal_persist");
+    let mut routes = fresh_claim_routes();
+    routes.extend([
+        (method("get_transfers"), vec![no_transfers()]),
+        (method("sweep_all"), vec![built_sweep()]),
+        (
+            method("relay_tx"),
+            vec![json!({
+                "jsonrpc": "2.0",
+                "id": "0",
+                "error": { "code": -1, "message": "daemon busy" },
+            })],
+        ),
+    ]);
+    let url = serve_scripted(routes).await;
+    let wallet = XmrWallet::connect_with_config(&url, fast_retry())
+        .await
+        .expect("connect");
+
+    let mut events = Vec::new();
+    let err = execute_claim_with_progress(
+        &wallet,
+        &journaled_claim(&destination, path.clone()),
+        |event| events.push(event),
+    )
+    .await
+    .expect_err("relay failed")
+    .to_string();
+
+    assert!(err.contains("daemon busy"), "{err}");
+    assert!(events.contains(&ClaimProgress::SweepBuilt {
+        tx_hashes: vec![SWEEP_TX.to_string()],
+        amount: 990,
+        fee: 10,
+    }));
+    let journal = load_journal(&path);
+    assert_eq!(journal.step, ClaimStep::TxBuilt);
+    let sweep = journal.sweep.expect("journaled sweep");
+    assert_eq!(sweep.destination, destination);
+    assert_eq!(sweep.txs[0].tx_metadata, "cafe");
+    assert_eq!(sweep.fee(), 10);
     let _ = std::fs::remove_file(&path);
 }
+
+#[tokio::test]
+async fn claim_stops_when_wallet_already_sent_to_destination() {
+    let destination = stagenet_destination();
+    let path = sweep_path("journal_already_sent");
+    // No sweep_all or relay_tx routes: building or relaying would fail.
+    let mut routes = fresh_claim_routes();
+    routes.push((
+        method("get_transfers"),
+        vec![pending_sweep_to(&destination)],
+    ));
+    let url = serve_scripted(routes).await;
+    let wallet = XmrWallet::connect_with_config(&url, fast_retry())
+        .await
+        .expect("connect");
+
+    let mut events = Vec::new();
+    let tx_hash = execute_claim_with_progress(
+        &wallet,
+        &journaled_claim(&destination, path.clone()),
+        |event| events.push(event),
+    )
+    .await
+    .expect("existing sweep");
+
+    assert_eq!(tx_hash, SWEEP_TX);
+    assert_eq!(
+        events.last(),
+        Some(&ClaimProgress::AlreadySwept {
+            tx_hash: SWEEP_TX.to_string()
+        })
+    );
+    let journal = load_journal(&path);
+    assert_eq!(journal.step, ClaimStep::TxRelayed);
+    assert_eq!(journal.tx_hash.as_deref(), Some(SWEEP_TX));
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn claim_treats_failed_relay_as_sent_when_wallet_lists_it() {
+    let destination = stagenet_destination();
+    let path = journal_at("journal_relay_lost", ClaimStep::TxBuilt, &destination);
+    let url = serve_scripted(vec![
+        (method("open_wallet"), vec![json_rpc(json!({}))]),
+        (method("close_wallet"), vec![json_rpc(json!({}))]),
+        (
+            method("refresh"),
+            vec![json_rpc(
+                json!({ "blocks_fetched": 0, "received_money": false }),
+            )],
+        ),
+        (
+            method("get_height"),
+            vec![json_rpc(json!({ "height": 1301 }))],
+        ),
+        (
+            method("relay_tx"),
+            vec![json!({
+                "jsonrpc": "2.0",
+                "id": "0",
+                "error": { "code": -1, "message": "request timed out" },
+            })],
+        ),
+        (
+            method("get_transfers"),
+            vec![pending_sweep_to(&destination)],
+        ),
+    ])
+    .await;
+    let wallet = XmrWallet::connect_with_config(&url, fast_retry())
+        .await
+        .expect("connect");
+
+    let tx_hash = execute_claim_with_progress(
+        &wallet,
+        &journaled_claim(&destination, path.clone()),
+        |_| {},
+    )
+    .await
+    .expect("relay went through");
+
+    assert_eq!(tx_hash, SWEEP_TX);
+    assert_eq!(load_journal(&path).step, ClaimStep::TxRelayed);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn claim_over_fee_cap_is_dropped_before_relay() {
+    let destination = stagenet_destination();
+    let path = sweep_path("journal_fee_cap");
+    // No relay_tx route: relaying would fail the test with a decode error
+    // rather than the fee error asserted below.
+    let mut routes = fresh_claim_routes();
+    routes.extend([
+        (method("get_transfers"), vec![no_transfers()]),
+        (method("sweep_all"), vec![built_sweep()]),
+    ]);
+    let url = serve_scripted(routes).await;
+    let wallet = XmrWallet::connect_with_config(&url, fast_retry())
+        .await
+        .expect("connect");
+    let mut params = journaled_claim(&destination, path.clone());
+    params.transfer.max_fee = Some(9);
+
+    let mut events = Vec::new();
+    let err = execute_claim_with_progress(&wallet, &params, |event| events.push(event))
+        .await
+        .expect_err("fee over cap")
+        .to_string();
+
+    assert!(err.contains("exceeds the maximum"), "{err}");
+    assert!(!events
+        .iter()
+        .any(|event| matches!(event, ClaimProgress::SweepBuilt { .. })));
+    let journal = load_journal(&path);
+    assert_eq!(journal.step, ClaimStep::Synced);
+    assert_eq!(journal.sweep, None);
+    let _ = std::fs::remove_file(&path);
+}
+
+fn journaled_claim(destination: &str, journal_path: PathBuf) -> ClaimParams {
+    ClaimParams {
+        alice_partial: Scalar::from(2u64),
+        bob_partial: Scalar::from(3u64),
+        revealed_secret: Zeroizing::new([5u8; 32]),
+        view_key: None,
+        destination_address: destination.to_string(),
+        network: Network::Stagenet,
+        wallet_filename: "claim_wallet".to_string(),
+        restore_height: Some(1000),
+        expected_hashlock: None,
+        journal_path: Some(journal_path),
+        unlock: UnlockWait::default(),
+        transfer: TransferOptions::default(),
+        wallet_password: None,
+        cleanup: WalletCleanup::KeepEncrypted,
+    }
+}
+
+fn journal_at(label: &str, step: ClaimStep, destination: &str) -> PathBuf {
+    let path = sweep_path(label);
+    let mut journal = ClaimJournal::new(destination, "claim_wallet");
+    journal.record_keys_imported();
+    journal.record_synced(1200);
+    journal.record_built(PendingSweep {
+        destination: destination.to_string(),
+        txs: vec![BuiltTx {
+            tx_hash: SWEEP_TX.to_string(),
+            tx_key: "22".repeat(32),
+            amount: 990,
+            fee: 10,
+            tx_blob: "beef".to_string(),
+            tx_metadata: "cafe".to_string(),
+        }],
+    });
+    if step >= ClaimStep::TxRelayed {
+        journal.record_relayed(SWEEP_TX);
+    }
+    journal.save(&path).expect("save journal");
+    path
+}
+
+#[tokio::test]
+async fn claim_after_relay_reports_recorded_tx_without_sweeping() {
+    let destination = stagenet_destination();
+    let path = journal_at("journal_relayed", ClaimStep::TxRelayed, &destination);
+    // No import, sweep_all or relay_tx routes: the rerun may only reopen
+    // the wallet and look for the confirmation.
+    let url = serve_scripted(vec![
+        (method("open_wallet"), vec![json_rpc(json!({}))]),
+        (
+            method("get_transfers"),
+            vec![pending_sweep_to(&destination)],
+        ),
+        (method("close_wallet"), vec![json_rpc(json!({}))]),
+    ])
+    .await;
+    let wallet = XmrWallet::connect_with_config(&url, fast_retry())
+        .await
+        .expect("connect");
+
+    let mut events = Vec::new();
+    let tx_hash = execute_claim_with_progress(
+        &wallet,
+        &journaled_claim(&destination, path.clone()),
+        |event| events.push(event),
+    )
+    .await
+    .expect("resumed claim");
+
+    assert_eq!(tx_hash, SWEEP_TX);
+    assert_eq!(
+        events,
+        vec![
+            ClaimProgress::KeysImported,
+            ClaimProgress::AlreadySwept {
+                tx_hash: SWEEP_TX.to_string()
+            },
+        ]
+    );
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn claim_relays_journaled_sweep_and_records_confirmation() {
+    let destination = stagenet_destination();
+    let path = journal_at("journal_built", ClaimStep::TxBuilt, &destination);
+    let mined = json_rpc(json!({
+        "out": [{
+            "txid": SWEEP_TX,
+            "amount": 990,
+            "fee": 10,
+            "height": 1300,
+            "destinations": [{ "address": destination, "amount": 990 }],
+        }],
+    }));
+    let mut params = journaled_claim(&destination, path.clone());
+    let wallet_dir = env::temp_dir().join(format!("xmr_claim_wallets_{}", std::process::id()));
+    std::fs::create_dir_all(&wallet_dir).expect("wallet dir");
+    std::fs::write(wallet_dir.join("claim_wallet"), b"cache").expect("wallet cache");
+    std::fs::write(wallet_dir.join("claim_wallet.keys"), b"keys").expect("wallet keys");
+    params.cleanup = WalletCleanup::SecureDelete {
+        wallet_dir: wallet_dir.clone(),
+    };
+    let keys = derive_claim_keys(&params).expect("claim keys");
+    let password = claim_wallet_password(&params, &keys).expect("password");
+    // Only an open_wallet carrying the derived password is answered, and
+    // there is no sweep_all route: the recorded sweep must be relayed as is.
+    let open_with_password: &'static str =
+        Box::leak(format!(r#""password":"{}""#, password.as_str()).into_boxed_str());
+    let url = serve_scripted(vec![
+        (open_with_password, vec![json_rpc(json!({}))]),
+        (method("close_wallet"), vec![json_rpc(json!({}))]),
+        (
+            method("refresh"),
+            vec![json_rpc(
+                json!({ "blocks_fetched": 0, "received_money": false }),
+            )],
+        ),
+        (
+            method("get_height"),
+            vec![json_rpc(json!({ "height": 1301 }))],
+        ),
+        (method("relay_tx"), vec![relayed()]),
+        (method("get_transfers"), vec![mined]),
+    ])
+    .await;
+    let wallet = XmrWallet::connect_with_config(&url, fast_retry())
+        .await
+        .expect("connect");
+
+    let mut events = Vec::new();
+    let tx_hash = execute_claim_with_progress(&wallet, &params, |event| events.push(event))
+        .await
+        .expect("resumed claim");
+
+    assert_eq!(tx_hash, SWEEP_TX);
+    assert_eq!(
+        events.last(),
+        Some(&ClaimProgress::WalletDeleted {
+            paths: vec![
+                wallet_dir.join("claim_wallet"),
+                wallet_dir.join("claim_wallet.keys"),
+            ],
+        })
+    );
+    assert!(!wallet_dir.join("claim_wallet.keys").exists());
+    let journal = ClaimJournal::load(&path)
+        .expect("load journal")
+        .expect("journal exists");
+    assert_eq!(journal.step, ClaimStep::Confirmed);
+    assert_eq!(journal.tx_hash.as_deref(), Some(SWEEP_TX));
+    assert_eq!(journal.confirmed_height, Some(1300));
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_dir_all(&wallet_dir);
+}
+
+#[test]
+fn claim_wallet_password_is_configured_or_derived_from_spend_key() {
+    let destination = stagenet_destination();
+    let mut params = journaled_claim(&destination, sweep_path("password"));
+    let keys = derive_claim_keys(&params).expect("claim keys");
+
+    let derived = claim_wallet_password(&params, &keys).expect("derived password");
+    assert_eq!(derived.len(), 64);
+    assert_eq!(
+        claim_wallet_password(&params, &keys).expect("derived password"),
+        derived
+    );
+
+    params.wallet_password = Some(Zeroizing::new("configured".to_string()));
+    assert_eq!(
+        claim_wallet_password(&params, &keys)
+            .expect("configured password")
+            .as_str(),
+        "configured"
+    );
+
+    params.wallet_password = Some(Zeroizing::new(String::new()));
+    assert!(claim_wallet_password(&params, &keys).is_err());
+}
@@ -0,0 +1,199 @@
+#![allow(dead_code)]
+
+pub mod wallet_rpc;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use md5::{Digest, Md5};
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use xmr_wallet::RetryConfig;
+
+/// Serves canned responses: the first route whose needle appears in the
+/// request line or body answers it.
+pub async fn serve(routes: Vec<(&'static str, Value)>) -> String {
+    serve_scripted(
+        routes
+            .into_iter()
+            .map(|(needle, body)| (needle, vec![body]))
+            .collect(),
+    )
+    .await
+}
+
+/// Like [`serve`], but each route answers with its responses in order and
+/// repeats the last one once the script runs out.
+pub async fn serve_scripted(routes: Vec<(&'static str, Vec<Value>)>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let url = format!("http://{}", listener.local_addr().expect("addr"));
+    let routes = Arc::new(Mutex::new(routes));
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                return;
+            };
+            let routes = routes.clone();
+            tokio::spawn(async move {
+                let request = read_request(&mut stream).await;
+                let body = {
+                    let mut routes = routes.lock().unwrap();
+                    routes
+                        .iter_mut()
+                        .find(|(needle, _)| request.contains(needle))
+                        .map(|(_, script)| {
+                            if script.len() > 1 {
+                                script.remove(0)
+                            } else {
+                                script[0].clone()
+                            }
+                        })
+                        .unwrap_or_else(|| json!({}))
+                        .to_string()
+                };
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            });
+        }
+    });
+    url
+}
+
+const DIGEST_REALM: &str = "monero-rpc";
+const DIGEST_NONCE: &str = "9f3c1b2a7d";
+
+/// Like [`serve`], behind `--rpc-login username:password`: requests without
+/// a valid digest `Authorization` get epee's 401 with an `MD5-sess` and an
+/// `MD5` challenge. Returns the URL and the number of challenges sent.
+pub async fn serve_digest(
+    username: &'static str,
+    password: &'static str,
+    routes: Vec<(&'static str, Value)>,
+) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let url = format!("http://{}", listener.local_addr().expect("addr"));
+    let challenges = Arc::new(AtomicUsize::new(0));
+    let counter = challenges.clone();
+    let routes = Arc::new(routes);
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                return;
+            };
+            let routes = routes.clone();
+            let counter = counter.clone();
+            tokio::spawn(async move {
+                let request = read_request(&mut stream).await;
+                let response = if digest_valid(&request, username, password) {
+                    let body = routes
+                        .iter()
+                        .find(|(needle, _)| request.contains(needle))
+                        .map(|(_, body)| body.clone())
+                        .unwrap_or_else(|| json!({}))
+                        .to_string();
+                    format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                } else {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    format!(
+                        "HTTP/1.1 401 Unauthorized\r\nwww-authenticate: Digest qop=\"auth\",algorithm=MD5-sess,realm=\"{DIGEST_REALM}\",nonce=\"{DIGEST_NONCE}\",stale=false\r\nwww-authenticate: Digest qop=\"auth\",algorithm=MD5,realm=\"{DIGEST_REALM}\",nonce=\"{DIGEST_NONCE}\",stale=false\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                    )
+                };
+                let _ = stream.write_all(response.as_bytes()).await;
+            });
+        }
+    });
+    (url, challenges)
+}
+
+fn digest_valid(request: &str, username: &str, password: &str) -> bool {
+    let Some(header) = request.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.eq_ignore_ascii_case("authorization")
+            .then(|| value.trim().strip_prefix("Digest "))
+            .flatten()
+    }) else {
+        return false;
+    };
+    let param = |name: &str| {
+        header.split(", ").find_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            (key == name).then(|| value.trim_matches('"').to_string())
+        })
+    };
+    let md5 = |input: String| hex::encode(Md5::digest(input.as_bytes()));
+    let (Some(uri), Some(nc), Some(cnonce), Some(response)) = (
+        param("uri"),
+        param("nc"),
+        param("cnonce"),
+        param("response"),
+    ) else {
+        return false;
+    };
+    if param("username").as_deref() != Some(username)
+        || param("nonce").as_deref() != Some(DIGEST_NONCE)
+        || param("algorithm").as_deref() != Some("MD5")
+    {
+        return false;
+    }
+    let ha1 = md5(format!("{username}:{DIGEST_REALM}:{password}"));
+    let ha2 = md5(format!("POST:{uri}"));
+    response == md5(format!("{ha1}:{DIGEST_NONCE}:{nc}:{cnonce}:auth:{ha2}"))
+}
+
+async fn read_request(stream: &mut TcpStream) -> String {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 1024];
+    loop {
+        let read = stream.read(&mut chunk).await.unwrap_or(0);
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        let text = String::from_utf8_lossy(&buffer);
+        if let Some(split) = text.find("\r\n\r\n") {
+            let length = text[..split]
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse::<usize>().ok())
+                        .flatten()
+                })
+                .unwrap_or(0);
+            if buffer.len() >= split + 4 + length {
+                break;
+            }
+        }
+    }
+    String::from_utf8_lossy(&buffer).into_owned()
+}
+
+pub fn json_rpc(result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": "0", "result": result })
+}
+
+/// Route needle matching a JSON-RPC method name.
+pub fn method(name: &str) -> &'static str {
+    Box::leak(format!("\"method\":\"{name}\"").into_boxed_str())
+}
+
+pub fn fast_retry() -> RetryConfig {
+    RetryConfig {
+        timeout: Duration::from_secs(2),
+        max_retries: 0,
+        base_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(1),
+        jitter_ms: 0,
+    }
+}
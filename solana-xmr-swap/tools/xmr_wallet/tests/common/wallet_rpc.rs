@@ -0,0 +1,969 @@
+//! In-process stand-in for monero-wallet-rpc.
+//!
+//! It keeps a tiny chain and the wallet files created on it, so a test can
+//! run the real `XmrWallet` calls end to end: funds sent to an address show
+//! up in the wallet holding it once mined and refreshed, unlock after
+//! [`UNLOCK_BLOCKS`], and are spent by `sweep_all` or `transfer` plus
+//! `relay_tx`. Any method can be scripted to fail or to answer late. [`MockWalletRpc::start_peer`]
+//! runs a second wallet-rpc on the same chain, so two parties can set up and
+//! spend a 2-of-2 multisig wallet against each other.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use monero::{Address, Network, PrivateKey, PublicKey};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+
+use super::read_request;
+
+/// Blocks before a received output can be spent.
+pub const UNLOCK_BLOCKS: u64 = 10;
+
+/// A scripted deviation for the next call of a method.
+#[derive(Debug, Clone, Copy)]
+pub enum Fault {
+    /// Answer with a JSON-RPC error; nothing is changed.
+    Error,
+    /// Run the call, but answer only after this long, so the client times
+    /// out while the effect still lands.
+    Delay(Duration),
+}
+
+#[derive(Debug, Clone)]
+struct Output {
+    txid: String,
+    address: String,
+    amount: u64,
+    height: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+struct WalletFile {
+    password: String,
+    address: String,
+    restore_height: u64,
+    synced_height: u64,
+    multisig: Option<MultisigFile>,
+}
+
+#[derive(Debug, Clone)]
+struct MultisigFile {
+    /// This wallet's `prepare_multisig` message.
+    prepared: String,
+    /// Key exchange rounds done: 1 after `make_multisig`, 2 once ready.
+    rounds: u32,
+    /// Joint address, derived from both parties' first messages.
+    joint: String,
+    /// Whether the other signer's multisig info has been imported.
+    imported: bool,
+}
+
+#[derive(Debug, Clone)]
+struct SentTx {
+    txid: String,
+    wallet: String,
+    destination: String,
+    amount: u64,
+    fee: u64,
+    metadata: String,
+    inputs: Vec<usize>,
+    /// Returned to the sending wallet when relayed.
+    change: u64,
+    relayed: bool,
+    /// Multisig wallets that signed the set; empty for ordinary sweeps.
+    signers: Vec<String>,
+}
+
+#[derive(Default)]
+struct MockState {
+    height: u64,
+    blocks_per_refresh: u64,
+    fee: u64,
+    outputs: Vec<Output>,
+    spent: HashSet<usize>,
+    sent: Vec<SentTx>,
+    wallets: HashMap<String, WalletFile>,
+    open: Option<String>,
+    faults: HashMap<String, VecDeque<Fault>>,
+    calls: Vec<String>,
+    counter: u64,
+}
+
+/// Handle on a running mock; clones share the same state.
+#[derive(Clone)]
+pub struct MockWalletRpc {
+    url: String,
+    state: Arc<Mutex<MockState>>,
+    /// Wallet this server has open; each server has its own.
+    open: Arc<Mutex<Option<String>>>,
+}
+
+impl MockWalletRpc {
+    /// Starts the server with the chain at `height`.
+    pub async fn start(height: u64) -> Self {
+        let state = Arc::new(Mutex::new(MockState {
+            height,
+            fee: 10_000,
+            ..MockState::default()
+        }));
+        Self::listen(state).await
+    }
+
+    /// Starts another wallet-rpc on the same chain and wallet directory,
+    /// with its own open wallet, as the counterparty's would be.
+    pub async fn start_peer(&self) -> Self {
+        Self::listen(self.state.clone()).await
+    }
+
+    async fn listen(state: Arc<Mutex<MockState>>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let url = format!("http://{}", listener.local_addr().expect("addr"));
+        let open = Arc::new(Mutex::new(None));
+        let (shared, shared_open) = (state.clone(), open.clone());
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let (state, open) = (shared.clone(), shared_open.clone());
+                tokio::spawn(async move {
+                    let request = read_request(&mut stream).await;
+                    let (body, delay) = handle(&state, &open, &request);
+                    if let Some(delay) = delay {
+                        tokio::time::sleep(delay).await;
+                    }
+                    let body = body.to_string();
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        Self { url, state, open }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn height(&self) -> u64 {
+        self.state.lock().unwrap().height
+    }
+
+    /// Mines `blocks` blocks, confirming everything in the pool.
+    pub fn mine(&self, blocks: u64) {
+        let mut state = self.state.lock().unwrap();
+        mine(&mut state, blocks);
+    }
+
+    /// Blocks mined by each `refresh`, so waits on the chain make progress.
+    pub fn mine_on_refresh(&self, blocks: u64) {
+        self.state.lock().unwrap().blocks_per_refresh = blocks;
+    }
+
+    /// Puts a transaction paying `amount` to `address` in the pool and
+    /// returns its id.
+    pub fn broadcast(&self, address: &str, amount: u64) -> String {
+        let mut state = self.state.lock().unwrap();
+        let txid = next_hash(&mut state, "tx");
+        state.outputs.push(Output {
+            txid: txid.clone(),
+            address: address.to_string(),
+            amount,
+            height: None,
+        });
+        txid
+    }
+
+    /// Pays `amount` to `address` in the current top block.
+    pub fn receive(&self, address: &str, amount: u64) -> String {
+        let txid = self.broadcast(address, amount);
+        self.mine(1);
+        txid
+    }
+
+    /// Makes the next call of `method` deviate as `fault` says. Faults queue
+    /// up per method.
+    pub fn fault(&self, method: &str, fault: Fault) {
+        self.state
+            .lock()
+            .unwrap()
+            .faults
+            .entry(method.to_string())
+            .or_default()
+            .push_back(fault);
+    }
+
+    /// How many times `method` was called, faulted calls included.
+    pub fn calls(&self, method: &str) -> usize {
+        let state = self.state.lock().unwrap();
+        state
+            .calls
+            .iter()
+            .filter(|called| *called == method)
+            .count()
+    }
+
+    pub fn wallet_password(&self, filename: &str) -> Option<String> {
+        let state = self.state.lock().unwrap();
+        state
+            .wallets
+            .get(filename)
+            .map(|wallet| wallet.password.clone())
+    }
+
+    pub fn open_wallet(&self) -> Option<String> {
+        self.open.lock().unwrap().clone()
+    }
+
+    /// Amount received by `address` in mined transactions.
+    pub fn received_by(&self, address: &str) -> u64 {
+        let state = self.state.lock().unwrap();
+        state
+            .outputs
+            .iter()
+            .filter(|output| output.address == address && output.height.is_some())
+            .map(|output| output.amount)
+            .sum()
+    }
+
+    /// Relayed transactions, as (tx id, destination, amount).
+    pub fn relayed(&self) -> Vec<(String, String, u64)> {
+        let state = self.state.lock().unwrap();
+        state
+            .sent
+            .iter()
+            .filter(|tx| tx.relayed)
+            .map(|tx| (tx.txid.clone(), tx.destination.clone(), tx.amount))
+            .collect()
+    }
+}
+
+/// A valid stagenet address for `label`, standing in for wallets whose keys
+/// the test does not care about.
+pub fn mock_address(label: &str) -> String {
+    let mut bytes: [u8; 32] = Sha256::digest(label.as_bytes()).into();
+    bytes[31] &= 0x0f;
+    let key = PrivateKey::from_slice(&bytes).expect("private key");
+    let public = PublicKey::from_private_key(&key);
+    Address::standard(Network::Stagenet, public, public).to_string()
+}
+
+fn next_hash(state: &mut MockState, label: &str) -> String {
+    state.counter += 1;
+    hex::encode(Sha256::digest(format!("{label}-{}", state.counter)))
+}
+
+fn mine(state: &mut MockState, blocks: u64) {
+    if blocks == 0 {
+        return;
+    }
+    let height = state.height;
+    for output in state.outputs.iter_mut().filter(|o| o.height.is_none()) {
+        output.height = Some(height);
+    }
+    state.height += blocks;
+}
+
+fn handle(
+    state: &Mutex<MockState>,
+    open: &Mutex<Option<String>>,
+    request: &str,
+) -> (Value, Option<Duration>) {
+    let body = request
+        .split_once("\r\n\r\n")
+        .map(|(_, body)| body)
+        .unwrap_or_default();
+    let Ok(call) = serde_json::from_str::<Value>(body) else {
+        return (rpc_error(&Value::Null, -32700, "parse error"), None);
+    };
+    let id = call.get("id").cloned().unwrap_or(Value::Null);
+    let method = call["method"].as_str().unwrap_or_default().to_string();
+    let params = call.get("params").cloned().unwrap_or_else(|| json!({}));
+
+    let mut state = state.lock().unwrap();
+    state.calls.push(method.clone());
+    let fault = state
+        .faults
+        .get_mut(&method)
+        .and_then(|faults| faults.pop_front());
+    let delay = match fault {
+        Some(Fault::Error) => return (rpc_error(&id, -1, "scripted failure"), None),
+        Some(Fault::Delay(delay)) => Some(delay),
+        None => None,
+    };
+    let mut open = open.lock().unwrap();
+    state.open = open.clone();
+    let reply = dispatch(&mut state, &method, &params);
+    *open = state.open.clone();
+    let body = match reply {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => rpc_error(&id, code, &message),
+    };
+    (body, delay)
+}
+
+fn rpc_error(id: &Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+type Reply = Result<Value, (i64, String)>;
+
+fn dispatch(state: &mut MockState, method: &str, params: &Value) -> Reply {
+    match method {
+        "create_wallet" => {
+            let filename = str_param(params, "filename")?;
+            let address = mock_address(&filename);
+            let height = state.height;
+            add_wallet(state, params, filename, address, height)
+        }
+        "generate_from_keys" => {
+            let filename = str_param(params, "filename")?;
+            let address = str_param(params, "address")?;
+            let restore_height = params["restore_height"].as_u64().unwrap_or(0);
+            add_wallet(state, params, filename, address.clone(), restore_height)?;
+            Ok(json!({
+                "address": address,
+                "info": "Wallet has been generated successfully.",
+            }))
+        }
+        "open_wallet" => {
+            let filename = str_param(params, "filename")?;
+            let password = params["password"].as_str().unwrap_or_default();
+            let wallet = state
+                .wallets
+                .get(&filename)
+                .ok_or((-1, format!("Failed to open wallet {filename}")))?;
+            if wallet.password != password {
+                return Err((-1, "invalid password".to_string()));
+            }
+            state.open = Some(filename);
+            Ok(json!({}))
+        }
+        "close_wallet" => {
+            state.open = None;
+            Ok(json!({}))
+        }
+        "refresh" => {
+            let blocks = state.blocks_per_refresh;
+            mine(state, blocks);
+            let height = state.height;
+            let wallet = open_wallet(state)?;
+            let fetched = height.saturating_sub(wallet.synced_height);
+            if let Some(start) = params["start_height"].as_u64() {
+                wallet.restore_height = wallet.restore_height.min(start);
+            }
+            wallet.synced_height = height;
+            Ok(json!({ "blocks_fetched": fetched, "received_money": false }))
+        }
+        "get_height" => {
+            let wallet = open_wallet(state)?;
+            Ok(json!({ "height": wallet.synced_height }))
+        }
+        "get_balance" => {
+            let wallet = open_wallet(state)?.clone();
+            let (balance, unlocked) = balances(state, &wallet);
+            Ok(json!({
+                "balance": balance,
+                "unlocked_balance": unlocked,
+                "multisig_import_needed": false,
+                "per_subaddress": [],
+            }))
+        }
+        "get_address" => {
+            let wallet = open_wallet(state)?;
+            Ok(json!({
+                "address": wallet.address,
+                "addresses": [{
+                    "address": wallet.address,
+                    "address_index": 0,
+                    "label": "Primary account",
+                    "used": true,
+                }],
+            }))
+        }
+        "get_transfers" => {
+            let wallet = open_wallet(state)?.clone();
+            Ok(transfers(state, &wallet, params))
+        }
+        "sweep_all" => sweep_all(state, params),
+        "transfer" => transfer(state, params),
+        "relay_tx" => {
+            let metadata = str_param(params, "hex")?;
+            let index = state
+                .sent
+                .iter()
+                .position(|tx| tx.metadata == metadata)
+                .ok_or((-1, "Failed to parse tx metadata".to_string()))?;
+            relay(state, index)?;
+            Ok(json!({ "tx_hash": state.sent[index].txid }))
+        }
+        "check_tx_key" => check_tx(state, params),
+        "check_tx_proof" => {
+            let txid = str_param(params, "txid")?;
+            if str_param(params, "signature")? != tx_proof(&txid) {
+                return Ok(
+                    json!({ "good": false, "received": 0, "in_pool": false, "confirmations": 0 }),
+                );
+            }
+            let mut check = check_tx(state, params)?;
+            check["good"] = json!(true);
+            Ok(check)
+        }
+        "prepare_multisig"
+        | "make_multisig"
+        | "exchange_multisig_keys"
+        | "is_multisig"
+        | "export_multisig_info"
+        | "import_multisig_info"
+        | "describe_transfer"
+        | "sign_multisig"
+        | "submit_multisig" => multisig(state, method, params),
+        _ => Err((-32601, format!("Method not found: {method}"))),
+    }
+}
+
+/// What `txid` paid `params["address"]`, as `check_tx_key` reports it.
+fn check_tx(state: &MockState, params: &Value) -> Reply {
+    let txid = str_param(params, "txid")?;
+    let address = str_param(params, "address")?;
+    let outputs: Vec<&Output> = state
+        .outputs
+        .iter()
+        .filter(|output| output.txid == txid)
+        .collect();
+    if outputs.is_empty() {
+        return Err((-8, "Transaction not found".to_string()));
+    }
+    let received: u64 = outputs
+        .iter()
+        .filter(|output| output.address == address)
+        .map(|output| output.amount)
+        .sum();
+    let height = outputs[0].height;
+    Ok(json!({
+        "received": received,
+        "in_pool": height.is_none(),
+        "confirmations": height.map_or(0, |height| state.height - height),
+    }))
+}
+
+/// The tx proof the mock accepts for `txid`, standing in for what the
+/// sender's `get_tx_proof` returns.
+pub fn tx_proof(txid: &str) -> String {
+    format!("OutProofV2{txid}")
+}
+
+fn str_param(params: &Value, name: &str) -> Result<String, (i64, String)> {
+    params[name]
+        .as_str()
+        .map(str::to_string)
+        .ok_or((-32602, format!("missing {name}")))
+}
+
+fn add_wallet(
+    state: &mut MockState,
+    params: &Value,
+    filename: String,
+    address: String,
+    restore_height: u64,
+) -> Reply {
+    if state.wallets.contains_key(&filename) {
+        return Err((-21, "Wallet already exists.".to_string()));
+    }
+    let wallet = WalletFile {
+        password: params["password"].as_str().unwrap_or_default().to_string(),
+        address,
+        restore_height,
+        synced_height: restore_height,
+        multisig: None,
+    };
+    state.wallets.insert(filename.clone(), wallet);
+    state.open = Some(filename);
+    Ok(json!({}))
+}
+
+fn open_wallet(state: &mut MockState) -> Result<&mut WalletFile, (i64, String)> {
+    let filename = state
+        .open
+        .clone()
+        .ok_or((-13, "No wallet file".to_string()))?;
+    Ok(state
+        .wallets
+        .get_mut(&filename)
+        .expect("open wallet exists"))
+}
+
+/// Outputs the wallet has scanned and not spent, by index.
+fn owned_outputs(state: &MockState, wallet: &WalletFile) -> Vec<usize> {
+    state
+        .outputs
+        .iter()
+        .enumerate()
+        .filter(|(index, output)| {
+            output.address == wallet.address
+                && !state.spent.contains(index)
+                && output.height.is_some_and(|height| {
+                    height >= wallet.restore_height && height < wallet.synced_height
+                })
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+fn unlocked_outputs(state: &MockState, wallet: &WalletFile) -> Vec<usize> {
+    owned_outputs(state, wallet)
+        .into_iter()
+        .filter(|index| {
+            state.outputs[*index]
+                .height
+                .is_some_and(|height| height + UNLOCK_BLOCKS <= wallet.synced_height)
+        })
+        .collect()
+}
+
+fn balances(state: &MockState, wallet: &WalletFile) -> (u64, u64) {
+    let sum = |indices: Vec<usize>| -> u64 {
+        indices
+            .into_iter()
+            .map(|index| state.outputs[index].amount)
+            .sum()
+    };
+    (
+        sum(owned_outputs(state, wallet)),
+        sum(unlocked_outputs(state, wallet)),
+    )
+}
+
+fn transfer_entry(
+    kind: &str,
+    txid: &str,
+    address: &str,
+    amount: u64,
+    fee: u64,
+    height: Option<u64>,
+    current: u64,
+) -> Value {
+    json!({
+        "address": address,
+        "amount": amount,
+        "confirmations": height.map_or(0, |height| current - height),
+        "double_spend_seen": false,
+        "fee": fee,
+        "height": height.unwrap_or(0),
+        "note": "",
+        "payment_id": "0000000000000000",
+        "subaddr_index": { "major": 0, "minor": 0 },
+        "suggested_confirmations_threshold": 1,
+        "timestamp": 1_700_000_000u64,
+        "txid": txid,
+        "type": kind,
+        "unlock_time": 0,
+        "destinations": [{ "address": address, "amount": amount }],
+    })
+}
+
+fn transfers(state: &MockState, wallet: &WalletFile, params: &Value) -> Value {
+    let wanted = |name: &str| params[name].as_bool().unwrap_or(false);
+    let filename = state.open.clone().unwrap_or_default();
+    let mut result = serde_json::Map::new();
+    if wanted("in") {
+        let incoming: Vec<Value> = state
+            .outputs
+            .iter()
+            .filter(|output| {
+                output.address == wallet.address
+                    && output.height.is_some_and(|height| {
+                        height >= wallet.restore_height && height < wallet.synced_height
+                    })
+            })
+            .map(|output| {
+                transfer_entry(
+                    "in",
+                    &output.txid,
+                    &output.address,
+                    output.amount,
+                    0,
+                    output.height,
+                    state.height,
+                )
+            })
+            .collect();
+        result.insert("in".to_string(), Value::from(incoming));
+    }
+    if wanted("pool") {
+        let pooled: Vec<Value> = state
+            .outputs
+            .iter()
+            .filter(|output| output.address == wallet.address && output.height.is_none())
+            .map(|output| {
+                transfer_entry(
+                    "pool",
+                    &output.txid,
+                    &output.address,
+                    output.amount,
+                    0,
+                    None,
+                    state.height,
+                )
+            })
+            .collect();
+        result.insert("pool".to_string(), Value::from(pooled));
+    }
+    for (category, mined) in [("out", true), ("pending", false)] {
+        if !wanted(category) {
+            continue;
+        }
+        let sent: Vec<Value> = state
+            .sent
+            .iter()
+            .filter(|tx| tx.relayed && tx.wallet == filename)
+            .filter_map(|tx| {
+                let height = state
+                    .outputs
+                    .iter()
+                    .find(|output| output.txid == tx.txid)
+                    .and_then(|output| output.height);
+                (height.is_some() == mined).then(|| {
+                    transfer_entry(
+                        category,
+                        &tx.txid,
+                        &tx.destination,
+                        tx.amount,
+                        tx.fee,
+                        height,
+                        state.height,
+                    )
+                })
+            })
+            .collect();
+        result.insert(category.to_string(), Value::from(sent));
+    }
+    Value::Object(result)
+}
+
+fn sweep_all(state: &mut MockState, params: &Value) -> Reply {
+    let destination = str_param(params, "address")?;
+    let filename = state
+        .open
+        .clone()
+        .ok_or((-13, "No wallet file".to_string()))?;
+    let wallet = state.wallets[&filename].clone();
+    let multisig = match &wallet.multisig {
+        Some(multisig) if multisig.rounds < 2 => {
+            return Err((
+                -1,
+                "This wallet is multisig, but not yet finalized".to_string(),
+            ))
+        }
+        Some(multisig) if !multisig.imported => {
+            return Err((
+                -1,
+                "This multisig wallet needs multisig info imported".to_string(),
+            ))
+        }
+        Some(_) => true,
+        None => false,
+    };
+    let inputs = unlocked_outputs(state, &wallet);
+    let total: u64 = inputs
+        .iter()
+        .map(|index| state.outputs[*index].amount)
+        .sum();
+    if total <= state.fee {
+        return Err((
+            -37,
+            "No unlocked balance in the specified account".to_string(),
+        ));
+    }
+    let txid = next_hash(state, "tx");
+    let tx_key = next_hash(state, "key");
+    let metadata = next_hash(state, "metadata");
+    let tx = SentTx {
+        txid: txid.clone(),
+        wallet: filename.clone(),
+        destination,
+        amount: total - state.fee,
+        fee: state.fee,
+        metadata: metadata.clone(),
+        inputs,
+        change: 0,
+        relayed: false,
+        signers: if multisig { vec![filename] } else { Vec::new() },
+    };
+    let result = if multisig {
+        json!({
+            "tx_hash_list": [txid],
+            "amount_list": [tx.amount],
+            "fee_list": [tx.fee],
+            "multisig_txset": metadata,
+            "unsigned_txset": "",
+        })
+    } else {
+        json!({
+            "tx_hash_list": [txid],
+            "tx_key_list": [tx_key],
+            "amount_list": [tx.amount],
+            "fee_list": [tx.fee],
+            "tx_blob_list": [format!("blob{metadata}")],
+            "tx_metadata_list": [metadata],
+            "multisig_txset": "",
+            "unsigned_txset": "",
+        })
+    };
+    state.sent.push(tx);
+    if !multisig && !params["do_not_relay"].as_bool().unwrap_or(false) {
+        let index = state.sent.len() - 1;
+        relay(state, index)?;
+    }
+    Ok(result)
+}
+
+/// Spends every unlocked output of the open wallet, paying the single
+/// destination and returning the rest as change.
+fn transfer(state: &mut MockState, params: &Value) -> Reply {
+    let destination = &params["destinations"][0];
+    let address = str_param(destination, "address")?;
+    let amount = destination["amount"]
+        .as_u64()
+        .ok_or((-32602, "missing amount".to_string()))?;
+    let filename = state
+        .open
+        .clone()
+        .ok_or((-13, "No wallet file".to_string()))?;
+    let wallet = state.wallets[&filename].clone();
+    let inputs = unlocked_outputs(state, &wallet);
+    let total: u64 = inputs
+        .iter()
+        .map(|index| state.outputs[*index].amount)
+        .sum();
+    if total < amount + state.fee {
+        return Err((-38, "not enough unlocked money".to_string()));
+    }
+    let txid = next_hash(state, "tx");
+    let tx_key = next_hash(state, "key");
+    let metadata = next_hash(state, "metadata");
+    let fee = state.fee;
+    state.sent.push(SentTx {
+        txid: txid.clone(),
+        wallet: filename,
+        destination: address,
+        amount,
+        fee,
+        metadata: metadata.clone(),
+        inputs,
+        change: total - amount - fee,
+        relayed: false,
+        signers: Vec::new(),
+    });
+    if !params["do_not_relay"].as_bool().unwrap_or(false) {
+        let index = state.sent.len() - 1;
+        relay(state, index)?;
+    }
+    Ok(json!({
+        "tx_hash": txid,
+        "tx_key": tx_key,
+        "amount": amount,
+        "fee": fee,
+        "tx_blob": format!("blob{metadata}"),
+        "tx_metadata": metadata,
+        "multisig_txset": "",
+        "unsigned_txset": "",
+    }))
+}
+
+fn relay(state: &mut MockState, index: usize) -> Result<(), (i64, String)> {
+    let tx = state.sent[index].clone();
+    if tx.relayed || tx.inputs.iter().any(|input| state.spent.contains(input)) {
+        return Err((-1, "Failed to commit tx: double spend".to_string()));
+    }
+    state.spent.extend(tx.inputs.iter().copied());
+    state.outputs.push(Output {
+        txid: tx.txid.clone(),
+        address: tx.destination.clone(),
+        amount: tx.amount,
+        height: None,
+    });
+    if tx.change > 0 {
+        let change = state.wallets[&tx.wallet].address.clone();
+        state.outputs.push(Output {
+            txid: tx.txid.clone(),
+            address: change,
+            amount: tx.change,
+            height: None,
+        });
+    }
+    state.sent[index].relayed = true;
+    Ok(())
+}
+
+/// The 2-of-2 multisig flow: `prepare_multisig`, `make_multisig` and one
+/// `exchange_multisig_keys` round make the wallet ready; spending needs the
+/// other signer's multisig info imported and both signatures on the set.
+fn multisig(state: &mut MockState, method: &str, params: &Value) -> Reply {
+    let filename = state
+        .open
+        .clone()
+        .ok_or((-13, "No wallet file".to_string()))?;
+    let tag = hex::encode(&Sha256::digest(filename.as_bytes())[..8]);
+    let password = params["password"].as_str().unwrap_or_default().to_string();
+    let peers: Vec<String> = params["multisig_info"]
+        .as_array()
+        .or_else(|| params["info"].as_array())
+        .map(|infos| {
+            infos
+                .iter()
+                .filter_map(|info| info.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    let wallet = open_wallet(state)?;
+    let rounds = wallet
+        .multisig
+        .as_ref()
+        .map_or(0, |multisig| multisig.rounds);
+    let ready = rounds == 2;
+    match method {
+        "prepare_multisig" => {
+            if rounds > 0 {
+                return Err((-1, "This wallet is already multisig".to_string()));
+            }
+            let prepared = format!("MultisigV1{tag}");
+            wallet.multisig = Some(MultisigFile {
+                prepared: prepared.clone(),
+                rounds: 0,
+                joint: String::new(),
+                imported: false,
+            });
+            Ok(json!({ "multisig_info": prepared }))
+        }
+        "make_multisig" => {
+            let Some(multisig) = wallet.multisig.as_mut().filter(|_| rounds == 0) else {
+                return Err((-1, "This wallet is not prepared for multisig".to_string()));
+            };
+            if password != wallet.password {
+                return Err((-1, "Invalid password".to_string()));
+            }
+            if params["threshold"].as_u64() != Some(2)
+                || peers.len() != 1
+                || !peers[0].starts_with("MultisigV1")
+                || peers[0] == multisig.prepared
+            {
+                return Err((-1, "Invalid multisig info".to_string()));
+            }
+            let mut infos = [multisig.prepared.clone(), peers[0].clone()];
+            infos.sort();
+            multisig.joint = mock_address(&infos.join("+"));
+            multisig.rounds = 1;
+            Ok(json!({ "address": "", "multisig_info": format!("MultisigxV2R1{tag}") }))
+        }
+        "exchange_multisig_keys" => {
+            let Some(multisig) = wallet.multisig.as_mut().filter(|_| rounds == 1) else {
+                return Err((-1, "This wallet is not in a key exchange round".to_string()));
+            };
+            if password != wallet.password {
+                return Err((-1, "Invalid password".to_string()));
+            }
+            let own = format!("MultisigxV2R1{tag}");
+            if peers.len() != 1 || !peers[0].starts_with("MultisigxV2R1") || peers[0] == own {
+                return Err((-1, "Invalid multisig info".to_string()));
+            }
+            multisig.rounds = 2;
+            wallet.address = multisig.joint.clone();
+            Ok(json!({ "address": wallet.address, "multisig_info": "" }))
+        }
+        "is_multisig" => {
+            let (threshold, total) = if rounds > 0 { (2, 2) } else { (0, 0) };
+            Ok(json!({
+                "multisig": rounds > 0,
+                "ready": ready,
+                "threshold": threshold,
+                "total": total,
+            }))
+        }
+        _ if !ready => Err((-1, "This wallet is not multisig".to_string())),
+        "export_multisig_info" => Ok(json!({ "info": format!("MultisigInfo{tag}") })),
+        "import_multisig_info" => {
+            let own = format!("MultisigInfo{tag}");
+            if peers.is_empty()
+                || peers
+                    .iter()
+                    .any(|info| !info.starts_with("MultisigInfo") || *info == own)
+            {
+                return Err((-1, "Failed to import multisig info".to_string()));
+            }
+            if let Some(multisig) = wallet.multisig.as_mut() {
+                multisig.imported = true;
+            }
+            let wallet = wallet.clone();
+            Ok(json!({ "n_outputs": owned_outputs(state, &wallet).len() }))
+        }
+        "describe_transfer" => {
+            let index = multisig_set(state, &params["multisig_txset"])?;
+            let tx = &state.sent[index];
+            Ok(json!({
+                "desc": [{
+                    "amount_in": tx.amount + tx.fee,
+                    "amount_out": tx.amount,
+                    "recipients": [{ "address": tx.destination, "amount": tx.amount }],
+                    "change_amount": 0,
+                    "change_address": "",
+                    "fee": tx.fee,
+                    "ring_size": 16,
+                    "unlock_time": 0,
+                    "dummy_outputs": 0,
+                    "extra": "",
+                    "payment_id": "",
+                }],
+            }))
+        }
+        "sign_multisig" => {
+            if !wallet
+                .multisig
+                .as_ref()
+                .is_some_and(|multisig| multisig.imported)
+            {
+                return Err((
+                    -1,
+                    "This multisig wallet needs multisig info imported".to_string(),
+                ));
+            }
+            let index = multisig_set(state, &params["tx_data_hex"])?;
+            let tx = &mut state.sent[index];
+            if tx.signers.contains(&filename) {
+                return Err((-1, "This wallet has already signed".to_string()));
+            }
+            tx.signers.push(filename);
+            Ok(json!({ "tx_data_hex": tx.metadata, "tx_hash_list": [tx.txid] }))
+        }
+        "submit_multisig" => {
+            let index = multisig_set(state, &params["tx_data_hex"])?;
+            if state.sent[index].signers.len() < 2 {
+                return Err((-1, "Not enough signers to submit".to_string()));
+            }
+            relay(state, index)?;
+            Ok(json!({ "tx_hash_list": [state.sent[index].txid] }))
+        }
+        _ => Err((-32601, format!("Method not found: {method}"))),
+    }
+}
+
+fn multisig_set(state: &MockState, txset: &Value) -> Result<usize, (i64, String)> {
+    let txset = txset.as_str().unwrap_or_default();
+    state
+        .sent
+        .iter()
+        .position(|tx| !tx.signers.is_empty() && tx.metadata == txset)
+        .ok_or((-1, "Failed to parse multisig tx data".to_string()))
+}
@@ -0,0 +1,337 @@
+//! Digest authentication, certificate and timeout settings for wallet-rpc
+//! and monerod connections.
+
+mod common;
+
+use std::env;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use common::{fast_retry, json_rpc, method, serve_digest};
+use openssl::asn1::Asn1Time;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, Private};
+use openssl::ssl::{SslAcceptor, SslMethod};
+use openssl::x509::extension::SubjectAlternativeName;
+use openssl::x509::{X509NameBuilder, X509};
+use serde_json::json;
+use tokio::net::TcpListener;
+use xmr_wallet::{CertFingerprint, RpcLogin, WalletConnectionConfig, XmrDaemon, XmrWallet};
+
+fn login(value: &str) -> WalletConnectionConfig {
+    WalletConnectionConfig {
+        login: Some(value.parse().expect("login")),
+        ..WalletConnectionConfig::default()
+    }
+}
+
+fn temp_file(label: &str, contents: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("xmr_connection_{label}_{}", std::process::id()));
+    std::fs::write(&path, contents).expect("write temp file");
+    path
+}
+
+/// Self-signed certificate, naming 127.0.0.1 when `ip_san` is set.
+/// wallet-rpc started with `--rpc-ssl enabled` and no certificate makes
+/// one without any names.
+fn self_signed(ip_san: bool) -> (X509, PKey<Private>) {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).expect("curve");
+    let key = PKey::from_ec_key(EcKey::generate(&group).expect("key")).expect("pkey");
+    let mut name = X509NameBuilder::new().expect("name");
+    name.append_entry_by_text("CN", "monero-wallet-rpc")
+        .expect("cn");
+    let name = name.build();
+    let mut cert = X509::builder().expect("builder");
+    cert.set_version(2).expect("version");
+    cert.set_subject_name(&name).expect("subject");
+    cert.set_issuer_name(&name).expect("issuer");
+    cert.set_pubkey(&key).expect("pubkey");
+    cert.set_not_before(&Asn1Time::days_from_now(0).expect("time"))
+        .expect("not before");
+    cert.set_not_after(&Asn1Time::days_from_now(1).expect("time"))
+        .expect("not after");
+    if ip_san {
+        let san = SubjectAlternativeName::new()
+            .ip("127.0.0.1")
+            .build(&cert.x509v3_context(None, None))
+            .expect("san");
+        cert.append_extension(san).expect("extension");
+    }
+    cert.sign(&key, MessageDigest::sha256()).expect("sign");
+    (cert.build(), key)
+}
+
+/// Serves `body` over TLS on a blocking thread and returns the URL.
+fn serve_tls(cert: &X509, key: &PKey<Private>, body: serde_json::Value) -> String {
+    let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).expect("acceptor");
+    acceptor.set_certificate(cert).expect("cert");
+    acceptor.set_private_key(key).expect("key");
+    let acceptor = acceptor.build();
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let url = format!("https://{}", listener.local_addr().expect("addr"));
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let Ok(mut tls) = acceptor.accept(stream) else {
+                continue;
+            };
+            let mut buffer = [0u8; 4096];
+            let _ = tls.read(&mut buffer);
+            let body = body.to_string();
+            let _ = write!(
+                tls,
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+        }
+    });
+    url
+}
+
+#[tokio::test]
+async fn ca_cert_replaces_the_system_roots() {
+    let (cert, key) = self_signed(true);
+    let url = serve_tls(&cert, &key, json_rpc(json!({ "height": 77 })));
+    let pem = String::from_utf8(cert.to_pem().expect("pem")).expect("utf8");
+    let path = temp_file("pinned.pem", &pem);
+    let pinned = WalletConnectionConfig {
+        ca_cert: Some(path.clone()),
+        ..WalletConnectionConfig::default()
+    };
+
+    let wallet = XmrWallet::connect_with_connection(&url, fast_retry(), &pinned)
+        .await
+        .expect("connect");
+    assert_eq!(wallet.get_height().await.expect("height"), 77);
+
+    let (other, _) = self_signed(true);
+    std::fs::write(&path, other.to_pem().expect("pem")).expect("write");
+    let repinned = XmrWallet::connect_with_connection(&url, fast_retry(), &pinned)
+        .await
+        .expect("connect");
+    let _ = std::fs::remove_file(&path);
+    assert!(repinned.get_height().await.is_err());
+
+    let system_roots = XmrWallet::connect_with_config(&url, fast_retry())
+        .await
+        .expect("connect");
+    assert!(system_roots.get_height().await.is_err());
+}
+
+#[tokio::test]
+async fn fingerprint_pins_a_certificate_without_names() {
+    let (cert, key) = self_signed(false);
+    let url = serve_tls(&cert, &key, json_rpc(json!({ "height": 78 })));
+    let der = cert.to_der().expect("der");
+    let fingerprint = hex::encode(cert.digest(MessageDigest::sha256()).expect("digest"));
+    assert_eq!(
+        fingerprint.parse::<CertFingerprint>().expect("fingerprint"),
+        CertFingerprint::of(&der)
+    );
+    let pinned = WalletConnectionConfig {
+        cert_sha256: Some(CertFingerprint::of(&der)),
+        ..WalletConnectionConfig::default()
+    };
+    let wallet = XmrWallet::connect_with_connection(&url, fast_retry(), &pinned)
+        .await
+        .expect("connect");
+    assert_eq!(wallet.get_height().await.expect("height"), 78);
+
+    let path = temp_file(
+        "nameless.pem",
+        &String::from_utf8(cert.to_pem().expect("pem")).expect("utf8"),
+    );
+    let as_ca = WalletConnectionConfig {
+        ca_cert: Some(path.clone()),
+        ..WalletConnectionConfig::default()
+    };
+    let wallet = XmrWallet::connect_with_connection(&url, fast_retry(), &as_ca)
+        .await
+        .expect("connect");
+    let _ = std::fs::remove_file(&path);
+    assert!(wallet.get_height().await.is_err());
+
+    let (other, _) = self_signed(false);
+    let wrong = WalletConnectionConfig {
+        cert_sha256: Some(CertFingerprint::of(&other.to_der().expect("der"))),
+        ..WalletConnectionConfig::default()
+    };
+    let wallet = XmrWallet::connect_with_connection(&url, fast_retry(), &wrong)
+        .await
+        .expect("connect");
+    assert!(wallet.get_height().await.is_err());
+}
+
+#[tokio::test]
+async fn wallet_answers_the_digest_challenge_once() {
+    let (url, challenges) = serve_digest(
+        "swap",
+        "s3cret",
+        vec![(method("get_height"), json_rpc(json!({ "height": 1234 })))],
+    )
+    .await;
+    let wallet = XmrWallet::connect_with_connection(&url, fast_retry(), &login("swap:s3cret"))
+        .await
+        .expect("connect");
+
+    assert_eq!(wallet.get_height().await.expect("height"), 1234);
+    assert_eq!(wallet.get_height().await.expect("height"), 1234);
+    assert_eq!(challenges.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn wrong_or_missing_login_is_refused() {
+    let (url, _) = serve_digest("swap", "s3cret", vec![]).await;
+
+    let wrong = XmrWallet::connect_with_connection(&url, fast_retry(), &login("swap:guess"))
+        .await
+        .expect("connect");
+    let err = wrong.get_height().await.expect_err("wrong password");
+    assert!(err.to_string().contains("rejected the RPC login"), "{err}");
+
+    let anonymous = XmrWallet::connect_with_config(&url, fast_retry())
+        .await
+        .expect("connect");
+    let err = anonymous.get_height().await.expect_err("no login");
+    assert!(err.to_string().contains("requires an RPC login"), "{err}");
+}
+
+#[tokio::test]
+async fn daemon_uses_the_same_connection_settings() {
+    let (url, challenges) = serve_digest(
+        "node",
+        "pw",
+        vec![
+            (
+                method("get_info"),
+                json_rpc(json!({
+                    "status": "OK",
+                    "height": 3_000_000,
+                    "target_height": 0,
+                    "synchronized": true,
+                    "top_block_hash": "aa".repeat(32),
+                    "tx_pool_size": 0,
+                    "nettype": "stagenet",
+                })),
+            ),
+            (
+                "/get_transaction_pool_hashes",
+                json!({ "status": "OK", "tx_hashes": [] }),
+            ),
+        ],
+    )
+    .await;
+    let daemon = XmrDaemon::connect_with_connection(&url, fast_retry(), &login("node:pw"))
+        .await
+        .expect("connect");
+
+    assert_eq!(daemon.get_info().await.expect("info").height, 3_000_000);
+    assert!(daemon
+        .get_transaction_pool_hashes()
+        .await
+        .expect("pool")
+        .is_empty());
+    assert_eq!(challenges.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn request_timeout_caps_a_stalled_server() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let url = format!("http://{}", listener.local_addr().expect("addr"));
+    tokio::spawn(async move {
+        let mut held = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            held.push(stream);
+        }
+    });
+    let connection = WalletConnectionConfig {
+        timeout: Some(Duration::from_millis(100)),
+        ..WalletConnectionConfig::default()
+    };
+    let wallet = XmrWallet::connect_with_connection(&url, fast_retry(), &connection)
+        .await
+        .expect("connect");
+
+    let started = Instant::now();
+    assert!(wallet.get_height().await.is_err());
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn config_loads_from_file_and_env() {
+    let path = temp_file(
+        "config.json",
+        r#"{ "login": "swap:pa:ss", "ca_cert": "/etc/monero/ca.pem", "timeout_ms": 2500 }"#,
+    );
+    let config = WalletConnectionConfig::from_file(&path).expect("load");
+    let _ = std::fs::remove_file(&path);
+    let login = config.login.as_ref().expect("login");
+    assert_eq!(login.username, "swap");
+    assert_eq!(login.password.as_str(), "pa:ss");
+    assert!(!format!("{config:?}").contains("pa:ss"));
+    assert_eq!(config.ca_cert, Some(PathBuf::from("/etc/monero/ca.pem")));
+    assert_eq!(config.timeout, Some(Duration::from_millis(2500)));
+
+    let colons = vec!["AB"; 32].join(":");
+    let path = temp_file(
+        "pinned.json",
+        &format!(r#"{{ "cert_sha256": "{colons}" }}"#),
+    );
+    let config = WalletConnectionConfig::from_file(&path).expect("load");
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(config.cert_sha256, Some(CertFingerprint([0xab; 32])));
+
+    for bad in [
+        r#"{ "login": "no-colon" }"#,
+        r#"{ "client_cert": "/etc/monero/client.pem" }"#,
+        r#"{ "timeout_ms": 0 }"#,
+        r#"{ "cert_sha256": "abcd" }"#,
+        r#"{ "ca_cert": "/etc/monero/ca.pem", "cert_sha256": "00" }"#,
+        r#"{ "password": "typo" }"#,
+    ] {
+        let path = temp_file("bad.json", bad);
+        assert!(WalletConnectionConfig::from_file(&path).is_err(), "{bad}");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    env::set_var("XMR_DAEMON_RPC_LOGIN", "node:pw");
+    env::set_var("XMR_DAEMON_RPC_REQUEST_TIMEOUT_MS", "750");
+    let config = WalletConnectionConfig::daemon_from_env().expect("env");
+    assert_eq!(
+        config.login,
+        Some("node:pw".parse::<RpcLogin>().expect("login"))
+    );
+    assert_eq!(config.timeout, Some(Duration::from_millis(750)));
+    assert_eq!(config.ca_cert, None);
+    env::set_var("XMR_DAEMON_RPC_REQUEST_TIMEOUT_MS", "soon");
+    assert!(WalletConnectionConfig::daemon_from_env().is_err());
+    env::remove_var("XMR_DAEMON_RPC_LOGIN");
+    env::remove_var("XMR_DAEMON_RPC_REQUEST_TIMEOUT_MS");
+}
+
+#[tokio::test]
+async fn unusable_certificates_are_refused_at_connect() {
+    let missing = WalletConnectionConfig {
+        ca_cert: Some(env::temp_dir().join("xmr_connection_missing_ca.pem")),
+        ..WalletConnectionConfig::default()
+    };
+    assert!(
+        XmrWallet::connect_with_connection("https://127.0.0.1:18083", fast_retry(), &missing)
+            .await
+            .is_err()
+    );
+
+    let path = temp_file("garbage.pem", "not a certificate");
+    let garbage = WalletConnectionConfig {
+        ca_cert: Some(path.clone()),
+        ..WalletConnectionConfig::default()
+    };
+    let result =
+        XmrDaemon::connect_with_connection("https://127.0.0.1:18081", fast_retry(), &garbage).await;
+    let _ = std::fs::remove_file(&path);
+    assert!(result.is_err());
+}
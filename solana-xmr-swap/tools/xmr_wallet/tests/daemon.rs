@@ -0,0 +1,177 @@
+mod common;
+
+use common::wallet_rpc::mock_address;
+use common::{fast_retry, json_rpc, serve};
+use serde_json::json;
+use xmr_wallet::{TransferOptions, XmrDaemon, XmrWallet};
+
+#[tokio::test]
+async fn reads_chain_height_and_sync_lag() {
+    let url = serve(vec![(
+        "\"get_info\"",
+        json_rpc(json!({
+            "status": "OK",
+            "height": 1_500,
+            "target_height": 1_520,
+            "synchronized": false,
+            "top_block_hash": "aa".repeat(32),
+            "tx_pool_size": 3,
+            "nettype": "stagenet",
+        })),
+    )])
+    .await;
+
+    let daemon = XmrDaemon::connect_with_config(&url, fast_retry())
+        .await
+        .expect("client");
+    let info = daemon.get_info().await.expect("get_info");
+    assert_eq!(info.height, 1_500);
+    assert_eq!(info.sync_lag(), 20);
+    assert_eq!(info.nettype, "stagenet");
+}
+
+#[tokio::test]
+async fn reads_block_header_by_height() {
+    let url = serve(vec![(
+        "get_block_header_by_height",
+        json_rpc(json!({
+            "status": "OK",
+            "block_header": {
+                "height": 42,
+                "hash": "bb".repeat(32),
+                "prev_hash": "cc".repeat(32),
+                "depth": 7,
+                "orphan_status": false,
+                "timestamp": 1_700_000_000u64,
+                "reward": 600_000_000_000u64,
+            },
+        })),
+    )])
+    .await;
+
+    let daemon = XmrDaemon::connect_with_config(&url, fast_retry())
+        .await
+        .expect("client");
+    let header = daemon.get_block_header_by_height(42).await.expect("header");
+    assert_eq!(header.height, 42);
+    assert_eq!(header.hash, "bb".repeat(32));
+    assert_eq!(header.prev_hash, "cc".repeat(32));
+}
+
+#[tokio::test]
+async fn reports_pool_and_mined_transactions() {
+    let pooled = "dd".repeat(32);
+    let url = serve(vec![
+        (
+            "/get_transactions",
+            json!({
+                "status": "OK",
+                "txs": [{
+                    "tx_hash": "ee".repeat(32),
+                    "in_pool": false,
+                    "block_height": 99,
+                    "double_spend_seen": false,
+                    "as_hex": "",
+                }],
+            }),
+        ),
+        (
+            "/get_transaction_pool_hashes",
+            json!({ "status": "OK", "tx_hashes": [pooled.clone()] }),
+        ),
+    ])
+    .await;
+
+    let daemon = XmrDaemon::connect_with_config(&url, fast_retry())
+        .await
+        .expect("client");
+    let txs = daemon
+        .get_transactions(&["ee".repeat(32)])
+        .await
+        .expect("get_transactions");
+    assert_eq!(txs.len(), 1);
+    assert_eq!(txs[0].block_height, Some(99));
+    assert!(!txs[0].in_pool);
+
+    assert!(daemon.is_in_pool(&pooled).await.expect("pool"));
+    assert!(!daemon.is_in_pool(&"ee".repeat(32)).await.expect("pool"));
+}
+
+#[tokio::test]
+async fn surfaces_daemon_errors() {
+    let url = serve(vec![
+        (
+            "\"get_info\"",
+            json!({ "jsonrpc": "2.0", "id": "0", "error": { "code": -1, "message": "busy" } }),
+        ),
+        ("/get_transactions", json!({ "status": "Failed" })),
+    ])
+    .await;
+
+    let daemon = XmrDaemon::connect_with_config(&url, fast_retry())
+        .await
+        .expect("client");
+    assert!(daemon.get_info().await.is_err());
+    assert!(daemon.get_transactions(&["ff".repeat(32)]).await.is_err());
+}
+
+#[tokio::test]
+async fn transfer_options_take_ring_size_from_hard_fork() {
+    let url = serve(vec![(
+        "hard_fork_info",
+        json_rpc(json!({
+            "version": 16,
+            "enabled": true,
+            "earliest_height": 1_788_000,
+            "status": "OK",
+        })),
+    )])
+    .await;
+    let daemon = XmrDaemon::connect_with_config(&url, fast_retry())
+        .await
+        .expect("connect");
+
+    let options = TransferOptions::default()
+        .validate_with_daemon(&daemon)
+        .await
+        .expect("validated");
+    assert_eq!(options.ring_size, Some(16));
+
+    let stale = TransferOptions {
+        ring_size: Some(11),
+        ..TransferOptions::default()
+    };
+    assert!(stale.validate_with_daemon(&daemon).await.is_err());
+}
+
+#[tokio::test]
+async fn ring_sizes_below_two_are_refused_before_any_call() {
+    for ring_size in [0, 1] {
+        let options = TransferOptions {
+            ring_size: Some(ring_size),
+            ..TransferOptions::default()
+        };
+        assert!(options.check().is_err(), "{ring_size}");
+        assert!(options.with_required_ring_size(16).is_err(), "{ring_size}");
+    }
+    assert!(TransferOptions::default().check().is_ok());
+
+    // Nothing listens here; the options must be refused first.
+    let wallet = XmrWallet::connect_with_config("http://127.0.0.1:9", fast_retry())
+        .await
+        .expect("client");
+    let options = TransferOptions {
+        ring_size: Some(0),
+        ..TransferOptions::default()
+    };
+    let err = wallet
+        .build_transfer(&mock_address("destination"), 1, &options)
+        .await
+        .expect_err("ring size 0");
+    assert!(err.to_string().contains("ring size"), "{err}");
+    let err = wallet
+        .build_sweep_all(&mock_address("destination"), &options)
+        .await
+        .expect_err("ring size 0");
+    assert!(err.to_string().contains("ring size"), "{err}");
+}
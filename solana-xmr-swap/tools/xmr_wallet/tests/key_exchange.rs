@@ -0,0 +1,134 @@
+use curve25519_dalek::constants::{ED25519_BASEPOINT_POINT, EIGHT_TORSION};
+use curve25519_dalek::scalar::Scalar;
+use xmr_wallet::{
+    combine_view_key_shares, create_key_share, joint_public_spend_key, verify_key_share,
+    CryptoError, KeyShare, PublicKey, SecretKey, KEY_SHARE_LEN,
+};
+
+const SWAP_ID: &[u8] = b"swap-0001";
+
+fn public_of(scalar: Scalar) -> PublicKey {
+    PublicKey::from_compressed((ED25519_BASEPOINT_POINT * scalar).compress().to_bytes()).unwrap()
+}
+
+#[test]
+fn verified_shares_sum_to_joint_key() {
+    let alice = Scalar::from(11u64);
+    let bob = Scalar::from(13u64);
+    let t = Scalar::from(17u64);
+
+    let alice_share = create_key_share(&SecretKey::from_scalar(alice), SWAP_ID).unwrap();
+    let bob_share = create_key_share(&SecretKey::from_scalar(bob), SWAP_ID).unwrap();
+    let shares = [
+        verify_key_share(&alice_share, SWAP_ID).unwrap(),
+        verify_key_share(&bob_share, SWAP_ID).unwrap(),
+    ];
+
+    let joint = joint_public_spend_key(&shares, &public_of(t)).unwrap();
+    assert_eq!(
+        joint.to_compressed(),
+        public_of(alice + bob + t).to_compressed()
+    );
+}
+
+#[test]
+fn share_round_trips_through_bytes() {
+    let share = create_key_share(&SecretKey::from_scalar(Scalar::from(5u64)), SWAP_ID).unwrap();
+    let bytes = share.to_bytes();
+    assert_eq!(bytes.len(), KEY_SHARE_LEN);
+    let parsed = KeyShare::from_bytes(&bytes).unwrap();
+    assert_eq!(parsed.to_bytes(), bytes);
+    verify_key_share(&parsed, SWAP_ID).unwrap();
+}
+
+#[test]
+fn rejects_proof_for_another_swap() {
+    let share = create_key_share(&SecretKey::from_scalar(Scalar::from(5u64)), SWAP_ID).unwrap();
+    assert!(matches!(
+        verify_key_share(&share, b"swap-0002"),
+        Err(CryptoError::InvalidProof)
+    ));
+}
+
+#[test]
+fn rejects_rogue_key_share() {
+    // The attacker publishes X - A, reusing a proof made for a key it knows.
+    let alice_public = public_of(Scalar::from(11u64));
+    let honest = create_key_share(&SecretKey::from_scalar(Scalar::from(99u64)), SWAP_ID).unwrap();
+    let rogue_point = ED25519_BASEPOINT_POINT * Scalar::from(1234u64) - alice_public.point();
+
+    let mut bytes = honest.to_bytes();
+    bytes[..32].copy_from_slice(&rogue_point.compress().to_bytes());
+    let rogue = KeyShare::from_bytes(&bytes).unwrap();
+    assert!(matches!(
+        verify_key_share(&rogue, SWAP_ID),
+        Err(CryptoError::InvalidProof)
+    ));
+}
+
+#[test]
+fn parsing_rejects_torsioned_or_non_canonical_points() {
+    let share = create_key_share(&SecretKey::from_scalar(Scalar::from(5u64)), SWAP_ID).unwrap();
+    let bytes = share.to_bytes();
+    let public = share.public().point();
+
+    let mut torsioned = bytes;
+    torsioned[..32].copy_from_slice((public + EIGHT_TORSION[1]).compress().as_bytes());
+    assert!(matches!(
+        KeyShare::from_bytes(&torsioned),
+        Err(CryptoError::TorsionedPoint)
+    ));
+
+    // y = 1 with the sign bit set decompresses but does not round-trip.
+    let mut non_canonical = [0u8; 32];
+    non_canonical[0] = 1;
+    non_canonical[31] = 0x80;
+    let mut bad_commitment = bytes;
+    bad_commitment[32..64].copy_from_slice(&non_canonical);
+    assert!(matches!(
+        KeyShare::from_bytes(&bad_commitment),
+        Err(CryptoError::NonCanonicalPoint)
+    ));
+    assert!(matches!(
+        PublicKey::from_compressed(non_canonical),
+        Err(CryptoError::NonCanonicalPoint)
+    ));
+}
+
+#[test]
+fn rejects_tampered_response() {
+    let share = create_key_share(&SecretKey::from_scalar(Scalar::from(5u64)), SWAP_ID).unwrap();
+    let mut bytes = share.to_bytes();
+    bytes[64] ^= 0x01;
+    let tampered = KeyShare::from_bytes(&bytes).unwrap();
+    assert!(verify_key_share(&tampered, SWAP_ID).is_err());
+}
+
+#[test]
+fn rejects_duplicate_shares() {
+    let share = create_key_share(&SecretKey::from_scalar(Scalar::from(5u64)), SWAP_ID).unwrap();
+    let verified = verify_key_share(&share, SWAP_ID).unwrap();
+    let result = joint_public_spend_key(&[verified.clone(), verified], &public_of(Scalar::ONE));
+    assert!(matches!(result, Err(CryptoError::DuplicateKeyShare)));
+}
+
+#[test]
+fn rejects_zero_secret() {
+    assert!(create_key_share(&SecretKey::from_scalar(Scalar::ZERO), SWAP_ID).is_err());
+}
+
+#[test]
+fn view_key_is_sum_of_shares() {
+    let alice = SecretKey::from_scalar(Scalar::from(3u64));
+    let bob = SecretKey::from_scalar(Scalar::from(4u64));
+    let joint = combine_view_key_shares(&[alice, bob]).unwrap();
+    assert_eq!(joint.as_scalar(), Scalar::from(7u64));
+}
+
+#[test]
+fn rejects_view_shares_summing_to_zero() {
+    let alice = SecretKey::from_scalar(Scalar::from(3u64));
+    let bob = SecretKey::from_scalar(-Scalar::from(3u64));
+    assert!(combine_view_key_shares(&[alice, bob]).is_err());
+    assert!(combine_view_key_shares(&[]).is_err());
+}
@@ -0,0 +1,220 @@
+use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+use curve25519_dalek::scalar::Scalar;
+use monero::Network;
+use std::env;
+use std::path::PathBuf;
+use xmr_wallet::{
+    combine_view_key_shares, create_key_share, derive_claim_keys, derive_lock_address, lock_xmr,
+    verify_key_share, BuiltTx, ClaimParams, LockReceipt, PendingLock, PublicKey, SecretKey,
+    TransferOptions, UnlockWait, VerifiedKeyShare, WalletCleanup, XmrWallet,
+};
+use zeroize::Zeroizing;
+
+const SWAP_ID: &[u8] = b"swap-0001";
+
+fn verified_share(scalar: Scalar) -> VerifiedKeyShare {
+    let share = create_key_share(&SecretKey::from_scalar(scalar), SWAP_ID).expect("key share");
+    verify_key_share(&share, SWAP_ID).expect("verified share")
+}
+
+fn adaptor_point(scalar: Scalar) -> PublicKey {
+    PublicKey::from_compressed((ED25519_BASEPOINT_POINT * scalar).compress().to_bytes())
+        .expect("adaptor point")
+}
+
+fn claim_params(alice: Scalar, bob: Scalar, secret: Scalar) -> ClaimParams {
+    ClaimParams {
+        alice_partial: alice,
+        bob_partial: bob,
+        revealed_secret: Zeroizing::new(secret.to_bytes()),
+        view_key: None,
+        destination_address: "stagenet_placeholder".to_string(),
+        network: Network::Stagenet,
+        wallet_filename: "claim_wallet".to_string(),
+        restore_height: Some(0),
+        expected_hashlock: None,
+        unlock: UnlockWait::default(),
+        journal_path: None,
+        transfer: TransferOptions::default(),
+        wallet_password: None,
+        cleanup: WalletCleanup::KeepEncrypted,
+    }
+}
+
+#[test]
+fn lock_address_matches_claim_address() {
+    let alice = SecretKey::random().as_scalar();
+    let bob = SecretKey::random().as_scalar();
+    let secret = SecretKey::random().as_scalar();
+    let keys = derive_claim_keys(&claim_params(alice, bob, secret)).expect("claim keys");
+
+    let view_key = SecretKey::from_bytes(keys.view_key.to_bytes());
+    let lock_address = derive_lock_address(
+        &[verified_share(alice), verified_share(bob)],
+        &adaptor_point(secret),
+        &view_key,
+        Network::Stagenet,
+    )
+    .expect("lock address");
+
+    assert_eq!(lock_address, keys.address);
+}
+
+#[test]
+fn shared_view_key_address_matches_claim_address() {
+    let alice = SecretKey::random().as_scalar();
+    let bob = SecretKey::random().as_scalar();
+    let secret = SecretKey::random().as_scalar();
+    let view_key = combine_view_key_shares(&[
+        SecretKey::from_scalar(SecretKey::random().as_scalar()),
+        SecretKey::from_scalar(SecretKey::random().as_scalar()),
+    ])
+    .expect("view key");
+
+    // The locking party only knows public spend shares and the view key.
+    let lock_address = derive_lock_address(
+        &[verified_share(alice), verified_share(bob)],
+        &adaptor_point(secret),
+        &view_key,
+        Network::Stagenet,
+    )
+    .expect("lock address");
+
+    let mut params = claim_params(alice, bob, secret);
+    params.view_key = Some(view_key.as_scalar());
+    let keys = derive_claim_keys(&params).expect("claim keys");
+    assert_eq!(keys.view_key.to_bytes(), view_key.to_bytes());
+    assert_eq!(lock_address, keys.address);
+}
+
+#[test]
+fn rejects_duplicate_shares() {
+    let view_key = SecretKey::from_scalar(Scalar::from(7u64));
+    let result = derive_lock_address(
+        &[
+            verified_share(Scalar::from(2u64)),
+            verified_share(Scalar::from(2u64)),
+        ],
+        &adaptor_point(Scalar::from(3u64)),
+        &view_key,
+        Network::Stagenet,
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn rejects_shares_cancelling_the_adaptor_point() {
+    let view_key = SecretKey::from_scalar(Scalar::from(7u64));
+    let result = derive_lock_address(
+        &[verified_share(Scalar::from(5u64))],
+        &adaptor_point(-Scalar::from(5u64)),
+        &view_key,
+        Network::Stagenet,
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn rejects_empty_shares() {
+    let view_key = SecretKey::from_scalar(Scalar::from(7u64));
+    let result = derive_lock_address(
+        &[],
+        &adaptor_point(Scalar::from(3u64)),
+        &view_key,
+        Network::Stagenet,
+    );
+    assert!(result.is_err());
+}
+
+fn receipt_path(label: &str) -> PathBuf {
+    let mut path = env::temp_dir();
+    path.push(format!("xmr_lock_{label}_{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn sample_receipt() -> LockReceipt {
+    LockReceipt {
+        tx_id: "ab".repeat(32),
+        tx_key: "cd".repeat(32),
+        address: "stagenet_placeholder".to_string(),
+        amount: 1_000_000_000_000,
+        fee: 30_000_000,
+    }
+}
+
+#[test]
+fn lock_receipt_round_trips_and_is_not_overwritten() {
+    let path = receipt_path("round_trip");
+    let receipt = sample_receipt();
+    receipt.save(&path).expect("save receipt");
+    assert_eq!(LockReceipt::load(&path).expect("load receipt"), receipt);
+
+    assert!(sample_receipt().save(&path).is_err());
+    let _ = std::fs::remove_file(&path);
+}
+
+fn sample_pending() -> PendingLock {
+    PendingLock {
+        receipt: sample_receipt(),
+        tx: BuiltTx {
+            tx_hash: "ab".repeat(32),
+            tx_key: "cd".repeat(32),
+            amount: 1_000_000_000_000,
+            fee: 30_000_000,
+            tx_blob: "00".to_string(),
+            tx_metadata: "11".to_string(),
+        },
+    }
+}
+
+#[test]
+fn pending_lock_reads_as_a_receipt() {
+    let path = receipt_path("pending");
+    let pending = sample_pending();
+    pending.save(&path).expect("save pending lock");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).expect("stat").permissions().mode();
+        assert_eq!(
+            mode & 0o777,
+            0o600,
+            "the tx key is readable by its owner only"
+        );
+    }
+    assert_eq!(
+        PendingLock::load(&path).expect("load pending lock"),
+        pending
+    );
+    assert_eq!(
+        LockReceipt::load(&path).expect("load receipt"),
+        pending.receipt
+    );
+    assert!(pending.save(&path).is_err());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn lock_xmr_refuses_a_receipt_for_another_lock() {
+    let path = receipt_path("existing");
+    sample_pending().save(&path).expect("save pending lock");
+
+    let view_key = SecretKey::from_scalar(Scalar::from(7u64));
+    let address = derive_lock_address(
+        &[verified_share(Scalar::from(3u64))],
+        &adaptor_point(Scalar::from(4u64)),
+        &view_key,
+        Network::Stagenet,
+    )
+    .expect("lock address");
+    // Nothing listens here; the receipt check must fail before any RPC.
+    let wallet = XmrWallet::connect("http://127.0.0.1:9")
+        .await
+        .expect("client");
+    let err = lock_xmr(&wallet, 1, &address, &TransferOptions::default(), &path)
+        .await
+        .expect_err("receipt for another lock");
+    assert!(err.to_string().contains("pays"), "{err}");
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,312 @@
+//! 2-of-2 multisig setup, lock watching and spending against two wallet-rpc
+//! stand-ins on one chain.
+
+mod common;
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use common::wallet_rpc::{mock_address, MockWalletRpc, UNLOCK_BLOCKS};
+use common::{json_rpc, serve_scripted};
+use monero::Address;
+use serde_json::json;
+use xmr_wallet::{
+    advance_multisig, cosign_multisig_sweep, export_multisig_sync, import_multisig_sync,
+    prepare_multisig_wallet, propose_multisig_sweep, submit_multisig_sweep, LockBackend,
+    LockMechanism, LockMechanismStore, LockWatcher, MultisigLock, RetryConfig, TransferOptions,
+    WatcherConfig, WatcherEvent, XmrDaemon, XmrWallet,
+};
+
+const LOCKED: u64 = 1_000_000;
+const PASSWORD: &str = "multisig-pw";
+
+fn retry() -> RetryConfig {
+    RetryConfig {
+        timeout: Duration::from_secs(2),
+        max_retries: 0,
+        base_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(1),
+        jitter_ms: 0,
+    }
+}
+
+#[derive(Default)]
+struct MemoryStore {
+    mechanisms: Mutex<HashMap<String, LockMechanism>>,
+}
+
+impl LockMechanismStore for MemoryStore {
+    fn save_lock_mechanism(
+        &self,
+        swap_id: &str,
+        mechanism: &LockMechanism,
+    ) -> xmr_wallet::Result<()> {
+        self.mechanisms
+            .lock()
+            .unwrap()
+            .insert(swap_id.to_string(), mechanism.clone());
+        Ok(())
+    }
+
+    fn load_lock_mechanism(&self, swap_id: &str) -> xmr_wallet::Result<Option<LockMechanism>> {
+        Ok(self.mechanisms.lock().unwrap().get(swap_id).cloned())
+    }
+}
+
+/// Runs the key exchange between two fresh wallets and returns them with
+/// the joint address.
+async fn setup(
+    alice_rpc: &MockWalletRpc,
+    bob_rpc: &MockWalletRpc,
+) -> (XmrWallet, XmrWallet, String) {
+    let alice = XmrWallet::connect_with_config(alice_rpc.url(), retry())
+        .await
+        .expect("connect alice");
+    let bob = XmrWallet::connect_with_config(bob_rpc.url(), retry())
+        .await
+        .expect("connect bob");
+
+    let alice_first = prepare_multisig_wallet(&alice, "alice_multisig", PASSWORD)
+        .await
+        .expect("prepare alice");
+    let bob_first = prepare_multisig_wallet(&bob, "bob_multisig", PASSWORD)
+        .await
+        .expect("prepare bob");
+
+    let alice_made = advance_multisig(&alice, &bob_first, PASSWORD)
+        .await
+        .expect("make alice");
+    let bob_made = advance_multisig(&bob, &alice_first, PASSWORD)
+        .await
+        .expect("make bob");
+    assert_eq!(alice_made.address, None);
+    assert_eq!(bob_made.address, None);
+
+    let alice_done = advance_multisig(&alice, &bob_made.info.expect("bob round"), PASSWORD)
+        .await
+        .expect("exchange alice");
+    let bob_done = advance_multisig(&bob, &alice_made.info.expect("alice round"), PASSWORD)
+        .await
+        .expect("exchange bob");
+    assert_eq!(alice_done.info, None);
+    let joint = alice_done.address.expect("alice address");
+    assert_eq!(bob_done.address.as_deref(), Some(joint.as_str()));
+    (alice, bob, joint)
+}
+
+/// Both wallets import each other's multisig info.
+async fn sync(alice: &XmrWallet, bob: &XmrWallet) {
+    let alice_info = export_multisig_sync(alice).await.expect("export alice");
+    let bob_info = export_multisig_sync(bob).await.expect("export bob");
+    import_multisig_sync(alice, &bob_info)
+        .await
+        .expect("import into alice");
+    import_multisig_sync(bob, &alice_info)
+        .await
+        .expect("import into bob");
+}
+
+#[tokio::test]
+async fn key_exchange_gives_both_parties_the_same_address() {
+    let alice_rpc = MockWalletRpc::start(100).await;
+    let bob_rpc = alice_rpc.start_peer().await;
+    let (alice, bob, joint) = setup(&alice_rpc, &bob_rpc).await;
+
+    Address::from_str(&joint).expect("joint address");
+    assert_eq!(alice_rpc.open_wallet().as_deref(), Some("alice_multisig"));
+    assert_eq!(bob_rpc.open_wallet().as_deref(), Some("bob_multisig"));
+    let status = bob.is_multisig().await.expect("status");
+    assert!(status.ready);
+    assert_eq!((status.threshold, status.total), (2, 2));
+    assert!(advance_multisig(&alice, "MultisigV1late", PASSWORD)
+        .await
+        .is_err());
+
+    let carol = XmrWallet::connect_with_config(alice_rpc.url(), retry())
+        .await
+        .expect("connect");
+    assert!(prepare_multisig_wallet(&carol, "carol_multisig", "")
+        .await
+        .is_err());
+}
+
+#[test]
+fn a_swap_keeps_the_lock_mechanism_it_was_recorded_with() {
+    let store = MemoryStore::default();
+    let multisig = LockMechanism::Multisig {
+        address: mock_address("joint"),
+    };
+    assert_eq!(
+        LockMechanism::load(&store, "swap-1").unwrap(),
+        LockMechanism::KeySum
+    );
+
+    multisig.record(&store, "swap-1").expect("record");
+    multisig
+        .record(&store, "swap-1")
+        .expect("same mechanism again");
+    assert_eq!(LockMechanism::load(&store, "swap-1").unwrap(), multisig);
+    let err = LockMechanism::KeySum
+        .record(&store, "swap-1")
+        .expect_err("switching mechanism")
+        .to_string();
+    assert!(err.contains("multisig lock"), "{err}");
+    let other = LockMechanism::Multisig {
+        address: mock_address("other"),
+    };
+    assert!(other.record(&store, "swap-1").is_err());
+
+    LockMechanism::KeySum
+        .record(&store, "swap-2")
+        .expect("record");
+    assert!(multisig.record(&store, "swap-2").is_err());
+}
+
+#[tokio::test]
+async fn lock_is_watched_and_swept_with_both_signatures() {
+    let alice_rpc = MockWalletRpc::start(100).await;
+    let bob_rpc = alice_rpc.start_peer().await;
+    let (alice, bob, joint) = setup(&alice_rpc, &bob_rpc).await;
+    let tx_id = alice_rpc.broadcast(&joint, LOCKED);
+
+    let daemon_url = serve_scripted(vec![
+        (
+            "\"get_info\"",
+            vec![
+                json_rpc(
+                    json!({ "status": "OK", "height": 100, "top_block_hash": "aa".repeat(32) }),
+                ),
+                json_rpc(
+                    json!({ "status": "OK", "height": 110, "top_block_hash": "aa".repeat(32) }),
+                ),
+            ],
+        ),
+        (
+            "get_block_header_by_height",
+            vec![json_rpc(json!({
+                "status": "OK",
+                "block_header": {
+                    "height": 100,
+                    "hash": "bb".repeat(32),
+                    "prev_hash": "cc".repeat(32),
+                    "depth": 10,
+                    "orphan_status": false,
+                    "timestamp": 1_700_000_000u64,
+                },
+            }))],
+        ),
+        (
+            "/get_transactions",
+            vec![json!({
+                "status": "OK",
+                "txs": [{ "tx_hash": tx_id, "in_pool": false, "block_height": 100 }],
+            })],
+        ),
+    ])
+    .await;
+    let daemon = XmrDaemon::connect_with_config(&daemon_url, retry())
+        .await
+        .expect("daemon");
+    let address = Address::from_str(&joint).expect("joint address");
+
+    let backend = MultisigLock::new(&bob);
+    let mut watcher = LockWatcher::new(WatcherConfig::default());
+    let first = watcher
+        .poll_for_lock(&backend, &daemon, &tx_id, None, &address, LOCKED)
+        .await
+        .expect("poll");
+    assert_eq!(first, Some(WatcherEvent::LockInPool { amount: LOCKED }));
+    alice_rpc.mine(UNLOCK_BLOCKS);
+    let second = watcher
+        .poll_for_lock(&backend, &daemon, &tx_id, None, &address, LOCKED)
+        .await
+        .expect("poll");
+    assert!(
+        matches!(second, Some(WatcherEvent::Confirmed { amount: LOCKED, .. })),
+        "{second:?}"
+    );
+
+    sync(&alice, &bob).await;
+    let destination = mock_address("multisig_destination");
+    let options = TransferOptions::default();
+    let proposed = propose_multisig_sweep(&alice, &destination, &options)
+        .await
+        .expect("propose");
+    assert!(submit_multisig_sweep(&alice, &proposed).await.is_err());
+
+    let signed = cosign_multisig_sweep(&bob, &proposed, &destination, &options)
+        .await
+        .expect("cosign");
+    let tx_hashes = submit_multisig_sweep(&bob, &signed).await.expect("submit");
+    assert_eq!(tx_hashes, proposed.tx_hashes);
+    alice_rpc.mine(1);
+    assert_eq!(alice_rpc.received_by(&destination), LOCKED - 10_000);
+}
+
+#[tokio::test]
+async fn lock_check_counts_only_the_lock_transaction() {
+    let alice_rpc = MockWalletRpc::start(100).await;
+    let bob_rpc = alice_rpc.start_peer().await;
+    let (_alice, bob, joint) = setup(&alice_rpc, &bob_rpc).await;
+    alice_rpc.receive(&joint, 7);
+    let tx_id = alice_rpc.broadcast(&joint, LOCKED);
+    let address = Address::from_str(&joint).expect("joint address");
+    // The multisig backend reads the wallet only; the daemon is never asked.
+    let daemon = XmrDaemon::connect_with_config("http://127.0.0.1:9", retry())
+        .await
+        .expect("daemon");
+    let backend = MultisigLock::new(&bob);
+
+    let pooled = backend
+        .check_lock(&daemon, &tx_id, None, &address, alice_rpc.height())
+        .await
+        .expect("check");
+    assert_eq!((pooled.received, pooled.in_pool), (LOCKED, true));
+    alice_rpc.mine(3);
+    let mined = backend
+        .check_lock(&daemon, &tx_id, None, &address, alice_rpc.height())
+        .await
+        .expect("check");
+    assert_eq!(
+        (mined.received, mined.confirmations, mined.in_pool),
+        (LOCKED, 3, false)
+    );
+}
+
+#[tokio::test]
+async fn cosigner_checks_the_sweep_before_signing() {
+    let alice_rpc = MockWalletRpc::start(100).await;
+    let bob_rpc = alice_rpc.start_peer().await;
+    let (alice, bob, joint) = setup(&alice_rpc, &bob_rpc).await;
+    alice_rpc.receive(&joint, LOCKED);
+    alice_rpc.mine(UNLOCK_BLOCKS);
+    let destination = mock_address("multisig_destination");
+    let options = TransferOptions::default();
+
+    alice.refresh(None).await.expect("refresh");
+    assert!(propose_multisig_sweep(&alice, &destination, &options)
+        .await
+        .is_err());
+
+    sync(&alice, &bob).await;
+    let proposed = propose_multisig_sweep(&alice, &destination, &options)
+        .await
+        .expect("propose");
+    let elsewhere = mock_address("somewhere_else");
+    assert!(cosign_multisig_sweep(&bob, &proposed, &elsewhere, &options)
+        .await
+        .is_err());
+    let capped = TransferOptions {
+        max_fee: Some(1),
+        ..TransferOptions::default()
+    };
+    assert!(
+        cosign_multisig_sweep(&bob, &proposed, &destination, &capped)
+            .await
+            .is_err()
+    );
+    assert!(submit_multisig_sweep(&bob, &proposed).await.is_err());
+    assert!(alice_rpc.relayed().is_empty());
+}
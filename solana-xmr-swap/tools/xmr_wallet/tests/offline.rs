@@ -0,0 +1,350 @@
+mod common;
+
+use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use common::{fast_retry, json_rpc, method, serve, serve_scripted};
+use monero::{Address, Network, PrivateKey, PublicKey};
+use serde_json::json;
+use xmr_wallet::{
+    export_cold_sync, export_sweep, import_cold_sync, relay_exported_sweep, sign_sweep, BuiltTx,
+    ColdSyncFile, ColdSyncImport, ColdSyncKind, KeyImageImport, PendingSweep, SignedKeyImage,
+    TransferOptions, UnlockWait, UnsignedSweep, XmrDaemon, XmrWallet,
+};
+
+fn destination() -> String {
+    let mut bytes = [0u8; 32];
+    bytes[0] = 9;
+    let key = PrivateKey::from_slice(&bytes).expect("private key");
+    let public = PublicKey::from_private_key(&key);
+    Address::standard(Network::Stagenet, public, public).to_string()
+}
+
+fn temp_path(label: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("xmr_offline_{label}_{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn built_tx(tx_hash: &str, tx_blob: &str) -> BuiltTx {
+    BuiltTx {
+        tx_hash: tx_hash.to_string(),
+        tx_key: "22".repeat(32),
+        amount: 990,
+        fee: 10,
+        tx_blob: tx_blob.to_string(),
+        tx_metadata: "cafe".to_string(),
+    }
+}
+
+#[test]
+fn cold_sync_file_round_trips() {
+    let path = temp_path("key_images");
+    let file = ColdSyncFile::KeyImages {
+        signed_key_images: vec![SignedKeyImage {
+            key_image: "aa".repeat(32),
+            signature: "bb".repeat(64),
+        }],
+    };
+    file.save(&path).expect("save");
+    assert!(file.save(&path).is_err(), "existing file is not replaced");
+
+    let loaded = ColdSyncFile::load(&path).expect("load");
+    assert_eq!(loaded, file);
+    assert_eq!(loaded.kind(), ColdSyncKind::KeyImages);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn exports_outputs_and_imports_key_images() {
+    let url = serve(vec![
+        (
+            method("export_outputs"),
+            json_rpc(json!({ "outputs_data_hex": "0102" })),
+        ),
+        (
+            method("import_key_images"),
+            json_rpc(json!({ "height": 1200, "spent": 0, "unspent": 990 })),
+        ),
+    ])
+    .await;
+    let wallet = XmrWallet::connect_with_config(&url, fast_retry())
+        .await
+        .expect("connect");
+
+    let outputs = export_cold_sync(&wallet, ColdSyncKind::Outputs)
+        .await
+        .expect("export outputs");
+    assert_eq!(
+        outputs,
+        ColdSyncFile::Outputs {
+            outputs_data_hex: "0102".to_string()
+        }
+    );
+
+    let images = ColdSyncFile::KeyImages {
+        signed_key_images: Vec::new(),
+    };
+    let imported = import_cold_sync(&wallet, &images)
+        .await
+        .expect("import key images");
+    assert_eq!(
+        imported,
+        ColdSyncImport::KeyImages(KeyImageImport {
+            height: 1200,
+            spent: 0,
+            unspent: 990,
+        })
+    );
+}
+
+fn key_images() -> ColdSyncFile {
+    ColdSyncFile::KeyImages {
+        signed_key_images: vec![SignedKeyImage {
+            key_image: "aa".repeat(32),
+            signature: "bb".repeat(64),
+        }],
+    }
+}
+
+fn unsigned_sweep() -> UnsignedSweep {
+    UnsignedSweep {
+        destination: destination(),
+        unsigned_txset: "0a0b".to_string(),
+    }
+}
+
+fn described_sweep(address: &str) -> serde_json::Value {
+    json_rpc(json!({
+        "desc": [{
+            "recipients": [{ "address": address, "amount": 990 }],
+            "change_amount": 0,
+            "change_address": "",
+            "fee": 10,
+        }],
+    }))
+}
+
+#[tokio::test]
+async fn export_sweep_builds_the_unsigned_set_after_importing_key_images() {
+    // No sign_transfer or relay_tx route: the view-only wallet cannot sign
+    // and the export must not broadcast anything.
+    let url = serve(vec![
+        (method("refresh"), json_rpc(json!({ "blocks_fetched": 0 }))),
+        (
+            method("import_key_images"),
+            json_rpc(json!({ "height": 1200, "spent": 0, "unspent": 1000 })),
+        ),
+        (method("get_height"), json_rpc(json!({ "height": 1300 }))),
+        (
+            method("get_balance"),
+            json_rpc(json!({ "balance": 1000, "unlocked_balance": 1000 })),
+        ),
+        (
+            method("sweep_all"),
+            json_rpc(json!({ "tx_hash_list": ["11".repeat(32)], "unsigned_txset": "0a0b" })),
+        ),
+    ])
+    .await;
+    let wallet = XmrWallet::connect_with_config(&url, fast_retry())
+        .await
+        .expect("connect");
+    let path = temp_path("unsigned");
+    let unlock = UnlockWait {
+        expected_amount: 1000,
+        poll_interval: Duration::from_millis(10),
+        timeout: Duration::from_secs(1),
+    };
+    let options = TransferOptions::default();
+
+    let sweep = export_sweep(
+        &wallet,
+        &key_images(),
+        &destination(),
+        &unlock,
+        &options,
+        &path,
+        |_| {},
+    )
+    .await
+    .expect("export");
+
+    assert_eq!(sweep, unsigned_sweep());
+    assert_eq!(UnsignedSweep::load(&path).expect("load"), sweep);
+    let again = export_sweep(
+        &wallet,
+        &key_images(),
+        &destination(),
+        &unlock,
+        &options,
+        &path,
+        |_| {},
+    )
+    .await;
+    assert!(again.is_err(), "a second export is refused");
+    let outputs = ColdSyncFile::Outputs {
+        outputs_data_hex: "0102".to_string(),
+    };
+    let _ = std::fs::remove_file(&path);
+    let wrong_kind = export_sweep(
+        &wallet,
+        &outputs,
+        &destination(),
+        &unlock,
+        &options,
+        &path,
+        |_| {},
+    )
+    .await;
+    assert!(wrong_kind.is_err(), "outputs are not key images");
+    assert!(!path.exists());
+}
+
+#[tokio::test]
+async fn sign_sweep_writes_raw_transactions_for_relay() {
+    let url = serve(vec![
+        (method("describe_transfer"), described_sweep(&destination())),
+        (
+            method("sign_transfer"),
+            json_rpc(json!({
+                "signed_txset": "0c0d",
+                "tx_hash_list": ["11".repeat(32)],
+                "tx_raw_list": ["beef"],
+                "tx_key_list": ["22".repeat(32)],
+            })),
+        ),
+    ])
+    .await;
+    let wallet = XmrWallet::connect_with_config(&url, fast_retry())
+        .await
+        .expect("connect");
+    let path = temp_path("signed");
+    let options = TransferOptions::default();
+
+    let sweep = sign_sweep(&wallet, &unsigned_sweep(), &destination(), &options, &path)
+        .await
+        .expect("sign");
+
+    assert_eq!(PendingSweep::load(&path).expect("load"), sweep);
+    assert_eq!(sweep.tx_hashes(), vec!["11".repeat(32)]);
+    assert_eq!(sweep.txs[0].tx_blob, "beef");
+    assert_eq!((sweep.amount(), sweep.fee()), (990, 10));
+    let again = sign_sweep(&wallet, &unsigned_sweep(), &destination(), &options, &path).await;
+    assert!(again.is_err(), "a second signature is refused");
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn sign_sweep_refuses_a_set_paying_another_address() {
+    // No sign_transfer route: a refused set is never signed.
+    let mut bytes = [0u8; 32];
+    bytes[0] = 4;
+    let other_key = PrivateKey::from_slice(&bytes).expect("private key");
+    let other_public = PublicKey::from_private_key(&other_key);
+    let other = Address::standard(Network::Stagenet, other_public, other_public).to_string();
+    let url = serve(vec![(method("describe_transfer"), described_sweep(&other))]).await;
+    let wallet = XmrWallet::connect_with_config(&url, fast_retry())
+        .await
+        .expect("connect");
+    let path = temp_path("refused");
+    let options = TransferOptions::default();
+
+    let err = sign_sweep(&wallet, &unsigned_sweep(), &destination(), &options, &path)
+        .await
+        .expect_err("foreign recipient")
+        .to_string();
+    assert!(err.contains("instead of the destination"), "{err}");
+    let err = sign_sweep(&wallet, &unsigned_sweep(), &other, &options, &path)
+        .await
+        .expect_err("destination differs from the unsigned sweep")
+        .to_string();
+    assert!(err.contains("unsigned sweep pays"), "{err}");
+    assert!(!path.exists());
+}
+
+#[tokio::test]
+async fn relay_skips_transactions_the_daemon_already_has() {
+    let known = "11".repeat(32);
+    let fresh = "33".repeat(32);
+    let url = serve(vec![
+        (
+            "/get_transactions",
+            json!({
+                "status": "OK",
+                "txs": [{ "tx_hash": known, "in_pool": true }],
+            }),
+        ),
+        // Resubmitting the known transaction would be reported as a double spend.
+        (
+            "\"tx_as_hex\":\"aaaa\"",
+            json!({ "status": "Failed", "reason": "double spend" }),
+        ),
+        ("\"tx_as_hex\":\"bbbb\"", json!({ "status": "OK" })),
+    ])
+    .await;
+    let daemon = XmrDaemon::connect_with_config(&url, fast_retry())
+        .await
+        .expect("connect");
+    let sweep = PendingSweep {
+        destination: destination(),
+        txs: vec![built_tx(&known, "aaaa"), built_tx(&fresh, "bbbb")],
+    };
+
+    let relayed = relay_exported_sweep(&daemon, &sweep).await.expect("relay");
+    assert_eq!(relayed, vec![known, fresh]);
+}
+
+#[tokio::test]
+async fn relay_counts_a_transaction_the_daemon_took_despite_an_error() {
+    let tx_hash = "55".repeat(32);
+    let url = serve_scripted(vec![
+        (
+            "/get_transactions",
+            vec![
+                json!({ "status": "OK", "txs": [] }),
+                json!({ "status": "OK", "txs": [{ "tx_hash": tx_hash, "in_pool": true }] }),
+            ],
+        ),
+        (
+            "/send_raw_transaction",
+            vec![json!({ "status": "Failed", "reason": "double spend" })],
+        ),
+    ])
+    .await;
+    let daemon = XmrDaemon::connect_with_config(&url, fast_retry())
+        .await
+        .expect("connect");
+    let sweep = PendingSweep {
+        destination: destination(),
+        txs: vec![built_tx(&tx_hash, "dddd")],
+    };
+
+    let relayed = relay_exported_sweep(&daemon, &sweep).await.expect("relay");
+    assert_eq!(relayed, vec![tx_hash]);
+}
+
+#[tokio::test]
+async fn relay_reports_daemon_rejection_reason() {
+    let url = serve(vec![
+        ("/get_transactions", json!({ "status": "OK", "txs": [] })),
+        (
+            "/send_raw_transaction",
+            json!({ "status": "Failed", "reason": "fee too low" }),
+        ),
+    ])
+    .await;
+    let daemon = XmrDaemon::connect_with_config(&url, fast_retry())
+        .await
+        .expect("connect");
+    let sweep = PendingSweep {
+        destination: destination(),
+        txs: vec![built_tx(&"44".repeat(32), "cccc")],
+    };
+
+    let err = relay_exported_sweep(&daemon, &sweep)
+        .await
+        .expect_err("rejected")
+        .to_string();
+    assert!(err.contains("fee too low"), "{err}");
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use common::{fast_retry, json_rpc, serve};
+use serde_json::{json, Value};
+use xmr_wallet::{
+    resolve_restore_height, LockWatcher, RestoreHeight, RestoreHeightStore, RestoreSource,
+    TxKeyCheck, WatcherConfig, WatcherState, WatcherStore, XmrDaemon,
+};
+
+const GENESIS_TIME: u64 = 1_700_000_000;
+
+#[derive(Default)]
+struct MemoryStore {
+    watchers: Mutex<HashMap<String, WatcherState>>,
+    heights: Mutex<HashMap<String, RestoreHeight>>,
+}
+
+impl WatcherStore for MemoryStore {
+    fn save_watcher_state(&self, swap_id: &str, state: &WatcherState) -> xmr_wallet::Result<()> {
+        self.watchers
+            .lock()
+            .unwrap()
+            .insert(swap_id.to_string(), state.clone());
+        Ok(())
+    }
+
+    fn load_watcher_state(&self, swap_id: &str) -> xmr_wallet::Result<Option<WatcherState>> {
+        Ok(self.watchers.lock().unwrap().get(swap_id).cloned())
+    }
+}
+
+impl RestoreHeightStore for MemoryStore {
+    fn save_restore_height(&self, swap_id: &str, height: &RestoreHeight) -> xmr_wallet::Result<()> {
+        self.heights
+            .lock()
+            .unwrap()
+            .insert(swap_id.to_string(), *height);
+        Ok(())
+    }
+
+    fn load_restore_height(&self, swap_id: &str) -> xmr_wallet::Result<Option<RestoreHeight>> {
+        Ok(self.heights.lock().unwrap().get(swap_id).copied())
+    }
+}
+
+/// A daemon whose chain has `blocks` blocks, two minutes apart.
+async fn chain_daemon(blocks: u64) -> XmrDaemon {
+    let mut routes: Vec<(&'static str, Value)> = vec![(
+        "\"get_info\"",
+        json_rpc(json!({
+            "status": "OK",
+            "height": blocks,
+            "top_block_hash": "aa".repeat(32),
+        })),
+    )];
+    for height in 0..blocks {
+        let needle: &'static str = Box::leak(format!("\"height\":{height}}}").into_boxed_str());
+        routes.push((
+            needle,
+            json_rpc(json!({
+                "status": "OK",
+                "block_header": {
+                    "height": height,
+                    "hash": "bb".repeat(32),
+                    "prev_hash": "cc".repeat(32),
+                    "depth": blocks - height,
+                    "orphan_status": false,
+                    "timestamp": GENESIS_TIME + height * 120,
+                },
+            })),
+        ));
+    }
+    let url = serve(routes).await;
+    XmrDaemon::connect_with_config(&url, fast_retry())
+        .await
+        .expect("daemon")
+}
+
+#[tokio::test]
+async fn restore_height_comes_from_watched_lock_height() {
+    let daemon = chain_daemon(1).await;
+    let store = MemoryStore::default();
+    let mut watcher = LockWatcher::new(WatcherConfig::default());
+    watcher.apply_tx_key_check(
+        TxKeyCheck {
+            received: 5_000,
+            confirmations: 12,
+            in_pool: false,
+        },
+        2_012,
+        5_000,
+    );
+    watcher.save(&store, "swap-1").expect("save watcher");
+
+    let height = resolve_restore_height(&store, &daemon, "swap-1", None, 100)
+        .await
+        .expect("restore height");
+    assert_eq!(
+        height,
+        RestoreHeight {
+            height: 1_900,
+            source: RestoreSource::LockHeight,
+        }
+    );
+    assert_eq!(
+        store.load_restore_height("swap-1").expect("load"),
+        Some(height)
+    );
+}
+
+#[tokio::test]
+async fn restore_height_is_estimated_from_lock_time() {
+    let daemon = chain_daemon(64).await;
+    let store = MemoryStore::default();
+
+    // Block 40 is mined at GENESIS_TIME + 4800; a lock sent a minute later
+    // can only be in block 41 or above.
+    let height = resolve_restore_height(&store, &daemon, "swap-2", Some(GENESIS_TIME + 4_860), 10)
+        .await
+        .expect("restore height");
+    assert_eq!(
+        height,
+        RestoreHeight {
+            height: 30,
+            source: RestoreSource::LockTimestamp,
+        }
+    );
+}
+
+#[tokio::test]
+async fn stored_restore_height_wins_and_unknown_lock_is_refused() {
+    let daemon = chain_daemon(1).await;
+    let store = MemoryStore::default();
+    assert!(resolve_restore_height(&store, &daemon, "swap-3", None, 10)
+        .await
+        .is_err());
+
+    store
+        .save_restore_height("swap-3", &RestoreHeight::manual(777))
+        .expect("save");
+    let height = resolve_restore_height(&store, &daemon, "swap-3", Some(GENESIS_TIME), 10)
+        .await
+        .expect("restore height");
+    assert_eq!(height, RestoreHeight::manual(777));
+}
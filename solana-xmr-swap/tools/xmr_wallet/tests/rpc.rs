@@ -1,6 +1,8 @@
 use std::env;
+use std::str::FromStr;
 
-use xmr_wallet::XmrWallet;
+use monero::Address;
+use xmr_wallet::{lock_xmr, LockReceipt, TransferOptions, XmrWallet};
 
 fn env_or_skip(key: &str) -> Option<String> {
     match env::var(key) {
@@ -52,3 +54,32 @@ async fn rpc_create_open_address_balance() {
 
     let _balance = wallet.get_balance().await.expect("get balance");
 }
+
+#[tokio::test]
+#[ignore = "requires a funded wallet open in monero-wallet-rpc"]
+async fn rpc_lock_persists_tx_key() {
+    let url = match env_or_skip("MONERO_WALLET_RPC_URL") {
+        Some(value) => value,
+        None => return,
+    };
+    let address = match env_or_skip("MONERO_LOCK_ADDRESS") {
+        Some(value) => Address::from_str(&value).expect("lock address"),
+        None => return,
+    };
+
+    let wallet = XmrWallet::connect(&url).await.expect("connect wallet RPC");
+    let mut path = env::temp_dir();
+    path.push(format!("xmr_lock_rpc_{}.json", std::process::id()));
+    let receipt = lock_xmr(
+        &wallet,
+        1_000_000,
+        &address,
+        &TransferOptions::default(),
+        &path,
+    )
+    .await
+    .expect("lock");
+    assert_eq!(receipt.tx_key.len(), 64);
+    assert_eq!(LockReceipt::load(&path).expect("load receipt"), receipt);
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,259 @@
+mod common;
+
+use common::{fast_retry, json_rpc, method, serve, serve_scripted};
+use monero::consensus::encode::deserialize;
+use monero::cryptonote::hash::Hashable;
+use monero::{Address, Network, PrivateKey, Transaction};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use xmr_wallet::{LockWatcher, SecretKey, ViewKeyScanner, WatcherConfig, WatcherEvent, XmrDaemon};
+
+#[derive(Deserialize)]
+struct VectorFile {
+    vectors: Vec<ScanVector>,
+}
+
+#[derive(Deserialize)]
+struct ScanVector {
+    tx_hex: String,
+    view_key: String,
+    spend_key: String,
+    subaddress: Subaddress,
+    output_index: usize,
+    amount: u64,
+}
+
+#[derive(Deserialize)]
+struct Subaddress {
+    major: u32,
+    minor: u32,
+}
+
+fn vector() -> ScanVector {
+    let manifest = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+    let path = manifest.join("../../test_vectors/monero_view_scan.json");
+    let raw = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("read vectors at {}", path.display()));
+    let mut file: VectorFile = serde_json::from_str(&raw).expect("parse vectors");
+    file.vectors.remove(0)
+}
+
+fn secret(hex_key: &str) -> SecretKey {
+    SecretKey::from_bytes(
+        hex::decode(hex_key)
+            .expect("hex")
+            .try_into()
+            .expect("32 bytes"),
+    )
+}
+
+fn address_of(vector: &ScanVector) -> Address {
+    let key = |hex_key: &str| {
+        monero::PublicKey::from_private_key(
+            &PrivateKey::from_slice(&hex::decode(hex_key).expect("hex")).expect("key"),
+        )
+    };
+    Address::standard(
+        Network::Mainnet,
+        key(&vector.spend_key),
+        key(&vector.view_key),
+    )
+}
+
+fn scanner_for(vector: &ScanVector) -> ViewKeyScanner {
+    ViewKeyScanner::new(&address_of(vector), &secret(&vector.view_key))
+        .expect("scanner")
+        .with_subaddresses(
+            0..vector.subaddress.major + 1,
+            0..vector.subaddress.minor + 1,
+        )
+}
+
+fn transaction(vector: &ScanVector) -> (Transaction, String) {
+    let tx: Transaction = deserialize(&hex::decode(&vector.tx_hex).expect("hex")).expect("tx");
+    let hash = hex::encode(tx.hash().as_bytes());
+    (tx, hash)
+}
+
+fn daemon_tx(vector: &ScanVector, tx_hash: &str, block_height: Option<u64>) -> Value {
+    json!({
+        "status": "OK",
+        "txs": [{
+            "tx_hash": tx_hash,
+            "as_hex": vector.tx_hex,
+            "in_pool": block_height.is_none(),
+            "block_height": block_height,
+        }],
+    })
+}
+
+fn daemon_info(height: u64) -> Value {
+    json_rpc(json!({
+        "status": "OK",
+        "height": height,
+        "target_height": 0,
+        "synchronized": true,
+        "top_block_hash": "aa".repeat(32),
+        "tx_pool_size": 0,
+        "nettype": "mainnet",
+    }))
+}
+
+#[test]
+fn decrypts_the_output_paying_the_address() {
+    let vector = vector();
+    let (tx, _) = transaction(&vector);
+
+    let found = scanner_for(&vector).scan_transaction(&tx).expect("scan");
+    assert_eq!(found, vec![(vector.output_index, vector.amount)]);
+
+    let main_only = ViewKeyScanner::new(&address_of(&vector), &secret(&vector.view_key))
+        .expect("scanner")
+        .scan_transaction(&tx)
+        .expect("scan");
+    assert!(main_only.is_empty());
+}
+
+#[test]
+fn rejects_a_view_key_for_another_address() {
+    let vector = vector();
+    assert!(ViewKeyScanner::new(&address_of(&vector), &SecretKey::random()).is_err());
+}
+
+#[tokio::test]
+async fn reports_confirmations_from_the_daemon() {
+    let vector = vector();
+    let (_, tx_hash) = transaction(&vector);
+    let url = serve_scripted(vec![(
+        "/get_transactions",
+        vec![
+            daemon_tx(&vector, &tx_hash, None),
+            daemon_tx(&vector, &tx_hash, Some(100)),
+        ],
+    )])
+    .await;
+    let daemon = XmrDaemon::connect_with_config(&url, fast_retry())
+        .await
+        .expect("daemon");
+    let scanner = scanner_for(&vector);
+
+    let pooled = scanner
+        .check_transaction(&daemon, &tx_hash, 100)
+        .await
+        .expect("check");
+    assert!(pooled.in_pool);
+    assert_eq!(pooled.received, vector.amount);
+    assert_eq!(pooled.confirmations, 0);
+
+    let mined = scanner
+        .check_transaction(&daemon, &tx_hash, 112)
+        .await
+        .expect("check");
+    assert!(!mined.in_pool);
+    assert_eq!(mined.received, vector.amount);
+    assert_eq!(mined.confirmations, 12);
+}
+
+#[tokio::test]
+async fn rejects_a_blob_that_does_not_match_its_hash() {
+    let vector = vector();
+    let other = "ab".repeat(32);
+    let url = serve(vec![(
+        "/get_transactions",
+        daemon_tx(&vector, &other, Some(100)),
+    )])
+    .await;
+    let daemon = XmrDaemon::connect_with_config(&url, fast_retry())
+        .await
+        .expect("daemon");
+
+    let result = scanner_for(&vector)
+        .check_transaction(&daemon, &other, 110)
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn scans_the_transactions_of_a_block() {
+    let vector = vector();
+    let (_, tx_hash) = transaction(&vector);
+    let url = serve(vec![
+        (
+            method("get_block"),
+            json_rpc(json!({
+                "status": "OK",
+                "block_header": {
+                    "height": 100,
+                    "hash": "bb".repeat(32),
+                    "prev_hash": "cc".repeat(32),
+                    "depth": 1,
+                    "orphan_status": false,
+                    "timestamp": 1_700_000_000u64,
+                },
+                "miner_tx_hash": "dd".repeat(32),
+                "tx_hashes": [tx_hash],
+            })),
+        ),
+        ("/get_transactions", daemon_tx(&vector, &tx_hash, Some(100))),
+    ])
+    .await;
+    let daemon = XmrDaemon::connect_with_config(&url, fast_retry())
+        .await
+        .expect("daemon");
+
+    let found = scanner_for(&vector)
+        .scan_block(&daemon, 100)
+        .await
+        .expect("scan block");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].tx_hash, tx_hash);
+    assert_eq!(found[0].index, vector.output_index);
+    assert_eq!(found[0].amount, vector.amount);
+    assert_eq!(found[0].block_height, Some(100));
+}
+
+#[tokio::test]
+async fn watcher_confirms_a_lock_with_the_scanner_backend() {
+    let vector = vector();
+    let (_, tx_hash) = transaction(&vector);
+    let url = serve(vec![
+        (method("get_info"), daemon_info(120)),
+        (
+            method("get_block_header_by_height"),
+            json_rpc(json!({
+                "status": "OK",
+                "block_header": {
+                    "height": 100,
+                    "hash": "bb".repeat(32),
+                    "prev_hash": "cc".repeat(32),
+                    "depth": 20,
+                    "orphan_status": false,
+                    "timestamp": 1_700_000_000u64,
+                },
+            })),
+        ),
+        ("/get_transactions", daemon_tx(&vector, &tx_hash, Some(100))),
+    ])
+    .await;
+    let daemon = XmrDaemon::connect_with_config(&url, fast_retry())
+        .await
+        .expect("daemon");
+    let address = address_of(&vector);
+    let scanner = scanner_for(&vector);
+
+    let mut watcher = LockWatcher::new(WatcherConfig::default());
+    let event = watcher
+        .poll_for_lock(&scanner, &daemon, &tx_hash, None, &address, vector.amount)
+        .await
+        .expect("poll");
+    assert!(
+        matches!(event, Some(WatcherEvent::Confirmed { amount, .. }) if amount == vector.amount),
+        "{event:?}"
+    );
+
+    let other = Address::standard(Network::Mainnet, address.public_spend, address.public_spend);
+    let result = watcher
+        .poll_for_lock(&scanner, &daemon, &tx_hash, None, &other, vector.amount)
+        .await;
+    assert!(result.is_err());
+}
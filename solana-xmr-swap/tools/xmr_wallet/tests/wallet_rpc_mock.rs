@@ -0,0 +1,420 @@
+//! End-to-end runs of the retry, lock, watcher and claim logic against the
+//! in-process wallet-rpc in `common::wallet_rpc`.
+
+mod common;
+
+use std::env;
+use std::str::FromStr;
+use std::time::Duration;
+
+use common::wallet_rpc::{mock_address, tx_proof, Fault, MockWalletRpc, UNLOCK_BLOCKS};
+use common::{json_rpc, serve_scripted};
+use curve25519_dalek::scalar::Scalar;
+use monero::{Address, Network};
+use serde_json::json;
+use xmr_wallet::{
+    derive_claim_keys, execute_claim_with_progress, lock_xmr, ClaimParams, ClaimProgress,
+    LockReceipt, LockWatcher, RetryConfig, TransferOptions, UnlockWait, WalletCleanup,
+    WatcherConfig, WatcherEvent, XmrDaemon, XmrWallet,
+};
+use zeroize::Zeroizing;
+
+const LOCKED: u64 = 1_000_000;
+const UNLOCK_MARGIN: u64 = UNLOCK_BLOCKS + 1;
+
+fn retry(timeout: Duration, max_retries: usize) -> RetryConfig {
+    RetryConfig {
+        timeout,
+        max_retries,
+        base_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(1),
+        jitter_ms: 0,
+    }
+}
+
+fn claim_params(label: &str, restore_height: u64) -> ClaimParams {
+    let journal = env::temp_dir().join(format!(
+        "xmr_mock_claim_{label}_{}.json",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&journal);
+    ClaimParams {
+        alice_partial: Scalar::from(11u64),
+        bob_partial: Scalar::from(13u64),
+        revealed_secret: Zeroizing::new([7u8; 32]),
+        view_key: None,
+        destination_address: mock_address("destination"),
+        network: Network::Stagenet,
+        wallet_filename: format!("claim_{label}"),
+        restore_height: Some(restore_height),
+        expected_hashlock: None,
+        journal_path: Some(journal),
+        unlock: UnlockWait {
+            expected_amount: LOCKED,
+            poll_interval: Duration::from_millis(5),
+            timeout: Duration::from_secs(5),
+        },
+        transfer: TransferOptions::default(),
+        wallet_password: None,
+        cleanup: WalletCleanup::KeepEncrypted,
+    }
+}
+
+/// Funds the claim address at the mock's current height.
+fn fund_lock(mock: &MockWalletRpc, params: &ClaimParams) -> String {
+    let keys = derive_claim_keys(params).expect("claim keys");
+    mock.receive(&keys.address.to_string(), LOCKED)
+}
+
+#[tokio::test]
+async fn failed_and_late_calls_are_retried() {
+    let mock = MockWalletRpc::start(100).await;
+    let wallet = XmrWallet::connect_with_config(mock.url(), retry(Duration::from_millis(200), 2))
+        .await
+        .expect("connect");
+    wallet
+        .create_wallet("retry_wallet", Some("pw"), "English")
+        .await
+        .expect("create wallet");
+
+    mock.fault("get_height", Fault::Error);
+    mock.fault("get_balance", Fault::Delay(Duration::from_secs(1)));
+    assert_eq!(wallet.get_height().await.expect("height"), 100);
+    assert_eq!(wallet.get_balance().await.expect("balance"), 0);
+    assert_eq!(mock.calls("get_height"), 2);
+    assert_eq!(mock.calls("get_balance"), 2);
+
+    let strict = XmrWallet::connect_with_config(mock.url(), retry(Duration::from_millis(200), 0))
+        .await
+        .expect("connect");
+    mock.fault("get_height", Fault::Error);
+    assert!(strict.get_height().await.is_err());
+}
+
+#[tokio::test]
+async fn calls_that_change_the_wallet_are_sent_once() {
+    let mock = MockWalletRpc::start(100).await;
+    let wallet = XmrWallet::connect_with_config(mock.url(), retry(Duration::from_millis(200), 2))
+        .await
+        .expect("connect");
+
+    mock.fault("create_wallet", Fault::Delay(Duration::from_secs(1)));
+    assert!(wallet
+        .create_wallet("once_wallet", Some("pw"), "English")
+        .await
+        .is_err());
+    assert_eq!(mock.calls("create_wallet"), 1);
+    assert!(mock.wallet_password("once_wallet").is_some());
+}
+
+#[tokio::test]
+async fn existing_view_wallet_is_reopened_only_for_its_address() {
+    let mock = MockWalletRpc::start(100).await;
+    let wallet = XmrWallet::connect_with_config(mock.url(), retry(Duration::from_millis(200), 0))
+        .await
+        .expect("connect");
+    let joint = mock_address("view_joint");
+    wallet
+        .generate_view_only([3u8; 32], &joint, "view_wallet", Some(90))
+        .await
+        .expect("create view wallet");
+
+    let err = wallet
+        .generate_view_only([3u8; 32], &joint, "view_wallet", Some(90))
+        .await
+        .expect_err("file exists");
+    assert!(err.is_wallet_exists(), "{err}");
+    wallet
+        .open_view_only("view_wallet", &joint)
+        .await
+        .expect("reopen");
+    assert_eq!(mock.open_wallet().as_deref(), Some("view_wallet"));
+
+    let err = wallet
+        .open_view_only("view_wallet", &mock_address("other"))
+        .await
+        .expect_err("another address");
+    assert!(err.to_string().contains("belongs to"), "{err}");
+    assert_eq!(mock.open_wallet(), None);
+}
+
+#[tokio::test]
+async fn claim_waits_for_unlock_sweeps_once_and_confirms_on_rerun() {
+    let mock = MockWalletRpc::start(100).await;
+    let params = claim_params("e2e", 90);
+    fund_lock(&mock, &params);
+    mock.mine_on_refresh(2);
+    let wallet = XmrWallet::connect_with_config(mock.url(), retry(Duration::from_secs(2), 2))
+        .await
+        .expect("connect");
+
+    let mut events = Vec::new();
+    let tx_hash = execute_claim_with_progress(&wallet, &params, |event| events.push(event))
+        .await
+        .expect("claim");
+
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, ClaimProgress::WaitingForUnlock { .. })));
+    assert_eq!(
+        mock.relayed(),
+        vec![(
+            tx_hash.clone(),
+            params.destination_address.clone(),
+            LOCKED - 10_000
+        )]
+    );
+    assert!(!mock
+        .wallet_password(&params.wallet_filename)
+        .expect("wallet created")
+        .is_empty());
+    assert_eq!(mock.open_wallet(), None);
+
+    mock.mine(1);
+    let mut events = Vec::new();
+    let rerun = execute_claim_with_progress(&wallet, &params, |event| events.push(event))
+        .await
+        .expect("rerun");
+    assert_eq!(rerun, tx_hash);
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, ClaimProgress::Confirmed { .. })));
+    assert_eq!(mock.calls("sweep_all"), 1);
+    assert_eq!(
+        mock.received_by(&params.destination_address),
+        LOCKED - 10_000
+    );
+    let _ = std::fs::remove_file(params.journal_path.as_ref().unwrap());
+}
+
+#[tokio::test]
+async fn relay_that_times_out_but_lands_is_not_repeated() {
+    let mock = MockWalletRpc::start(100).await;
+    let params = claim_params("late_relay", 90);
+    fund_lock(&mock, &params);
+    mock.mine(UNLOCK_MARGIN);
+    mock.fault("relay_tx", Fault::Delay(Duration::from_secs(1)));
+    let wallet = XmrWallet::connect_with_config(mock.url(), retry(Duration::from_millis(300), 2))
+        .await
+        .expect("connect");
+
+    let tx_hash = execute_claim_with_progress(&wallet, &params, |_| {})
+        .await
+        .expect("claim");
+
+    assert_eq!(mock.calls("relay_tx"), 1);
+    assert_eq!(mock.relayed().len(), 1);
+    assert_eq!(mock.relayed()[0].0, tx_hash);
+    let _ = std::fs::remove_file(params.journal_path.as_ref().unwrap());
+}
+
+/// Opens a funded wallet to send the lock from.
+async fn funded_wallet(mock: &MockWalletRpc, config: RetryConfig) -> XmrWallet {
+    let wallet = XmrWallet::connect_with_config(mock.url(), config)
+        .await
+        .expect("connect");
+    wallet
+        .create_wallet("locker", Some("pw"), "English")
+        .await
+        .expect("create wallet");
+    let address = wallet.get_primary_address().await.expect("address");
+    mock.receive(&address, 3 * LOCKED);
+    mock.mine(UNLOCK_MARGIN);
+    wallet.refresh(None).await.expect("refresh");
+    wallet
+}
+
+fn lock_receipt_path(label: &str) -> std::path::PathBuf {
+    let path = env::temp_dir().join(format!("xmr_mock_lock_{label}_{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[tokio::test]
+async fn lock_relay_that_times_out_keeps_the_tx_key_and_is_not_repeated() {
+    let mock = MockWalletRpc::start(100).await;
+    let wallet = funded_wallet(&mock, retry(Duration::from_millis(300), 2)).await;
+    let joint = Address::from_str(&mock_address("joint")).expect("joint address");
+    let path = lock_receipt_path("late_relay");
+    mock.fault("relay_tx", Fault::Delay(Duration::from_secs(1)));
+
+    let receipt = lock_xmr(&wallet, LOCKED, &joint, &TransferOptions::default(), &path)
+        .await
+        .expect("lock");
+    assert_eq!(LockReceipt::load(&path).expect("receipt"), receipt);
+
+    let rerun = lock_xmr(&wallet, LOCKED, &joint, &TransferOptions::default(), &path)
+        .await
+        .expect("rerun");
+    assert_eq!(rerun, receipt);
+    assert_eq!(mock.calls("transfer"), 1);
+    assert_eq!(mock.calls("relay_tx"), 1);
+    assert_eq!(
+        mock.relayed(),
+        vec![(receipt.tx_id.clone(), joint.to_string(), LOCKED)]
+    );
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn failed_lock_relay_is_retried_from_the_persisted_tx() {
+    let mock = MockWalletRpc::start(100).await;
+    let wallet = funded_wallet(&mock, retry(Duration::from_secs(2), 0)).await;
+    let joint = Address::from_str(&mock_address("joint")).expect("joint address");
+    let path = lock_receipt_path("failed_relay");
+    mock.fault("relay_tx", Fault::Error);
+
+    lock_xmr(&wallet, LOCKED, &joint, &TransferOptions::default(), &path)
+        .await
+        .expect_err("relay fails");
+    let stored = LockReceipt::load(&path).expect("receipt persisted before relay");
+    assert!(mock.relayed().is_empty());
+
+    let receipt = lock_xmr(&wallet, LOCKED, &joint, &TransferOptions::default(), &path)
+        .await
+        .expect("rerun relays");
+    assert_eq!(receipt, stored);
+    assert_eq!(mock.calls("transfer"), 1);
+    assert_eq!(
+        mock.relayed(),
+        vec![(stored.tx_id.clone(), joint.to_string(), LOCKED)]
+    );
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn restore_height_above_the_lock_never_sees_it() {
+    let mock = MockWalletRpc::start(100).await;
+    let mut params = claim_params("late_restore", 150);
+    params.unlock.timeout = Duration::from_millis(200);
+    fund_lock(&mock, &params);
+    mock.mine(60);
+    let wallet = XmrWallet::connect_with_config(mock.url(), retry(Duration::from_secs(2), 0))
+        .await
+        .expect("connect");
+
+    let err = execute_claim_with_progress(&wallet, &params, |_| {})
+        .await
+        .expect_err("lock below restore height");
+    assert!(err.to_string().contains("unlock"), "{err}");
+    assert_eq!(mock.calls("sweep_all"), 0);
+    let _ = std::fs::remove_file(params.journal_path.as_ref().unwrap());
+}
+
+#[tokio::test]
+async fn stale_wallet_under_the_claim_name_is_not_swept() {
+    let mock = MockWalletRpc::start(100).await;
+    let mut params = claim_params("stale", 90);
+    params.wallet_password = Some(Zeroizing::new("pw".to_string()));
+    fund_lock(&mock, &params);
+    let wallet = XmrWallet::connect_with_config(mock.url(), retry(Duration::from_secs(2), 0))
+        .await
+        .expect("connect");
+    wallet
+        .create_wallet(&params.wallet_filename, Some("pw"), "English")
+        .await
+        .expect("stale wallet");
+    wallet.close_wallet().await.expect("close");
+
+    let err = execute_claim_with_progress(&wallet, &params, |_| {})
+        .await
+        .expect_err("wallet of another address");
+    assert!(err.to_string().contains("belongs to"), "{err}");
+    assert_eq!(mock.calls("sweep_all"), 0);
+    assert_eq!(mock.open_wallet(), None);
+    let _ = std::fs::remove_file(params.journal_path.as_ref().unwrap());
+}
+
+#[tokio::test]
+async fn watcher_follows_lock_from_pool_to_confirmation() {
+    let mock = MockWalletRpc::start(100).await;
+    let joint = Address::from_str(&mock_address("joint")).expect("joint address");
+    let tx_id = mock.broadcast(&joint.to_string(), LOCKED);
+    let daemon_url = serve_scripted(vec![
+        (
+            "\"get_info\"",
+            vec![
+                json_rpc(
+                    json!({ "status": "OK", "height": 100, "top_block_hash": "aa".repeat(32) }),
+                ),
+                json_rpc(
+                    json!({ "status": "OK", "height": 110, "top_block_hash": "aa".repeat(32) }),
+                ),
+            ],
+        ),
+        (
+            "get_block_header_by_height",
+            vec![json_rpc(json!({
+                "status": "OK",
+                "block_header": {
+                    "height": 100,
+                    "hash": "bb".repeat(32),
+                    "prev_hash": "cc".repeat(32),
+                    "depth": 10,
+                    "orphan_status": false,
+                    "timestamp": 1_700_000_000u64,
+                },
+            }))],
+        ),
+        (
+            "/get_transactions",
+            vec![json!({
+                "status": "OK",
+                "txs": [{ "tx_hash": tx_id, "in_pool": false, "block_height": 100 }],
+            })],
+        ),
+    ])
+    .await;
+    let wallet = XmrWallet::connect_with_config(mock.url(), retry(Duration::from_secs(2), 0))
+        .await
+        .expect("connect");
+    let daemon = XmrDaemon::connect_with_config(&daemon_url, retry(Duration::from_secs(2), 0))
+        .await
+        .expect("daemon");
+    let tx_key = "33".repeat(32);
+
+    let mut watcher = LockWatcher::new(WatcherConfig::default());
+    let first = watcher
+        .poll_for_lock(&wallet, &daemon, &tx_id, Some(&tx_key), &joint, LOCKED)
+        .await
+        .expect("poll");
+    assert_eq!(first, Some(WatcherEvent::LockInPool { amount: LOCKED }));
+
+    mock.mine(10);
+    let second = watcher
+        .poll_for_lock(&wallet, &daemon, &tx_id, Some(&tx_key), &joint, LOCKED)
+        .await
+        .expect("poll");
+    assert_eq!(
+        second,
+        Some(WatcherEvent::Confirmed {
+            observed_height: 100,
+            confirmations: 10,
+            amount: LOCKED,
+        })
+    );
+}
+
+#[tokio::test]
+async fn tx_proof_stands_in_for_the_tx_key() {
+    let mock = MockWalletRpc::start(100).await;
+    let joint = mock_address("proof_joint");
+    let tx_id = mock.broadcast(&joint, LOCKED);
+    mock.mine(3);
+    let wallet = XmrWallet::connect_with_config(mock.url(), retry(Duration::from_secs(2), 0))
+        .await
+        .expect("connect");
+
+    let check = wallet
+        .check_tx_proof(&tx_id, &joint, "", &tx_proof(&tx_id))
+        .await
+        .expect("good proof");
+    assert_eq!((check.received, check.confirmations), (LOCKED, 3));
+    assert!(!check.in_pool);
+
+    let err = wallet
+        .check_tx_proof(&tx_id, &joint, "", "OutProofV2forged")
+        .await
+        .expect_err("bad proof");
+    assert!(err.to_string().contains("does not verify"), "{err}");
+}
@@ -1,4 +1,8 @@
-use xmr_wallet::{LockWatcher, WatcherConfig, WatcherEvent};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+
+use xmr_wallet::{ChainView, LockWatcher, TxKeyCheck, WatcherConfig, WatcherEvent, WatcherState};
 
 #[test]
 fn reports_no_lock_without_observation() {
@@ -14,7 +18,7 @@ fn waits_for_confirmations_then_confirms() {
         reorg_buffer: 5,
     };
     let mut watcher = LockWatcher::new(config);
-    watcher.observe_lock(50);
+    watcher.observe_lock(50, 1_000);
 
     let awaiting = watcher.evaluate(55);
     assert_eq!(
@@ -23,6 +27,7 @@ fn waits_for_confirmations_then_confirms() {
             observed_height: 50,
             current_height: 55,
             remaining: 5,
+            amount: 1_000,
         }
     );
 
@@ -32,6 +37,7 @@ fn waits_for_confirmations_then_confirms() {
         WatcherEvent::Confirmed {
             observed_height: 50,
             confirmations: 10,
+            amount: 1_000,
         }
     );
 }
@@ -43,7 +49,7 @@ fn detects_reorg_and_clears_lock() {
         reorg_buffer: 5,
     };
     let mut watcher = LockWatcher::new(config);
-    watcher.observe_lock(50);
+    watcher.observe_lock(50, 1_000);
     watcher.update_height(100);
 
     let reorg = watcher.update_height(90);
@@ -58,3 +64,169 @@ fn detects_reorg_and_clears_lock() {
     let after = watcher.evaluate(90);
     assert_eq!(after, WatcherEvent::NoLockObserved);
 }
+
+fn check(received: u64, confirmations: u64, in_pool: bool) -> TxKeyCheck {
+    TxKeyCheck {
+        received,
+        confirmations,
+        in_pool,
+    }
+}
+
+#[test]
+fn tx_key_check_reports_exact_amount_and_confirmations() {
+    let mut watcher = LockWatcher::new(WatcherConfig::default());
+    let event = watcher.apply_tx_key_check(check(1_500, 4, false), 104, 1_000);
+    assert_eq!(
+        event,
+        WatcherEvent::AwaitingConfirmations {
+            observed_height: 100,
+            current_height: 104,
+            remaining: 6,
+            amount: 1_500,
+        }
+    );
+
+    let event = watcher.apply_tx_key_check(check(1_500, 12, false), 112, 1_000);
+    assert_eq!(
+        event,
+        WatcherEvent::Confirmed {
+            observed_height: 100,
+            confirmations: 12,
+            amount: 1_500,
+        }
+    );
+}
+
+#[test]
+fn tx_key_check_rejects_underpayment() {
+    let mut watcher = LockWatcher::new(WatcherConfig::default());
+    let event = watcher.apply_tx_key_check(check(999, 20, false), 120, 1_000);
+    assert_eq!(
+        event,
+        WatcherEvent::Underpaid {
+            expected: 1_000,
+            received: 999,
+        }
+    );
+    assert_eq!(watcher.evaluate(120), WatcherEvent::NoLockObserved);
+}
+
+#[test]
+fn tx_key_check_reports_pool_transaction() {
+    let mut watcher = LockWatcher::new(WatcherConfig::default());
+    let event = watcher.apply_tx_key_check(check(1_000, 0, true), 120, 1_000);
+    assert_eq!(event, WatcherEvent::LockInPool { amount: 1_000 });
+}
+
+/// A chain whose blocks the test rewrites to simulate reorgs.
+struct ScriptedChain {
+    hashes: Mutex<HashMap<u64, [u8; 32]>>,
+}
+
+impl ScriptedChain {
+    fn new(tip: u64) -> Self {
+        let hashes = (0..=tip).map(|height| (height, block(height, 0))).collect();
+        Self {
+            hashes: Mutex::new(hashes),
+        }
+    }
+
+    fn replace(&self, from: u64, to: u64, fork: u8) {
+        let mut hashes = self.hashes.lock().unwrap();
+        for height in from..=to {
+            hashes.insert(height, block(height, fork));
+        }
+    }
+}
+
+impl ChainView for ScriptedChain {
+    fn block_hash(&self, height: u64) -> impl Future<Output = xmr_wallet::Result<[u8; 32]>> + Send {
+        let hash = self.hashes.lock().unwrap().get(&height).copied();
+        async move { hash.ok_or_else(|| anyhow::anyhow!("no block at {height}").into()) }
+    }
+}
+
+fn block(height: u64, fork: u8) -> [u8; 32] {
+    let mut hash = [fork; 32];
+    hash[..8].copy_from_slice(&height.to_le_bytes());
+    hash
+}
+
+#[tokio::test]
+async fn detects_same_height_reorg_of_lock_block() {
+    let chain = ScriptedChain::new(104);
+    let mut watcher = LockWatcher::new(WatcherConfig::default());
+    watcher.apply_tx_key_check(check(1_000, 5, false), 105, 1_000);
+    assert_eq!(watcher.check_block_hashes(&chain, 105).await.unwrap(), None);
+
+    // Replacing blocks above the lock leaves it in place.
+    chain.replace(103, 104, 1);
+    assert_eq!(watcher.check_block_hashes(&chain, 105).await.unwrap(), None);
+    assert!(matches!(
+        watcher.evaluate(105),
+        WatcherEvent::AwaitingConfirmations {
+            observed_height: 100,
+            ..
+        }
+    ));
+
+    // Same tip height, but the lock's block is orphaned.
+    chain.replace(100, 104, 2);
+    let event = watcher.check_block_hashes(&chain, 105).await.unwrap();
+    assert!(matches!(event, Some(WatcherEvent::ReorgDetected { .. })));
+    assert_eq!(watcher.evaluate(105), WatcherEvent::NoLockObserved);
+}
+
+#[tokio::test]
+async fn detects_deep_reorg_replacing_lock_block() {
+    let chain = ScriptedChain::new(130);
+    let mut watcher = LockWatcher::new(WatcherConfig::default());
+    watcher.update_height(131);
+    watcher.apply_tx_key_check(check(1_000, 31, false), 131, 1_000);
+    assert_eq!(watcher.check_block_hashes(&chain, 131).await.unwrap(), None);
+
+    // A longer fork from below the lock: no height regression at all.
+    chain.replace(95, 135, 3);
+    assert_eq!(watcher.update_height(136), None);
+    let event = watcher.check_block_hashes(&chain, 136).await.unwrap();
+    assert_eq!(
+        event,
+        Some(WatcherEvent::ReorgDetected {
+            previous_height: 136,
+            current_height: 136,
+        })
+    );
+}
+
+#[tokio::test]
+async fn resumed_watcher_keeps_progress_and_block_hashes() {
+    let chain = ScriptedChain::new(104);
+    let config = WatcherConfig::default();
+    let mut watcher = LockWatcher::new(config);
+    watcher.update_height(105);
+    watcher.apply_tx_key_check(check(1_000, 5, false), 105, 1_000);
+    watcher.check_block_hashes(&chain, 105).await.unwrap();
+
+    let json = serde_json::to_string(&watcher.state()).expect("serialize");
+    let state: WatcherState = serde_json::from_str(&json).expect("deserialize");
+    assert_eq!(state, watcher.state());
+    assert_eq!(state.lock_height(), Some(100));
+    assert_eq!(state.last_seen_height(), Some(105));
+
+    let mut resumed = LockWatcher::resume(config, state);
+    assert_eq!(
+        resumed.evaluate(107),
+        WatcherEvent::AwaitingConfirmations {
+            observed_height: 100,
+            current_height: 107,
+            remaining: 3,
+            amount: 1_000,
+        }
+    );
+
+    // The restored hashes still catch an orphaned lock block.
+    chain.replace(100, 104, 1);
+    let event = resumed.check_block_hashes(&chain, 105).await.unwrap();
+    assert!(matches!(event, Some(WatcherEvent::ReorgDetected { .. })));
+}
@@ -1,6 +1,12 @@
 use std::env;
+use std::str::FromStr;
 
-use xmr_wallet::{LockWatcher, WatcherConfig, XmrWallet};
+use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+use monero::{Address, Network};
+use xmr_wallet::{
+    combine_view_key_shares, create_key_share, derive_lock_address, open_view_only_wallet,
+    verify_key_share, LockWatcher, PublicKey, SecretKey, WatcherConfig, XmrDaemon, XmrWallet,
+};
 
 fn env_or_skip(key: &str) -> Option<String> {
     match env::var(key) {
@@ -14,14 +20,66 @@ fn env_or_skip(key: &str) -> Option<String> {
 
 #[tokio::test]
 #[ignore = "requires monero-wallet-rpc running locally"]
-async fn watcher_poll_uses_rpc() {
+async fn watcher_verifies_lock_with_tx_key() {
     let url = match env_or_skip("MONERO_WALLET_RPC_URL") {
         Some(value) => value,
         None => return,
     };
+    let daemon_url = match env_or_skip("MONEROD_RPC_URL") {
+        Some(value) => value,
+        None => return,
+    };
+    let (tx_id, tx_key, address) = match (
+        env_or_skip("MONERO_LOCK_TX_ID"),
+        env_or_skip("MONERO_LOCK_TX_KEY"),
+        env_or_skip("MONERO_LOCK_ADDRESS"),
+    ) {
+        (Some(tx_id), Some(tx_key), Some(address)) => (tx_id, tx_key, address),
+        _ => return,
+    };
+    let address = Address::from_str(&address).expect("lock address");
 
     let wallet = XmrWallet::connect(&url).await.expect("connect wallet RPC");
+    let daemon = XmrDaemon::connect(&daemon_url)
+        .await
+        .expect("connect daemon RPC");
     let mut watcher = LockWatcher::new(WatcherConfig::default());
-    let result = watcher.poll_for_lock(&wallet, 1).await;
-    assert!(result.is_ok());
+    let event = watcher
+        .poll_for_lock(&wallet, &daemon, &tx_id, Some(&tx_key), &address, 1)
+        .await
+        .expect("poll");
+    assert!(event.is_some());
+}
+
+#[tokio::test]
+#[ignore = "requires monero-wallet-rpc running locally"]
+async fn watcher_opens_view_only_wallet() {
+    let url = match env_or_skip("MONERO_WALLET_RPC_URL") {
+        Some(value) => value,
+        None => return,
+    };
+
+    let view_key =
+        combine_view_key_shares(&[SecretKey::random(), SecretKey::random()]).expect("view key");
+    let shares: Vec<_> = (0..2)
+        .map(|_| {
+            let share = create_key_share(&SecretKey::random(), b"swap-0001").expect("key share");
+            verify_key_share(&share, b"swap-0001").expect("verified share")
+        })
+        .collect();
+    let adaptor_point = PublicKey::from_compressed(
+        (ED25519_BASEPOINT_POINT * SecretKey::random().as_scalar())
+            .compress()
+            .to_bytes(),
+    )
+    .expect("adaptor point");
+    let address = derive_lock_address(&shares, &adaptor_point, &view_key, Network::Stagenet)
+        .expect("address");
+
+    let wallet = XmrWallet::connect(&url).await.expect("connect wallet RPC");
+    let filename = format!("lock_watch_{}", std::process::id());
+    open_view_only_wallet(&wallet, &address, &view_key, &filename, Some(0))
+        .await
+        .expect("open view-only wallet");
+    assert_eq!(wallet.get_balance().await.expect("balance"), 0);
 }